@@ -36,6 +36,37 @@ impl Aabb {
         Self { min: a, max: b }
     }
 
+    /// Returns the parameters at which the ray enters and leaves the slab
+    /// between the box's two faces on axis `a`
+    pub fn slab(&self, r: Ray, a: usize) -> (f32, f32) {
+        let inv_d = 1.0 / r.dir()[a];
+        let near = (self.min[a] - r.origin()[a]) * inv_d;
+        let far = (self.max[a] - r.origin()[a]) * inv_d;
+
+        if inv_d < 0.0 {
+            (far, near)
+        } else {
+            (near, far)
+        }
+    }
+
+    /// Clips the part of the ray between `t_min` and `t_max` to the box,
+    /// returning the parameters it starts and ends at. The ray misses the
+    /// box if the start isn't before the end.
+    pub fn clip(&self, r: Ray, t_min: f32, t_max: f32) -> (f32, f32) {
+        let (mut t0, mut t1) = (t_min, t_max);
+        for a in 0..3 {
+            let (near, far) = self.slab(r, a);
+
+            // Rays parallel to a slab they're inside give NaNs, which
+            // these ignore
+            t0 = f32::max(t0, near);
+            t1 = f32::min(t1, far);
+        }
+
+        (t0, t1)
+    }
+
     pub fn surrounding_box(box0: Self, box1: Self) -> Self {
         let small = Vector::new(
             f32::min(box0.min.x, box1.min.x),
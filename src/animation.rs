@@ -0,0 +1,216 @@
+//! Keyframe animation support for the scene format. Any numeric or vector
+//! property in a scene file can either be a plain value or a keyframe
+//! track, which is evaluated at a point in time (in seconds) when the
+//! scene is constructed.
+use crate::Vector;
+
+use serde::{Deserialize, Serialize};
+
+/// Types that can be linearly interpolated between two values
+pub trait Lerp {
+    fn lerp(a: Self, b: Self, t: f32) -> Self;
+}
+
+impl Lerp for f32 {
+    fn lerp(a: Self, b: Self, t: f32) -> Self {
+        a + (b - a) * t
+    }
+}
+
+impl Lerp for Vector {
+    fn lerp(a: Self, b: Self, t: f32) -> Self {
+        a + (b - a) * t
+    }
+}
+
+/// Types from the scene schema that can be animated. The value is the
+/// renderer-side type that the schema type converts into.
+pub trait Animatable {
+    type Value: Lerp;
+    fn value(&self) -> Self::Value;
+}
+
+impl Animatable for f32 {
+    type Value = f32;
+    fn value(&self) -> f32 {
+        *self
+    }
+}
+
+/// How to interpolate between a keyframe and the one that follows it
+#[derive(Debug, Copy, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Interpolation {
+    Linear,
+    Bezier,
+}
+
+impl Default for Interpolation {
+    fn default() -> Self {
+        Interpolation::Linear
+    }
+}
+
+/// The default easing curve for bezier keyframes (CSS `ease-in-out`)
+const DEFAULT_EASE: [f32; 4] = [0.42, 0.0, 0.58, 1.0];
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct Keyframe<T> {
+    pub time: f32,
+    pub value: T,
+    #[serde(default)]
+    pub interpolation: Interpolation,
+    /// The two inner control points `[x1, y1, x2, y2]` of the cubic
+    /// bezier easing curve used when the interpolation is `bezier`
+    pub ease: Option<[f32; 4]>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct Track<T> {
+    pub keyframes: Vec<Keyframe<T>>,
+}
+
+impl<T> Track<T> {
+    /// Makes sure that the track has keyframes, that they're in order and
+    /// that their easing curves only go forwards in time, which evaluating
+    /// it relies on
+    pub fn check(&self) -> Result<(), String> {
+        if self.keyframes.is_empty() {
+            return Err(String::from("Keyframe tracks must not be empty"));
+        }
+
+        for keyframe in &self.keyframes {
+            if let Some([x1, _, x2, _]) = keyframe.ease {
+                if !(0.0..=1.0).contains(&x1) || !(0.0..=1.0).contains(&x2) {
+                    return Err(format!(
+                        "Bezier ease x values must be between 0 and 1, but \
+                         got {} and {}",
+                        x1, x2
+                    ));
+                }
+            }
+        }
+
+        for pair in self.keyframes.windows(2) {
+            if !(pair[0].time <= pair[1].time) {
+                return Err(format!(
+                    "Keyframes must be sorted by time, but {} comes before {}",
+                    pair[0].time, pair[1].time
+                ));
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// A property which is either a constant or a keyframe track
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(untagged)]
+pub enum Animated<T> {
+    Static(T),
+    Track(Track<T>),
+}
+
+impl<T: Animatable> Animated<T> {
+    /// Evaluates the property at the given time. Times before the first
+    /// keyframe or after the last one hold the respective value.
+    pub fn at(&self, time: f32) -> T::Value {
+        let keyframes = match self {
+            Animated::Static(v) => return v.value(),
+            Animated::Track(track) => &track.keyframes,
+        };
+
+        let next = keyframes.iter().position(|k| k.time > time);
+
+        match next {
+            None => keyframes
+                .last()
+                .expect("Keyframe tracks must not be empty")
+                .value
+                .value(),
+            Some(0) => keyframes[0].value.value(),
+            Some(i) => {
+                let k0 = &keyframes[i - 1];
+                let k1 = &keyframes[i];
+                let s = (time - k0.time) / (k1.time - k0.time);
+
+                let s = match k0.interpolation {
+                    Interpolation::Linear => s,
+                    Interpolation::Bezier => {
+                        let [x1, y1, x2, y2] = k0.ease.unwrap_or(DEFAULT_EASE);
+                        cubic_bezier_ease(x1, y1, x2, y2, s)
+                    }
+                };
+
+                T::Value::lerp(k0.value.value(), k1.value.value(), s)
+            }
+        }
+    }
+
+    /// Whether the property can change over time
+    pub fn is_animated(&self) -> bool {
+        match self {
+            Animated::Static(_) => false,
+            Animated::Track(track) => track.keyframes.len() > 1,
+        }
+    }
+}
+
+/// Evaluates a cubic bezier easing curve with end points (0, 0) and
+/// (1, 1) and inner control points (x1, y1) and (x2, y2) at `x`.
+///
+/// The curve is parametric, so the parameter that produces `x` is found
+/// by bisection first (x is monotonic as long as x1 and x2 are in [0, 1]).
+fn cubic_bezier_ease(x1: f32, y1: f32, x2: f32, y2: f32, x: f32) -> f32 {
+    let bezier = |p1: f32, p2: f32, u: f32| {
+        let inv = 1.0 - u;
+        3.0 * inv * inv * u * p1 + 3.0 * inv * u * u * p2 + u * u * u
+    };
+
+    let mut lo = 0.0;
+    let mut hi = 1.0;
+    let mut u = x;
+
+    for _ in 0..32 {
+        let bx = bezier(x1, x2, u);
+        if f32::abs(bx - x) < 1e-6 {
+            break;
+        }
+
+        if bx < x {
+            lo = u;
+        } else {
+            hi = u;
+        }
+
+        u = 0.5 * (lo + hi);
+    }
+
+    bezier(y1, y2, u)
+}
+
+/// The interval of time (in seconds) that the camera shutter is open for
+#[derive(Debug, Copy, Clone)]
+pub struct Shutter {
+    pub open: f32,
+    pub close: f32,
+}
+
+impl Shutter {
+    /// The shutter interval for the given frame of an animation, where
+    /// `fraction` is the portion of the frame that the shutter is open
+    /// for (e.g. 0.5 for a 180 degree shutter)
+    pub fn for_frame(frame: usize, fps: f32, fraction: f32) -> Self {
+        let open = frame as f32 / fps;
+        Self {
+            open,
+            close: open + fraction / fps,
+        }
+    }
+
+    /// Whether the shutter is open for a non-zero amount of time
+    pub fn is_open(self) -> bool {
+        self.close > self.open
+    }
+}
@@ -0,0 +1,248 @@
+//! A medium filling all of space, such as haze or fog, which makes beams of
+//! light visible and fades distant objects. The density is either the same
+//! everywhere or falls off exponentially with height, like fog that settles
+//! near the ground.
+//!
+//! Rather than being an object in the scene, the atmosphere is handled by
+//! the renderer before each ray's surface hit. Scattering distances are
+//! sampled both in proportion to the fog's transmittance and with
+//! equiangular sampling towards a point on the light, which concentrates
+//! samples where the ray passes close to the light and keeps shafts of
+//! light from being noisy.
+use crate::{materials::Material, HitRecord, Ray, Vector};
+
+use rand::prelude::*;
+
+/// Below this distance between the ray and the light, equiangular sampling
+/// isn't used as its pdf becomes too peaked
+const MIN_LIGHT_DISTANCE: f32 = 1e-4;
+
+/// Below this vertical component of a ray's direction, exponential fog is
+/// treated as having a constant density along the ray
+const MIN_VERTICAL: f32 = 1e-5;
+
+#[derive(Debug)]
+pub struct Atmosphere {
+    /// The density at `base_height`
+    density: f32,
+    /// The height over which the density falls by a factor of e, or None
+    /// if the density is the same everywhere
+    falloff: Option<f32>,
+    base_height: f32,
+    /// The phase function, which is usually [`HenyeyGreenstein`]
+    ///
+    /// [`HenyeyGreenstein`]: ../materials/struct.HenyeyGreenstein.html
+    phase_function: Box<dyn Material>,
+}
+
+impl Atmosphere {
+    pub fn new(
+        density: f32,
+        falloff: Option<f32>,
+        base_height: f32,
+        phase_function: Box<dyn Material>,
+    ) -> Self {
+        Self {
+            density,
+            falloff,
+            base_height,
+            phase_function,
+        }
+    }
+
+    pub fn phase_function(&self) -> &Box<dyn Material> {
+        &self.phase_function
+    }
+
+    /// Decides whether a ray scatters in the atmosphere before `t_max`,
+    /// where it would hit a surface. `light` is a point on a light used for
+    /// equiangular sampling, if the scene has any lights.
+    ///
+    /// If the ray scatters, returns where it does along with a weight that
+    /// the light scattered there has to be multiplied by. Otherwise the ray
+    /// reaches the surface, and no weighting is needed since the chance of
+    /// reaching it is the fog's transmittance. A weight of zero means that
+    /// no light can reach the point, which may then be infinitely far away.
+    pub fn sample(
+        &self,
+        r: Ray,
+        t_min: f32,
+        t_max: f32,
+        light: Option<Vector>,
+    ) -> Option<(HitRecord, f32)> {
+        if self.density <= 0.0 {
+            return None;
+        }
+
+        // Distances along the ray are measured from its origin, in the
+        // same units as the scene
+        let length = r.dir().length();
+        let path = Path::new(self, r.origin(), r.dir() / length);
+        let (start, end) = (t_min * length, t_max * length);
+
+        let start_depth = path.optical_depth(start);
+        let depth = path.optical_depth(end) - start_depth;
+        let scatter_chance = 1.0 - f32::exp(-depth);
+        if !(random::<f32>() < scatter_chance) {
+            return None;
+        }
+
+        let equiangular =
+            light.and_then(|l| Equiangular::new(&path, l, start, end));
+        let distance = match &equiangular {
+            Some(equiangular) if random::<bool>() => equiangular.generate(),
+            _ => {
+                let target = -f32::ln(1.0 - random::<f32>() * scatter_chance);
+                path.distance(start_depth + target)
+            }
+        };
+        let distance = distance.max(start).min(end);
+
+        // The pdf of the distance, conditioned on the ray scattering
+        let density = path.density(distance);
+        let transmittance =
+            f32::exp(-(path.optical_depth(distance) - start_depth));
+        let free_flight = density * transmittance / scatter_chance;
+        let pdf = match &equiangular {
+            Some(equiangular) => {
+                0.5 * free_flight + 0.5 * equiangular.value(distance)
+            }
+            None => free_flight,
+        };
+
+        let hit_record = HitRecord::in_medium(r, distance / length);
+
+        // Equiangular sampling can pick distances so far along rays which
+        // escape the scene that no light gets there
+        let weight = if free_flight > 0.0 {
+            free_flight / pdf
+        } else {
+            0.0
+        };
+
+        Some((hit_record, weight))
+    }
+}
+
+/// A ray through the atmosphere, with a normalized direction
+struct Path {
+    /// The density at the ray's origin
+    density: f32,
+    /// How quickly the density falls with distance along the ray, which is
+    /// zero when it's the same everywhere
+    rate: f32,
+    origin: Vector,
+    dir: Vector,
+}
+
+impl Path {
+    fn new(atmosphere: &Atmosphere, origin: Vector, dir: Vector) -> Self {
+        match atmosphere.falloff {
+            Some(falloff) => {
+                let height = origin.y - atmosphere.base_height;
+                let rate = dir.y / falloff;
+
+                Self {
+                    density: atmosphere.density * f32::exp(-height / falloff),
+                    rate: if rate.abs() < MIN_VERTICAL { 0.0 } else { rate },
+                    origin,
+                    dir,
+                }
+            }
+            None => Self {
+                density: atmosphere.density,
+                rate: 0.0,
+                origin,
+                dir,
+            },
+        }
+    }
+
+    fn density(&self, distance: f32) -> f32 {
+        self.density * f32::exp(-self.rate * distance)
+    }
+
+    /// The integral of the density from the origin to a distance
+    fn optical_depth(&self, distance: f32) -> f32 {
+        if self.rate == 0.0 {
+            self.density * distance
+        } else if distance == f32::INFINITY {
+            // Rays heading upwards leave the fog behind, while those
+            // heading downwards go through ever thicker fog
+            if self.rate > 0.0 {
+                self.density / self.rate
+            } else {
+                f32::INFINITY
+            }
+        } else {
+            self.density * -f32::exp_m1(-self.rate * distance) / self.rate
+        }
+    }
+
+    /// The distance at which the optical depth from the origin reaches
+    /// `depth`, which is infinite if it never does
+    fn distance(&self, depth: f32) -> f32 {
+        if self.rate == 0.0 {
+            return depth / self.density;
+        }
+
+        let remaining = 1.0 - depth * self.rate / self.density;
+        if remaining <= 0.0 {
+            f32::INFINITY
+        } else {
+            -remaining.ln() / self.rate
+        }
+    }
+}
+
+/// Samples distances along a ray in proportion to the inverse square
+/// falloff of light from a point, ignoring the fog's transmittance. See
+/// Kulla and Fajardo's "Importance Sampling Techniques for Path Tracing in
+/// Participating Media".
+struct Equiangular {
+    /// The distance along the ray to its closest point to the light
+    closest: f32,
+    /// The distance between the light and the ray
+    height: f32,
+    /// The angles subtended at the light by the start and end of the ray
+    theta_start: f32,
+    theta_end: f32,
+}
+
+impl Equiangular {
+    fn new(path: &Path, light: Vector, start: f32, end: f32) -> Option<Self> {
+        let closest = Vector::dot(light - path.origin, path.dir);
+        let height = (path.origin + closest * path.dir - light).length();
+        if !(height > MIN_LIGHT_DISTANCE) {
+            return None;
+        }
+
+        let theta_start = f32::atan((start - closest) / height);
+        let theta_end = f32::atan((end - closest) / height);
+        if !(theta_end > theta_start) {
+            return None;
+        }
+
+        Some(Self {
+            closest,
+            height,
+            theta_start,
+            theta_end,
+        })
+    }
+
+    fn generate(&self) -> f32 {
+        let theta = self.theta_start
+            + random::<f32>() * (self.theta_end - self.theta_start);
+
+        self.closest + self.height * theta.tan()
+    }
+
+    fn value(&self, distance: f32) -> f32 {
+        let offset = distance - self.closest;
+
+        self.height
+            / ((self.theta_end - self.theta_start)
+                * (self.height * self.height + offset * offset))
+    }
+}
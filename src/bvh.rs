@@ -48,6 +48,8 @@ impl Hittable for Bvh {
 }
 
 impl Bvh {
+    /// Builds a BVH over the objects, which can't be empty. Every object
+    /// needs a bounding box, so unbounded objects have to be kept out.
     pub fn construct(
         objects: &mut Vec<Box<dyn Hittable>>,
         t0: f32,
@@ -1,3 +1,4 @@
+use crate::spline::Spline;
 use crate::util::random_in_unit_disk;
 use crate::{Ray, Vector};
 
@@ -7,16 +8,22 @@ use rand::prelude::*;
 
 #[derive(Debug, Clone)]
 pub struct Camera {
+    /// The camera's position and orientation, evenly spaced across the
+    /// shutter interval. A stationary camera only has one pose.
+    poses: Vec<Pose>,
+    lens_radius: f32,
+    t0: f32,
+    t1: f32,
+}
+
+#[derive(Debug, Copy, Clone)]
+struct Pose {
     lower_left_corner: Vector,
     horizontal: Vector,
     vertical: Vector,
     origin: Vector,
-    lens_radius: f32,
     u: Vector,
     v: Vector,
-    w: Vector,
-    t0: f32,
-    t1: f32,
 }
 
 pub struct CameraConstructor {
@@ -31,8 +38,44 @@ pub struct CameraConstructor {
     pub t1: f32,
 }
 
-impl Camera {
-    pub fn new(vals: CameraConstructor) -> Self {
+/// A path for the camera to fly along. The camera moves along the path at
+/// a constant speed, starting at `start` and reaching the end of the path
+/// `duration` seconds later.
+#[derive(Debug)]
+pub struct CameraPath {
+    pub path: Spline,
+    /// A path for the point the camera is looking at, which is traversed
+    /// over the same period of time
+    pub target: Option<Spline>,
+    /// Whether the camera should look in the direction of travel
+    pub look_along_tangent: bool,
+    pub start: f32,
+    pub duration: f32,
+}
+
+impl CameraPath {
+    /// Positions the camera for the given time. The look_at point is only
+    /// changed if there is a target path or the camera looks along the
+    /// tangent of the path.
+    pub fn apply(&self, time: f32, vals: &mut CameraConstructor) {
+        let fraction = if self.duration > 0.0 {
+            (time - self.start) / self.duration
+        } else {
+            1.0
+        };
+
+        vals.look_from = self.path.point(fraction);
+
+        if let Some(target) = &self.target {
+            vals.look_at = target.point(fraction);
+        } else if self.look_along_tangent {
+            vals.look_at = vals.look_from + self.path.tangent(fraction);
+        }
+    }
+}
+
+impl Pose {
+    fn new(vals: &CameraConstructor) -> Self {
         let theta = vals.vfov * f32::consts::PI / 180.0;
         let half_height = f32::tan(theta / 2.0);
         let half_width = vals.aspect_r * half_height;
@@ -44,7 +87,6 @@ impl Camera {
         Self {
             u,
             v,
-            w,
             lower_left_corner: vals.look_from
                 - half_width * vals.focus_dist * u
                 - half_height * vals.focus_dist * v
@@ -52,12 +94,48 @@ impl Camera {
             horizontal: 2.0 * half_width * vals.focus_dist * u,
             vertical: 2.0 * half_height * vals.focus_dist * v,
             origin: vals.look_from,
+        }
+    }
+
+    fn lerp(a: Self, b: Self, t: f32) -> Self {
+        let lerp = |a: Vector, b: Vector| a + (b - a) * t;
+
+        Self {
+            lower_left_corner: lerp(a.lower_left_corner, b.lower_left_corner),
+            horizontal: lerp(a.horizontal, b.horizontal),
+            vertical: lerp(a.vertical, b.vertical),
+            origin: lerp(a.origin, b.origin),
+            // The lens is sampled along u and v, which have to stay unit
+            // vectors for the defocus blur to keep its size
+            u: lerp(a.u, b.u).normalize(),
+            v: lerp(a.v, b.v).normalize(),
+        }
+    }
+}
+
+impl Camera {
+    pub fn new(vals: CameraConstructor) -> Self {
+        Self {
+            poses: vec![Pose::new(&vals)],
             lens_radius: vals.aperture / 2.0,
             t0: vals.t0,
             t1: vals.t1,
         }
     }
 
+    /// Creates a camera that moves during the shutter interval. The
+    /// settings should be evenly spaced in time from the first one's `t0`
+    /// to its `t1`, and rays are interpolated between the two settings
+    /// that surround the ray's time.
+    pub fn new_moving(vals: &[CameraConstructor]) -> Self {
+        Self {
+            poses: vals.iter().map(Pose::new).collect(),
+            lens_radius: vals[0].aperture / 2.0,
+            t0: vals[0].t0,
+            t1: vals[0].t1,
+        }
+    }
+
     pub fn default(aspect_r: f32) -> Self {
         let defaults = CameraConstructor {
             look_from: Vector::zeros(),
@@ -73,18 +151,32 @@ impl Camera {
 
         Self::new(defaults)
     }
+
+    fn pose(&self, time: f32) -> Pose {
+        if self.poses.len() == 1 || self.t1 <= self.t0 {
+            return self.poses[0];
+        }
+
+        let segments = self.poses.len() - 1;
+        let s = (time - self.t0) / (self.t1 - self.t0) * segments as f32;
+        let i = (s.max(0.0) as usize).min(segments - 1);
+
+        Pose::lerp(self.poses[i], self.poses[i + 1], s - i as f32)
+    }
 }
 
 impl Camera {
     pub fn get_ray(&self, u: f32, v: f32) -> Ray {
-        let rd = self.lens_radius * random_in_unit_disk();
-        let offset = self.u * rd.x + self.v * rd.y;
         let time = self.t0 + random::<f32>() * (self.t1 - self.t0);
+        let pose = self.pose(time);
+
+        let rd = self.lens_radius * random_in_unit_disk();
+        let offset = pose.u * rd.x + pose.v * rd.y;
 
         Ray::new(
-            self.origin + offset,
-            self.lower_left_corner + self.horizontal * u + self.vertical * v
-                - self.origin
+            pose.origin + offset,
+            pose.lower_left_corner + pose.horizontal * u + pose.vertical * v
+                - pose.origin
                 - offset,
             time,
         )
@@ -0,0 +1,228 @@
+//! Command line argument handling. The scene file and output file are
+//! positional (in that order) and the remaining options are flags.
+use crate::objects::{RotationAxis, TurntableView};
+use crate::progress::ProgressMode;
+
+use std::env;
+use std::ops::Range;
+
+const USAGE: &str = "usage: raytracer [scene.json|scene.gltf|scene.glb] \
+                     [output.ppm|output.y4m] \
+                     [--progress human|json] [--progress-file <path>] \
+                     [--frames a..b|a..=b] [--fps N] [--shutter fraction] \
+                     [--turntable N] [--turntable-mode camera|scene] \
+                     [--turntable-axis X|Y|Z] [--contact-sheet <path>] \
+                     [--set <property>=<json>]...";
+
+#[derive(Debug)]
+pub struct Options {
+    pub scene_file: String,
+    pub output_file: String,
+    pub progress: ProgressMode,
+    /// The frames of the animation to render, if rendering a sequence
+    pub frames: Option<Range<usize>>,
+    pub fps: f32,
+    /// The portion of each frame that the shutter is open for
+    pub shutter: f32,
+    pub turntable: Option<Turntable>,
+    /// Where to write an image tiling all of the turntable views
+    pub contact_sheet: Option<String>,
+    /// Scene properties to override, as (property, JSON value) pairs
+    pub overrides: Vec<(String, String)>,
+}
+
+/// Renders the scene from evenly spaced angles all the way around
+#[derive(Debug)]
+pub struct Turntable {
+    pub views: usize,
+    /// The axis to rotate the scene about, or None to orbit the camera
+    pub scene_axis: Option<RotationAxis>,
+}
+
+impl Turntable {
+    pub fn view(&self, view: usize) -> TurntableView {
+        let angle = 360.0 * view as f32 / self.views as f32;
+
+        match self.scene_axis {
+            Some(axis) => TurntableView::Scene { axis, angle },
+            None => TurntableView::Camera { angle },
+        }
+    }
+}
+
+impl Options {
+    pub fn from_args() -> Result<Self, String> {
+        let mut positional = Vec::new();
+        let mut progress = ProgressMode::Human;
+        let mut frames = None;
+        let mut fps = 24.0;
+        let mut shutter = 0.5;
+        let mut views = None;
+        let mut rotate_scene = false;
+        let mut axis = RotationAxis::Y;
+        let mut contact_sheet = None;
+        let mut overrides = Vec::new();
+
+        let mut args = env::args().skip(1);
+        while let Some(arg) = args.next() {
+            match arg.as_str() {
+                "--progress" => match flag_value(&arg, args.next())?.as_str() {
+                    "human" => progress = ProgressMode::Human,
+                    "json" => {
+                        if let ProgressMode::Human = progress {
+                            progress = ProgressMode::Json(None);
+                        }
+                    }
+                    other => {
+                        return Err(format!(
+                            "Unknown progress mode {}\n{}",
+                            other, USAGE
+                        ));
+                    }
+                },
+                "--progress-file" => {
+                    progress = ProgressMode::Json(Some(flag_value(
+                        &arg,
+                        args.next(),
+                    )?));
+                }
+                "--frames" => {
+                    frames =
+                        Some(parse_frames(&flag_value(&arg, args.next())?)?);
+                }
+                "--fps" => {
+                    fps = parse_number(&arg, &flag_value(&arg, args.next())?)?;
+                }
+                "--shutter" => {
+                    let value = flag_value(&arg, args.next())?;
+                    shutter = parse_number(&arg, &value)?;
+
+                    // The shutter can't stay open past the next frame
+                    if shutter > 1.0 {
+                        return Err(format!(
+                            "Invalid value for {}: {}",
+                            arg, value
+                        ));
+                    }
+                }
+                "--turntable" => {
+                    let value = flag_value(&arg, args.next())?;
+                    views = Some(
+                        value
+                            .parse::<usize>()
+                            .ok()
+                            .filter(|v| *v > 0)
+                            .ok_or_else(|| {
+                                format!("Invalid value for {}: {}", arg, value)
+                            })?,
+                    );
+                }
+                "--turntable-mode" => {
+                    match flag_value(&arg, args.next())?.as_str() {
+                        "camera" => rotate_scene = false,
+                        "scene" => rotate_scene = true,
+                        other => {
+                            return Err(format!(
+                                "Unknown turntable mode {}\n{}",
+                                other, USAGE
+                            ));
+                        }
+                    }
+                }
+                "--turntable-axis" => {
+                    axis = match flag_value(&arg, args.next())?.as_str() {
+                        "X" => RotationAxis::X,
+                        "Y" => RotationAxis::Y,
+                        "Z" => RotationAxis::Z,
+                        other => {
+                            return Err(format!(
+                                "Unknown turntable axis {}\n{}",
+                                other, USAGE
+                            ));
+                        }
+                    };
+                    rotate_scene = true;
+                }
+                "--contact-sheet" => {
+                    contact_sheet = Some(flag_value(&arg, args.next())?);
+                }
+                "--set" => {
+                    let value = flag_value(&arg, args.next())?;
+                    let mut split = value.splitn(2, '=');
+
+                    match (split.next(), split.next()) {
+                        (Some(property), Some(json)) => overrides
+                            .push((String::from(property), String::from(json))),
+                        _ => {
+                            return Err(format!(
+                                "Invalid value for {}: {}",
+                                arg, value
+                            ));
+                        }
+                    }
+                }
+                _ if arg.starts_with("--") => {
+                    return Err(format!("Unknown flag {}\n{}", arg, USAGE));
+                }
+                _ => positional.push(arg),
+            }
+        }
+
+        let mut positional = positional.into_iter();
+
+        Ok(Self {
+            scene_file: positional
+                .next()
+                .unwrap_or_else(|| String::from("./scenes/1.json")),
+            output_file: positional
+                .next()
+                .unwrap_or_else(|| String::from("./out/image.ppm")),
+            progress,
+            frames,
+            fps,
+            shutter,
+            turntable: views.map(|views| Turntable {
+                views,
+                scene_axis: if rotate_scene { Some(axis) } else { None },
+            }),
+            contact_sheet,
+            overrides,
+        })
+    }
+}
+
+fn flag_value(flag: &str, value: Option<String>) -> Result<String, String> {
+    value.ok_or_else(|| format!("Missing value for {}\n{}", flag, USAGE))
+}
+
+fn parse_number(flag: &str, value: &str) -> Result<f32, String> {
+    value
+        .parse::<f32>()
+        .ok()
+        .filter(|v| *v > 0.0)
+        .ok_or_else(|| format!("Invalid value for {}: {}", flag, value))
+}
+
+/// Parses a frame range in Rust's range syntax, either `a..b` (exclusive)
+/// or `a..=b` (inclusive)
+fn parse_frames(value: &str) -> Result<Range<usize>, String> {
+    let err = || format!("Invalid frame range {}\n{}", value, USAGE);
+
+    let (start, end, inclusive) = if let Some(i) = value.find("..=") {
+        (&value[..i], &value[i + 3..], true)
+    } else if let Some(i) = value.find("..") {
+        (&value[..i], &value[i + 2..], false)
+    } else {
+        return Err(err());
+    };
+
+    let start = start.parse::<usize>().map_err(|_| err())?;
+    let end = end.parse::<usize>().map_err(|_| err())?;
+    let end = if inclusive { end + 1 } else { end };
+
+    if end <= start {
+        return Err(err());
+    }
+
+    Ok(start..end)
+}
@@ -1,6 +1,7 @@
 use std::error::Error;
 use std::fs::File;
 use std::io::prelude::*;
+use std::io::BufWriter;
 use std::path::Path;
 
 pub struct Pixel {
@@ -12,8 +13,8 @@ pub struct Pixel {
 }
 
 /// Writes a 2 dimensional vector of Pixels to a P6 PPM file
-pub fn gen_ppm(image: Vec<Vec<Pixel>>, outfile: String) -> Result<(), String> {
-    let path = Path::new(&outfile);
+pub fn gen_ppm(image: &[Vec<Pixel>], outfile: &str) -> Result<(), String> {
+    let path = Path::new(outfile);
     let display = path.display();
     let height = image.len();
 
@@ -45,3 +46,171 @@ pub fn gen_ppm(image: Vec<Vec<Pixel>>, outfile: String) -> Result<(), String> {
             })
         })
 }
+
+/// Tiles several images of the same size into a single image, filling
+/// the grid left to right and top to bottom. The grid is as close to
+/// square as possible and any leftover cells are black.
+pub fn contact_sheet(images: &[Vec<Vec<Pixel>>]) -> Vec<Vec<Pixel>> {
+    let height = images[0].len();
+    let width = images[0][0].len();
+
+    let cols = (images.len() as f32).sqrt().ceil() as usize;
+    let rows = (images.len() + cols - 1) / cols;
+
+    let sheet_width = cols * width;
+    let sheet_height = rows * height;
+
+    (0..sheet_height)
+        .map(|y| {
+            (0..sheet_width)
+                .map(|x| {
+                    let (col, row) = (x / width, y / height);
+                    let (r, g, b) = images
+                        .get(row * cols + col)
+                        .map(|img| {
+                            let p = &img[y % height][x % width];
+                            (p.r, p.g, p.b)
+                        })
+                        .unwrap_or((0, 0, 0));
+
+                    Pixel {
+                        r,
+                        g,
+                        b,
+                        x,
+                        y: sheet_height - 1 - y,
+                    }
+                })
+                .collect()
+        })
+        .collect()
+}
+
+/// Streams frames into an uncompressed YUV4MPEG2 video. Frames are
+/// converted to limited range 4:2:0 Y'CbCr using the BT.709 coefficients,
+/// with each chroma sample being the average of a 2x2 block of pixels.
+pub struct Y4mWriter {
+    file: BufWriter<File>,
+    path: String,
+    width: usize,
+    height: usize,
+}
+
+impl Y4mWriter {
+    /// Creates the file and writes the stream header
+    pub fn create(
+        outfile: &str,
+        width: usize,
+        height: usize,
+        fps: f32,
+    ) -> Result<Self, String> {
+        let file = File::create(outfile)
+            .map_err(|why| format!("Couldn't create {}: {}", outfile, why))?;
+
+        let (num, den) = frame_rate(fps);
+        let header = format!(
+            "YUV4MPEG2 W{} H{} F{}:{} Ip A1:1 C420jpeg XCOLORRANGE=LIMITED\n",
+            width, height, num, den
+        );
+
+        let mut writer = Self {
+            file: BufWriter::new(file),
+            path: String::from(outfile),
+            width,
+            height,
+        };
+
+        writer.write(header.as_bytes())?;
+        Ok(writer)
+    }
+
+    pub fn write_frame(&mut self, image: &[Vec<Pixel>]) -> Result<(), String> {
+        assert!(image.len() == self.height);
+
+        let (width, height) = (self.width, self.height);
+        let (chroma_width, chroma_height) = ((width + 1) / 2, (height + 1) / 2);
+
+        let mut frame = Vec::with_capacity(
+            width * height + 2 * chroma_width * chroma_height + 6,
+        );
+        frame.extend_from_slice(b"FRAME\n");
+
+        for row in image {
+            assert!(row.len() == width);
+            frame.extend(row.iter().map(|p| {
+                let (y, _, _) = bt709(p);
+                quantize(16.0 + 219.0 * y)
+            }));
+        }
+
+        let mut cb = Vec::with_capacity(chroma_width * chroma_height);
+        let mut cr = Vec::with_capacity(chroma_width * chroma_height);
+
+        for cy in 0..chroma_height {
+            for cx in 0..chroma_width {
+                let (mut sum_b, mut sum_r, mut n) = (0.0, 0.0, 0.0);
+
+                for y in 2 * cy..(2 * cy + 2).min(height) {
+                    for x in 2 * cx..(2 * cx + 2).min(width) {
+                        let (_, pb, pr) = bt709(&image[y][x]);
+                        sum_b += pb;
+                        sum_r += pr;
+                        n += 1.0;
+                    }
+                }
+
+                cb.push(quantize(128.0 + 224.0 * sum_b / n));
+                cr.push(quantize(128.0 + 224.0 * sum_r / n));
+            }
+        }
+
+        frame.extend(cb);
+        frame.extend(cr);
+
+        self.write(&frame)?;
+        self.file
+            .flush()
+            .map_err(|why| format!("Couldn't write {}: {}", self.path, why))
+    }
+
+    pub fn path(&self) -> &str {
+        &self.path
+    }
+
+    fn write(&mut self, bytes: &[u8]) -> Result<(), String> {
+        self.file
+            .write_all(bytes)
+            .map_err(|why| format!("Couldn't write {}: {}", self.path, why))
+    }
+}
+
+/// Converts a gamma encoded pixel to normalized Y' in [0, 1] and Pb, Pr
+/// in [-0.5, 0.5]
+fn bt709(pixel: &Pixel) -> (f32, f32, f32) {
+    let r = pixel.r as f32 / 255.0;
+    let g = pixel.g as f32 / 255.0;
+    let b = pixel.b as f32 / 255.0;
+
+    let y = 0.2126 * r + 0.7152 * g + 0.0722 * b;
+    (y, (b - y) / 1.8556, (r - y) / 1.5748)
+}
+
+fn quantize(value: f32) -> u8 {
+    value.round().max(0.0).min(255.0) as u8
+}
+
+/// Expresses a frame rate as a reduced fraction, keeping 3 decimal places
+/// so rates like 29.97 survive
+fn frame_rate(fps: f32) -> (u64, u64) {
+    let num = (fps * 1000.0).round() as u64;
+    let den = 1000;
+
+    let (mut a, mut b) = (num, den);
+    while b != 0 {
+        let t = a % b;
+        a = b;
+        b = t;
+    }
+
+    (num / a, den / a)
+}
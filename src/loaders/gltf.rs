@@ -0,0 +1,930 @@
+//! glTF 2.0 loading from either .gltf files or .glb binaries. Every mesh
+//! in the default scene is baked into world space with its node's
+//! transform, so a whole file becomes a single [`MeshData`] with a group
+//! for each node. Metallic-roughness materials are approximated with the
+//! renderer's own materials.
+//!
+//! [`MeshData`]: struct.MeshData.html
+use super::png::decode_png;
+use super::{Face, MeshData};
+use crate::materials::{Dielectric, DiffuseLight, Lambertian, Material, Metal};
+use crate::matrix::Matrix4;
+use crate::quaternion::Quaternion;
+use crate::textures::{ConstantTexture, ImageTexture, Texture};
+use crate::Vector;
+
+use serde::Deserialize;
+use std::collections::{HashMap, HashSet};
+use std::f32;
+use std::fs;
+use std::path::Path;
+use std::sync::Arc;
+
+const GLB_MAGIC: u32 = 0x4654_6c67;
+const GLB_JSON_CHUNK: u32 = 0x4e4f_534a;
+const GLB_BIN_CHUNK: u32 = 0x004e_4942;
+
+const MODE_TRIANGLES: u32 = 4;
+const MODE_TRIANGLE_STRIP: u32 = 5;
+const MODE_TRIANGLE_FAN: u32 = 6;
+
+/// The contents of a glTF file that the renderer can use
+#[derive(Debug)]
+pub struct GltfScene {
+    pub mesh: MeshData,
+    /// The perspective cameras in the scene, in the order they're found
+    pub cameras: Vec<GltfCamera>,
+}
+
+#[derive(Debug, Copy, Clone)]
+pub struct GltfCamera {
+    pub look_from: Vector,
+    pub look_at: Vector,
+    pub vup: Vector,
+    /// The vertical field of view in degrees
+    pub vfov: f32,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct Document {
+    #[serde(default)]
+    accessors: Vec<Accessor>,
+    #[serde(default)]
+    buffers: Vec<Buffer>,
+    #[serde(default)]
+    buffer_views: Vec<BufferView>,
+    #[serde(default)]
+    cameras: Vec<CameraDef>,
+    #[serde(default)]
+    images: Vec<ImageDef>,
+    #[serde(default)]
+    materials: Vec<MaterialDef>,
+    #[serde(default)]
+    meshes: Vec<MeshDef>,
+    #[serde(default)]
+    nodes: Vec<Node>,
+    scene: Option<usize>,
+    #[serde(default)]
+    scenes: Vec<SceneDef>,
+    #[serde(default)]
+    textures: Vec<TextureDef>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct Accessor {
+    buffer_view: Option<usize>,
+    #[serde(default)]
+    byte_offset: usize,
+    component_type: u32,
+    #[serde(default)]
+    normalized: bool,
+    count: usize,
+    #[serde(rename = "type")]
+    kind: String,
+    sparse: Option<serde_json::Value>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct Buffer {
+    uri: Option<String>,
+    byte_length: usize,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct BufferView {
+    buffer: usize,
+    #[serde(default)]
+    byte_offset: usize,
+    byte_length: usize,
+    byte_stride: Option<usize>,
+}
+
+#[derive(Debug, Deserialize)]
+struct CameraDef {
+    perspective: Option<Perspective>,
+}
+
+#[derive(Debug, Deserialize)]
+struct Perspective {
+    yfov: f32,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct ImageDef {
+    uri: Option<String>,
+    buffer_view: Option<usize>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct MaterialDef {
+    pbr_metallic_roughness: Option<Pbr>,
+    emissive_factor: Option<[f32; 3]>,
+    alpha_mode: Option<String>,
+    #[serde(default)]
+    extensions: MaterialExtensions,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct Pbr {
+    base_color_factor: Option<[f32; 4]>,
+    base_color_texture: Option<TextureRef>,
+    metallic_factor: Option<f32>,
+    roughness_factor: Option<f32>,
+}
+
+#[derive(Debug, Deserialize)]
+struct TextureRef {
+    index: usize,
+}
+
+#[derive(Debug, Default, Deserialize)]
+struct MaterialExtensions {
+    #[serde(rename = "KHR_materials_transmission")]
+    transmission: Option<Transmission>,
+    #[serde(rename = "KHR_materials_ior")]
+    ior: Option<Ior>,
+    #[serde(rename = "KHR_materials_emissive_strength")]
+    emissive_strength: Option<EmissiveStrength>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct Transmission {
+    transmission_factor: Option<f32>,
+}
+
+#[derive(Debug, Deserialize)]
+struct Ior {
+    ior: Option<f32>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct EmissiveStrength {
+    emissive_strength: Option<f32>,
+}
+
+#[derive(Debug, Deserialize)]
+struct MeshDef {
+    primitives: Vec<Primitive>,
+}
+
+#[derive(Debug, Deserialize)]
+struct Primitive {
+    attributes: HashMap<String, usize>,
+    indices: Option<usize>,
+    material: Option<usize>,
+    mode: Option<u32>,
+}
+
+#[derive(Debug, Deserialize)]
+struct Node {
+    name: Option<String>,
+    #[serde(default)]
+    children: Vec<usize>,
+    mesh: Option<usize>,
+    camera: Option<usize>,
+    matrix: Option<Vec<f32>>,
+    translation: Option<[f32; 3]>,
+    rotation: Option<[f32; 4]>,
+    scale: Option<[f32; 3]>,
+}
+
+#[derive(Debug, Deserialize)]
+struct SceneDef {
+    #[serde(default)]
+    nodes: Vec<usize>,
+}
+
+#[derive(Debug, Deserialize)]
+struct TextureDef {
+    source: Option<usize>,
+}
+
+/// A decoded image in linear color, shared by the textures that use it
+#[derive(Debug, Clone)]
+struct Image {
+    width: usize,
+    height: usize,
+    pixels: Arc<Vec<Vector>>,
+}
+
+/// A parsed file along with the contents of its buffers
+struct Loader<'a> {
+    doc: Document,
+    buffers: Vec<Vec<u8>>,
+    dir: &'a Path,
+    images: HashMap<usize, Image>,
+    scene: GltfScene,
+    /// Faces of primitives without normals, which are computed afterwards
+    faces_without_normals: Vec<usize>,
+}
+
+pub fn load_gltf(path: &Path) -> Result<GltfScene, String> {
+    let bytes = fs::read(path)
+        .map_err(|e| format!("Failed to read {}: {}", path.display(), e))?;
+    let dir = path.parent().unwrap_or_else(|| Path::new("."));
+
+    load(&bytes, dir).map_err(|e| format!("{}: {}", path.display(), e))
+}
+
+fn load(bytes: &[u8], dir: &Path) -> Result<GltfScene, String> {
+    let (json, bin) = if bytes.starts_with(b"glTF") {
+        split_glb(bytes)?
+    } else {
+        (bytes, None)
+    };
+
+    let doc: Document = serde_json::from_slice(json)
+        .map_err(|e| format!("Failed to parse JSON: {}", e))?;
+    let buffers = doc
+        .buffers
+        .iter()
+        .enumerate()
+        .map(|(i, buffer)| load_buffer(buffer, i, bin, dir))
+        .collect::<Result<_, _>>()?;
+
+    let mut loader = Loader {
+        doc,
+        buffers,
+        dir,
+        images: HashMap::new(),
+        scene: GltfScene {
+            mesh: MeshData::default(),
+            cameras: Vec::new(),
+        },
+        faces_without_normals: Vec::new(),
+    };
+
+    for i in 0..loader.doc.materials.len() {
+        let material = loader.material(i)?;
+        loader.scene.mesh.materials.push(material);
+    }
+
+    let mut visited = HashSet::new();
+    for node in loader.root_nodes() {
+        loader.add_node(node, Matrix4::identity(), &mut visited)?;
+    }
+
+    let faces = std::mem::replace(&mut loader.faces_without_normals, vec![]);
+    loader.scene.mesh.add_vertex_normals(&faces);
+
+    Ok(loader.scene)
+}
+
+/// Splits a binary glTF file into its JSON and binary chunks
+fn split_glb(bytes: &[u8]) -> Result<(&[u8], Option<&[u8]>), String> {
+    let int = |pos: usize| {
+        bytes
+            .get(pos..pos + 4)
+            .map(|b| u32::from_le_bytes([b[0], b[1], b[2], b[3]]))
+            .ok_or("Truncated GLB file")
+    };
+
+    if int(0)? != GLB_MAGIC || int(4)? != 2 {
+        return Err(String::from("Only version 2 GLB files are supported"));
+    }
+
+    let mut json = None;
+    let mut bin = None;
+    let mut pos = 12;
+
+    while pos + 8 <= bytes.len() {
+        let len = int(pos)? as usize;
+        let kind = int(pos + 4)?;
+        let data = bytes
+            .get(pos + 8..pos + 8 + len)
+            .ok_or("Truncated GLB chunk")?;
+
+        match kind {
+            GLB_JSON_CHUNK => json = Some(data),
+            GLB_BIN_CHUNK => bin = Some(data),
+            _ => {}
+        }
+
+        pos += 8 + len;
+    }
+
+    Ok((json.ok_or("Missing JSON chunk")?, bin))
+}
+
+fn load_buffer(
+    buffer: &Buffer,
+    index: usize,
+    bin: Option<&[u8]>,
+    dir: &Path,
+) -> Result<Vec<u8>, String> {
+    let data = match (&buffer.uri, bin) {
+        (Some(uri), _) => load_uri(uri, dir)?,
+        // Only the first buffer of a GLB file can use the binary chunk
+        (None, Some(bin)) if index == 0 => bin.to_vec(),
+        (None, _) => return Err(format!("Buffer {} has no data", index)),
+    };
+
+    if data.len() < buffer.byte_length {
+        return Err(format!("Buffer {} is too short", index));
+    }
+
+    Ok(data)
+}
+
+/// Reads data from either a base64 data URI or a file relative to the
+/// glTF file
+fn load_uri(uri: &str, dir: &Path) -> Result<Vec<u8>, String> {
+    if uri.starts_with("data:") {
+        let start = uri.find(";base64,").ok_or("Unsupported data URI")?;
+        decode_base64(&uri[start + 8..])
+    } else {
+        let path = dir.join(percent_decode(uri));
+        fs::read(&path)
+            .map_err(|e| format!("Failed to read {}: {}", path.display(), e))
+    }
+}
+
+fn decode_base64(data: &str) -> Result<Vec<u8>, String> {
+    let mut out = Vec::with_capacity(data.len() * 3 / 4);
+    let mut bits = 0u32;
+    let mut count = 0;
+
+    for c in data.bytes() {
+        let value = match c {
+            b'A'..=b'Z' => c - b'A',
+            b'a'..=b'z' => c - b'a' + 26,
+            b'0'..=b'9' => c - b'0' + 52,
+            b'+' => 62,
+            b'/' => 63,
+            b'=' => break,
+            _ => return Err(String::from("Invalid base64 data")),
+        };
+
+        bits = bits << 6 | value as u32;
+        count += 6;
+
+        if count >= 8 {
+            count -= 8;
+            out.push((bits >> count) as u8);
+        }
+    }
+
+    Ok(out)
+}
+
+/// Relative URIs can contain percent encoded characters such as spaces
+fn percent_decode(uri: &str) -> String {
+    let bytes = uri.as_bytes();
+    let mut out = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+
+    while i < bytes.len() {
+        let hex = bytes
+            .get(i + 1..i + 3)
+            .and_then(|h| std::str::from_utf8(h).ok())
+            .and_then(|h| u8::from_str_radix(h, 16).ok());
+
+        match (bytes[i], hex) {
+            (b'%', Some(byte)) => {
+                out.push(byte);
+                i += 3;
+            }
+            (byte, _) => {
+                out.push(byte);
+                i += 1;
+            }
+        }
+    }
+
+    String::from_utf8_lossy(&out).into_owned()
+}
+
+impl<'a> Loader<'a> {
+    /// The nodes of the default scene. Files without scenes use every node
+    /// that isn't a child of another.
+    fn root_nodes(&self) -> Vec<usize> {
+        let doc = &self.doc;
+
+        match doc.scenes.get(doc.scene.unwrap_or(0)) {
+            Some(scene) => scene.nodes.clone(),
+            None => {
+                let children: HashSet<usize> = doc
+                    .nodes
+                    .iter()
+                    .flat_map(|n| n.children.iter().copied())
+                    .collect();
+
+                (0..doc.nodes.len())
+                    .filter(|i| !children.contains(i))
+                    .collect()
+            }
+        }
+    }
+
+    fn add_node(
+        &mut self,
+        index: usize,
+        parent: Matrix4,
+        visited: &mut HashSet<usize>,
+    ) -> Result<(), String> {
+        if !visited.insert(index) {
+            return Err(format!("Node {} appears more than once", index));
+        }
+
+        let node = self
+            .doc
+            .nodes
+            .get(index)
+            .ok_or_else(|| format!("Invalid node {}", index))?;
+        let world = parent * local_transform(node)?;
+        let children = node.children.clone();
+        let (camera, mesh) = (node.camera, node.mesh);
+        let name = match &node.name {
+            Some(name) => name.clone(),
+            None => format!("node{}", index),
+        };
+
+        if let Some(camera) = camera {
+            self.add_camera(camera, world)?;
+        }
+
+        if let Some(mesh) = mesh {
+            self.add_mesh(mesh, world, &name)?;
+        }
+
+        for child in children {
+            self.add_node(child, world, visited)?;
+        }
+
+        Ok(())
+    }
+
+    /// Cameras look down their local -Z axis with +Y up
+    fn add_camera(
+        &mut self,
+        index: usize,
+        world: Matrix4,
+    ) -> Result<(), String> {
+        let camera = self
+            .doc
+            .cameras
+            .get(index)
+            .ok_or_else(|| format!("Invalid camera {}", index))?;
+
+        // Orthographic cameras have no equivalent
+        if let Some(perspective) = &camera.perspective {
+            let look_from = world.transform_point(Vector::zeros());
+            let forward = world.transform_vector(Vector::new(0.0, 0.0, -1.0));
+
+            self.scene.cameras.push(GltfCamera {
+                look_from,
+                look_at: look_from + forward.normalized_exact(),
+                vup: world.transform_vector(Vector::new(0.0, 1.0, 0.0)),
+                vfov: perspective.yfov.to_degrees(),
+            });
+        }
+
+        Ok(())
+    }
+
+    fn add_mesh(
+        &mut self,
+        index: usize,
+        world: Matrix4,
+        name: &str,
+    ) -> Result<(), String> {
+        // Meshes scaled to nothing can't be seen
+        let normal_matrix = match world.inverse() {
+            Some(inverse) => inverse.transpose(),
+            None => return Ok(()),
+        };
+
+        // Mirroring transforms reverse the winding order of the faces
+        let m = &world.m;
+        let determinant = Vector::dot(
+            Vector::new(m[0][0], m[0][1], m[0][2]),
+            Vector::cross(
+                Vector::new(m[1][0], m[1][1], m[1][2]),
+                Vector::new(m[2][0], m[2][1], m[2][2]),
+            ),
+        );
+
+        let mesh = &mut self.scene.mesh;
+        let group = match mesh.groups.iter().position(|g| g == name) {
+            Some(i) => i,
+            None => {
+                mesh.groups.push(String::from(name));
+                mesh.groups.len() - 1
+            }
+        };
+
+        let primitives = &self
+            .doc
+            .meshes
+            .get(index)
+            .ok_or_else(|| format!("Invalid mesh {}", index))?
+            .primitives;
+
+        for primitive in primitives {
+            let triangles = match self.triangles(primitive)? {
+                Some(triangles) => triangles,
+                None => continue,
+            };
+
+            let attribute = |name: &str| primitive.attributes.get(name);
+            let positions = self.read(
+                *attribute("POSITION").ok_or("Primitive has no positions")?,
+                3,
+            )?;
+            let normals = match attribute("NORMAL") {
+                Some(&i) => Some(self.read(i, 3)?),
+                None => None,
+            };
+            let uvs = match attribute("TEXCOORD_0") {
+                Some(&i) => Some(self.read(i, 2)?),
+                None => None,
+            };
+
+            let vertices = positions.len() / 3;
+            if triangles.iter().flatten().any(|&i| i >= vertices) {
+                return Err(String::from("Vertex index out of range"));
+            }
+            if normals.as_ref().map_or(false, |n| n.len() / 3 < vertices) {
+                return Err(String::from("Primitive has too few normals"));
+            }
+            if uvs.as_ref().map_or(false, |uvs| uvs.len() / 2 < vertices) {
+                return Err(String::from(
+                    "Primitive has too few texture coordinates",
+                ));
+            }
+            if let Some(material) = primitive.material {
+                if material >= self.doc.materials.len() {
+                    return Err(format!(
+                        "Material index {} out of range",
+                        material
+                    ));
+                }
+            }
+
+            let mesh = &mut self.scene.mesh;
+            let position_offset = mesh.positions.len();
+            let normal_offset = mesh.normals.len();
+            let uv_offset = mesh.uvs.len();
+
+            let vector = |values: &[f64], i: usize| {
+                let v = &values[i * 3..i * 3 + 3];
+                Vector::new(v[0] as f32, v[1] as f32, v[2] as f32)
+            };
+
+            for i in 0..vertices {
+                mesh.positions
+                    .push(world.transform_point(vector(&positions, i)));
+            }
+
+            if let Some(normals) = &normals {
+                for i in 0..vertices {
+                    let n = normal_matrix.transform_vector(vector(normals, i));
+                    mesh.normals.push(n.normalized_exact());
+                }
+            }
+
+            // glTF texture coordinates start at the top of the image
+            if let Some(uvs) = &uvs {
+                for i in 0..vertices {
+                    let (u, v) = (uvs[i * 2] as f32, uvs[i * 2 + 1] as f32);
+                    mesh.uvs.push((u, 1.0 - v));
+                }
+            }
+
+            for mut tri in triangles {
+                if determinant < 0.0 {
+                    tri.swap(1, 2);
+                }
+
+                let offset = |offset: usize| {
+                    [tri[0] + offset, tri[1] + offset, tri[2] + offset]
+                };
+
+                if normals.is_none() {
+                    self.faces_without_normals.push(mesh.faces.len());
+                }
+
+                mesh.faces.push(Face {
+                    positions: offset(position_offset),
+                    normals: offset(normal_offset),
+                    uvs: uvs.as_ref().map(|_| offset(uv_offset)),
+                    material: primitive.material,
+                    group: Some(group),
+                });
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Lists the vertex indices of each triangle in a primitive, or returns
+    /// None for points and lines
+    fn triangles(
+        &self,
+        primitive: &Primitive,
+    ) -> Result<Option<Vec<[usize; 3]>>, String> {
+        let indices: Vec<usize> = match primitive.indices {
+            Some(i) => self.read(i, 1)?.iter().map(|&i| i as usize).collect(),
+            None => {
+                let position = primitive
+                    .attributes
+                    .get("POSITION")
+                    .ok_or("Primitive has no positions")?;
+                (0..self.accessor(*position)?.count).collect()
+            }
+        };
+
+        let n = indices.len();
+        let triangles = match primitive.mode.unwrap_or(MODE_TRIANGLES) {
+            MODE_TRIANGLES => indices
+                .chunks(3)
+                .filter(|c| c.len() == 3)
+                .map(|c| [c[0], c[1], c[2]])
+                .collect(),
+            // Every other triangle in a strip is wound the other way
+            MODE_TRIANGLE_STRIP => (2..n.max(2))
+                .map(|i| match i % 2 {
+                    0 => [indices[i - 2], indices[i - 1], indices[i]],
+                    _ => [indices[i - 1], indices[i - 2], indices[i]],
+                })
+                .collect(),
+            MODE_TRIANGLE_FAN => (2..n.max(2))
+                .map(|i| [indices[0], indices[i - 1], indices[i]])
+                .collect(),
+            _ => return Ok(None),
+        };
+
+        Ok(Some(triangles))
+    }
+
+    fn accessor(&self, index: usize) -> Result<&Accessor, String> {
+        self.doc
+            .accessors
+            .get(index)
+            .ok_or_else(|| format!("Invalid accessor {}", index))
+    }
+
+    /// Reads the values of an accessor, checking that each element has the
+    /// expected number of components. The values are doubles so that
+    /// every u32 index is exactly representable.
+    fn read(
+        &self,
+        index: usize,
+        components: usize,
+    ) -> Result<Vec<f64>, String> {
+        let accessor = self.accessor(index)?;
+        let expected = match components {
+            1 => "SCALAR",
+            2 => "VEC2",
+            _ => "VEC3",
+        };
+
+        if accessor.kind != expected {
+            return Err(format!("Accessor {} should be a {}", index, expected));
+        }
+
+        if accessor.sparse.is_some() {
+            return Err(String::from("Sparse accessors aren't supported"));
+        }
+
+        // Accessors without a buffer view are all zeros
+        let view = match accessor.buffer_view {
+            Some(i) => self
+                .doc
+                .buffer_views
+                .get(i)
+                .ok_or_else(|| format!("Invalid buffer view {}", i))?,
+            None => return Ok(vec![0.0; accessor.count * components]),
+        };
+
+        let size = match accessor.component_type {
+            5120 | 5121 => 1,
+            5122 | 5123 => 2,
+            5125 | 5126 => 4,
+            other => return Err(format!("Invalid component type {}", other)),
+        };
+        let stride = view.byte_stride.unwrap_or(size * components);
+        let buffer = self
+            .buffers
+            .get(view.buffer)
+            .ok_or_else(|| format!("Invalid buffer {}", view.buffer))?;
+        let data = buffer
+            .get(view.byte_offset..view.byte_offset + view.byte_length)
+            .ok_or("Buffer view is out of range")?;
+
+        let mut values = Vec::with_capacity(accessor.count * components);
+        for i in 0..accessor.count {
+            for c in 0..components {
+                let start = accessor.byte_offset + i * stride + c * size;
+                let b = data
+                    .get(start..start + size)
+                    .ok_or("Accessor is out of range")?;
+
+                values.push(component(
+                    b,
+                    accessor.component_type,
+                    accessor.normalized,
+                ));
+            }
+        }
+
+        Ok(values)
+    }
+
+    fn material(&mut self, index: usize) -> Result<Box<dyn Material>, String> {
+        let def = &self.doc.materials[index];
+        let pbr = def.pbr_metallic_roughness.as_ref();
+        let extensions = &def.extensions;
+
+        let [r, g, b, alpha] = pbr
+            .and_then(|p| p.base_color_factor)
+            .unwrap_or([1.0, 1.0, 1.0, 1.0]);
+        let base_color = Vector::new(r, g, b);
+        let metallic = pbr.and_then(|p| p.metallic_factor).unwrap_or(1.0);
+        let roughness = pbr.and_then(|p| p.roughness_factor).unwrap_or(1.0);
+
+        let [er, eg, eb] = def.emissive_factor.unwrap_or([0.0, 0.0, 0.0]);
+        let strength = extensions
+            .emissive_strength
+            .as_ref()
+            .and_then(|e| e.emissive_strength)
+            .unwrap_or(1.0);
+        let emissive = strength * Vector::new(er, eg, eb);
+
+        let transmission = extensions
+            .transmission
+            .as_ref()
+            .and_then(|t| t.transmission_factor)
+            .unwrap_or(0.0);
+        let blended = def.alpha_mode.as_ref().map_or(false, |m| m == "BLEND");
+        let ior = extensions.ior.as_ref().and_then(|i| i.ior).unwrap_or(1.5);
+
+        let texture = pbr.and_then(|p| p.base_color_texture.as_ref());
+        let texture = match texture.map(|t| t.index) {
+            Some(i) => self.texture(i, base_color)?,
+            None => Box::new(ConstantTexture::new(base_color)),
+        };
+
+        // The closest equivalent is picked in the same way as for MTL
+        // materials
+        Ok(if emissive.x + emissive.y + emissive.z > 0.0 {
+            Box::new(DiffuseLight::new(Box::new(ConstantTexture::new(
+                emissive,
+            ))))
+        } else if transmission > 0.5 || (blended && alpha < 1.0) {
+            Box::new(Dielectric::new(ior))
+        } else if metallic >= 0.5 {
+            Box::new(Metal::new(r, g, b, roughness))
+        } else {
+            Box::new(Lambertian::new(texture))
+        })
+    }
+
+    /// Creates a texture for the image a glTF texture uses, tinted by the
+    /// base color factor
+    fn texture(
+        &mut self,
+        index: usize,
+        tint: Vector,
+    ) -> Result<Box<dyn Texture>, String> {
+        let source = self
+            .doc
+            .textures
+            .get(index)
+            .ok_or_else(|| format!("Invalid texture {}", index))?
+            .source;
+
+        // Textures may only have images in extension formats
+        let source = match source {
+            Some(source) => source,
+            None => return Ok(Box::new(ConstantTexture::new(tint))),
+        };
+
+        if !self.images.contains_key(&source) {
+            let image = self.load_image(source)?;
+            self.images.insert(source, image);
+        }
+
+        let image = &self.images[&source];
+        Ok(Box::new(ImageTexture::new(
+            image.width,
+            image.height,
+            image.pixels.clone(),
+            tint,
+        )))
+    }
+
+    fn load_image(&self, index: usize) -> Result<Image, String> {
+        let def = self
+            .doc
+            .images
+            .get(index)
+            .ok_or_else(|| format!("Invalid image {}", index))?;
+
+        let bytes = match (&def.uri, def.buffer_view) {
+            (Some(uri), _) => load_uri(uri, self.dir)?,
+            (None, Some(i)) => {
+                let view = self
+                    .doc
+                    .buffer_views
+                    .get(i)
+                    .ok_or_else(|| format!("Invalid buffer view {}", i))?;
+
+                self.buffers
+                    .get(view.buffer)
+                    .and_then(|b| {
+                        b.get(
+                            view.byte_offset
+                                ..view.byte_offset + view.byte_length,
+                        )
+                    })
+                    .ok_or("Buffer view is out of range")?
+                    .to_vec()
+            }
+            (None, None) => return Err(format!("Image {} has no data", index)),
+        };
+
+        if bytes.starts_with(&[0xff, 0xd8]) {
+            return Err(format!(
+                "Image {} is a JPEG, only PNG textures are supported",
+                index
+            ));
+        }
+
+        let bitmap = decode_png(&bytes)
+            .map_err(|e| format!("Image {}: {}", index, e))?;
+
+        // Base color textures are sRGB encoded
+        let pixels = bitmap
+            .pixels
+            .iter()
+            .map(|p| Vector::new(srgb(p[0]), srgb(p[1]), srgb(p[2])))
+            .collect();
+
+        Ok(Image {
+            width: bitmap.width,
+            height: bitmap.height,
+            pixels: Arc::new(pixels),
+        })
+    }
+}
+
+/// A node's transform, given either as a column-major matrix or as a
+/// translation, rotation and scale applied in reverse order
+fn local_transform(node: &Node) -> Result<Matrix4, String> {
+    if let Some(matrix) = &node.matrix {
+        return Ok(Matrix4::from_rows(matrix)?.transpose());
+    }
+
+    let [tx, ty, tz] = node.translation.unwrap_or([0.0, 0.0, 0.0]);
+    let [x, y, z, w] = node.rotation.unwrap_or([0.0, 0.0, 0.0, 1.0]);
+    let [sx, sy, sz] = node.scale.unwrap_or([1.0, 1.0, 1.0]);
+
+    let rotation = Quaternion {
+        w,
+        v: Vector::new(x, y, z),
+    }
+    .normalize()
+    .matrix();
+
+    Ok(Matrix4::translation(Vector::new(tx, ty, tz))
+        * rotation
+        * Matrix4::scale(Vector::new(sx, sy, sz)))
+}
+
+/// Reads a single little endian component, mapping normalized integers to
+/// [0, 1] or [-1, 1]
+fn component(b: &[u8], component_type: u32, normalized: bool) -> f64 {
+    let (value, max) = match component_type {
+        5120 => (b[0] as i8 as f64, 127.0),
+        5121 => (b[0] as f64, 255.0),
+        5122 => (i16::from_le_bytes([b[0], b[1]]) as f64, 32767.0),
+        5123 => (u16::from_le_bytes([b[0], b[1]]) as f64, 65535.0),
+        5125 => (u32::from_le_bytes([b[0], b[1], b[2], b[3]]) as f64, 1.0),
+        _ => (f32::from_le_bytes([b[0], b[1], b[2], b[3]]) as f64, 1.0),
+    };
+
+    if normalized {
+        f64::max(value / max, -1.0)
+    } else {
+        value
+    }
+}
+
+fn srgb(value: u8) -> f32 {
+    let c = value as f32 / 255.0;
+    if c <= 0.04045 {
+        c / 12.92
+    } else {
+        ((c + 0.055) / 1.055).powf(2.4)
+    }
+}
@@ -0,0 +1,150 @@
+//! Loading of grayscale height maps for terrain. Netpbm images, both the
+//! plain text (`P2` and `P3`) and binary (`P5` and `P6`) kinds, are read
+//! with their full precision of up to 16 bits. PNG images are decoded to 8
+//! bits, which can give terrain visible steps, so 16 bit PGM files are
+//! better for smooth slopes. Colors are averaged into a single height.
+use super::png::decode_png;
+
+use std::fs;
+use std::path::Path;
+
+/// Heights between 0 and 1 on a grid, stored row by row from the top
+#[derive(Debug)]
+pub struct HeightMap {
+    pub width: usize,
+    pub height: usize,
+    pub values: Vec<f32>,
+}
+
+impl HeightMap {
+    pub fn value(&self, x: usize, y: usize) -> f32 {
+        self.values[y * self.width + x]
+    }
+}
+
+pub fn load_heightmap(path: &Path) -> Result<HeightMap, String> {
+    let bytes = fs::read(path)
+        .map_err(|e| format!("Failed to read {}: {}", path.display(), e))?;
+
+    parse(&bytes).map_err(|e| format!("{}: {}", path.display(), e))
+}
+
+fn parse(bytes: &[u8]) -> Result<HeightMap, String> {
+    let map = if bytes.starts_with(b"P") {
+        parse_netpbm(bytes)?
+    } else {
+        let bitmap = decode_png(bytes)?;
+        let values = bitmap
+            .pixels
+            .iter()
+            .map(|p| (p[0] as f32 + p[1] as f32 + p[2] as f32) / (3.0 * 255.0))
+            .collect();
+
+        HeightMap {
+            width: bitmap.width,
+            height: bitmap.height,
+            values,
+        }
+    };
+
+    // Terrain is made of the cells between samples, so there has to be at
+    // least one
+    if map.width < 2 || map.height < 2 {
+        return Err(String::from(
+            "Height maps need at least two pixels along each side",
+        ));
+    }
+
+    Ok(map)
+}
+
+fn parse_netpbm(bytes: &[u8]) -> Result<HeightMap, String> {
+    let (channels, binary) = match bytes.get(1) {
+        Some(b'2') => (1, false),
+        Some(b'3') => (3, false),
+        Some(b'5') => (1, true),
+        Some(b'6') => (3, true),
+        _ => return Err(String::from("Unsupported Netpbm image type")),
+    };
+
+    let mut header = Tokens { bytes, pos: 2 };
+    let width = header.number()?;
+    let height = header.number()?;
+    let max = header.number()?;
+    if max == 0 || max > 65535 {
+        return Err(format!("Invalid maximum value {}", max));
+    }
+
+    let count = width * height * channels;
+    let samples = if binary {
+        // A single whitespace character separates the header from the
+        // pixels
+        let data = bytes.get(header.pos + 1..).unwrap_or(&[]);
+        let sample_size = if max < 256 { 1 } else { 2 };
+        if data.len() < count * sample_size {
+            return Err(String::from("Image data is truncated"));
+        }
+
+        (0..count)
+            .map(|i| match sample_size {
+                1 => data[i] as usize,
+                _ => {
+                    u16::from_be_bytes([data[2 * i], data[2 * i + 1]]) as usize
+                }
+            })
+            .collect()
+    } else {
+        (0..count)
+            .map(|_| header.number())
+            .collect::<Result<Vec<_>, _>>()?
+    };
+
+    let scale = 1.0 / (channels * max) as f32;
+    let values = samples
+        .chunks(channels)
+        .map(|pixel| pixel.iter().sum::<usize>() as f32 * scale)
+        .collect();
+
+    Ok(HeightMap {
+        width,
+        height,
+        values,
+    })
+}
+
+/// Reads the whitespace separated numbers of a Netpbm header or plain text
+/// image, skipping comments
+struct Tokens<'a> {
+    bytes: &'a [u8],
+    pos: usize,
+}
+
+impl<'a> Tokens<'a> {
+    fn number(&mut self) -> Result<usize, String> {
+        loop {
+            match self.bytes.get(self.pos) {
+                Some(b'#') => {
+                    while self
+                        .bytes
+                        .get(self.pos)
+                        .map_or(false, |&c| c != b'\n')
+                    {
+                        self.pos += 1;
+                    }
+                }
+                Some(c) if c.is_ascii_whitespace() => self.pos += 1,
+                _ => break,
+            }
+        }
+
+        let start = self.pos;
+        while self.bytes.get(self.pos).map_or(false, u8::is_ascii_digit) {
+            self.pos += 1;
+        }
+
+        std::str::from_utf8(&self.bytes[start..self.pos])
+            .unwrap()
+            .parse()
+            .map_err(|_| String::from("Invalid number in Netpbm image"))
+    }
+}
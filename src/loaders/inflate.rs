@@ -0,0 +1,390 @@
+//! Decompression of zlib streams (RFC 1950 and 1951), which is all that's
+//! needed to read PNG images.
+
+/// The base lengths and extra bits for length codes 257 to 285
+const LENGTHS: [(u16, u8); 29] = [
+    (3, 0),
+    (4, 0),
+    (5, 0),
+    (6, 0),
+    (7, 0),
+    (8, 0),
+    (9, 0),
+    (10, 0),
+    (11, 1),
+    (13, 1),
+    (15, 1),
+    (17, 1),
+    (19, 2),
+    (23, 2),
+    (27, 2),
+    (31, 2),
+    (35, 3),
+    (43, 3),
+    (51, 3),
+    (59, 3),
+    (67, 4),
+    (83, 4),
+    (99, 4),
+    (115, 4),
+    (131, 5),
+    (163, 5),
+    (195, 5),
+    (227, 5),
+    (258, 0),
+];
+
+/// The base distances and extra bits for distance codes 0 to 29
+const DISTANCES: [(u16, u8); 30] = [
+    (1, 0),
+    (2, 0),
+    (3, 0),
+    (4, 0),
+    (5, 1),
+    (7, 1),
+    (9, 2),
+    (13, 2),
+    (17, 3),
+    (25, 3),
+    (33, 4),
+    (49, 4),
+    (65, 5),
+    (97, 5),
+    (129, 6),
+    (193, 6),
+    (257, 7),
+    (385, 7),
+    (513, 8),
+    (769, 8),
+    (1025, 9),
+    (1537, 9),
+    (2049, 10),
+    (3073, 10),
+    (4097, 11),
+    (6145, 11),
+    (8193, 12),
+    (12289, 12),
+    (16385, 13),
+    (24577, 13),
+];
+
+/// The order that code length code lengths are stored in
+const CODE_LENGTH_ORDER: [usize; 19] = [
+    16, 17, 18, 0, 8, 7, 9, 6, 10, 5, 11, 4, 12, 3, 13, 2, 14, 1, 15,
+];
+
+const MAX_BITS: usize = 15;
+
+struct BitReader<'a> {
+    bytes: &'a [u8],
+    pos: usize,
+    bit: u8,
+}
+
+/// A canonical Huffman code, stored as the number of codes of each length
+/// and the symbols sorted by code
+struct Huffman {
+    counts: [u16; MAX_BITS + 1],
+    symbols: Vec<u16>,
+}
+
+impl<'a> BitReader<'a> {
+    fn bit(&mut self) -> Result<u32, String> {
+        let byte = *self
+            .bytes
+            .get(self.pos)
+            .ok_or("Unexpected end of compressed data")?;
+        let bit = (byte >> self.bit) & 1;
+
+        self.bit += 1;
+        if self.bit == 8 {
+            self.bit = 0;
+            self.pos += 1;
+        }
+
+        Ok(bit as u32)
+    }
+
+    /// Reads a number stored least significant bit first
+    fn bits(&mut self, count: u8) -> Result<u32, String> {
+        let mut value = 0;
+        for i in 0..count {
+            value |= self.bit()? << i;
+        }
+
+        Ok(value)
+    }
+
+    fn align_to_byte(&mut self) {
+        if self.bit != 0 {
+            self.bit = 0;
+            self.pos += 1;
+        }
+    }
+}
+
+impl Huffman {
+    fn new(lengths: &[u8]) -> Self {
+        let mut counts = [0; MAX_BITS + 1];
+        for &length in lengths {
+            counts[length as usize] += 1;
+        }
+        counts[0] = 0;
+
+        let mut offsets = [0; MAX_BITS + 1];
+        for i in 1..MAX_BITS {
+            offsets[i + 1] = offsets[i] + counts[i];
+        }
+
+        let mut symbols = vec![0; lengths.len()];
+        for (symbol, &length) in lengths.iter().enumerate() {
+            if length != 0 {
+                symbols[offsets[length as usize] as usize] = symbol as u16;
+                offsets[length as usize] += 1;
+            }
+        }
+
+        Self { counts, symbols }
+    }
+
+    /// Decodes a symbol one bit at a time. Huffman codes are stored most
+    /// significant bit first, unlike everything else.
+    fn decode(&self, reader: &mut BitReader) -> Result<u16, String> {
+        let mut code = 0;
+        let mut first = 0;
+        let mut index = 0;
+
+        for &count in &self.counts[1..] {
+            code |= reader.bit()? as i32;
+            let count = count as i32;
+
+            if code - first < count {
+                return Ok(self.symbols[(index + code - first) as usize]);
+            }
+
+            index += count;
+            first = (first + count) << 1;
+            code <<= 1;
+        }
+
+        Err(String::from("Invalid Huffman code"))
+    }
+}
+
+/// Decompresses a zlib stream
+pub fn inflate_zlib(bytes: &[u8]) -> Result<Vec<u8>, String> {
+    if bytes.len() < 2 || (bytes[0] as u16 * 256 + bytes[1] as u16) % 31 != 0 {
+        return Err(String::from("Invalid zlib header"));
+    }
+
+    if bytes[0] & 0x0f != 8 {
+        return Err(String::from("Unsupported compression method"));
+    }
+
+    if bytes[1] & 0x20 != 0 {
+        return Err(String::from("Preset dictionaries aren't supported"));
+    }
+
+    inflate(&bytes[2..])
+}
+
+/// Decompresses a raw deflate stream
+pub fn inflate(bytes: &[u8]) -> Result<Vec<u8>, String> {
+    let mut reader = BitReader {
+        bytes,
+        pos: 0,
+        bit: 0,
+    };
+    let mut out = Vec::new();
+
+    loop {
+        let last = reader.bit()? == 1;
+
+        match reader.bits(2)? {
+            0 => stored_block(&mut reader, &mut out)?,
+            1 => {
+                let (lengths, distances) = fixed_codes();
+                compressed_block(&mut reader, &mut out, &lengths, &distances)?;
+            }
+            2 => {
+                let (lengths, distances) = dynamic_codes(&mut reader)?;
+                compressed_block(&mut reader, &mut out, &lengths, &distances)?;
+            }
+            _ => return Err(String::from("Invalid block type")),
+        }
+
+        if last {
+            return Ok(out);
+        }
+    }
+}
+
+fn stored_block(
+    reader: &mut BitReader,
+    out: &mut Vec<u8>,
+) -> Result<(), String> {
+    reader.align_to_byte();
+
+    let header = reader
+        .bytes
+        .get(reader.pos..reader.pos + 4)
+        .ok_or("Unexpected end of compressed data")?;
+    let len = header[0] as usize | (header[1] as usize) << 8;
+    let nlen = header[2] as usize | (header[3] as usize) << 8;
+
+    if len != !nlen & 0xffff {
+        return Err(String::from("Invalid stored block length"));
+    }
+
+    let start = reader.pos + 4;
+    let data = reader
+        .bytes
+        .get(start..start + len)
+        .ok_or("Unexpected end of compressed data")?;
+
+    out.extend_from_slice(data);
+    reader.pos = start + len;
+
+    Ok(())
+}
+
+fn fixed_codes() -> (Huffman, Huffman) {
+    let mut lengths = [0; 288];
+    for (symbol, length) in lengths.iter_mut().enumerate() {
+        *length = match symbol {
+            0..=143 => 8,
+            144..=255 => 9,
+            256..=279 => 7,
+            _ => 8,
+        };
+    }
+
+    (Huffman::new(&lengths), Huffman::new(&[5; 30]))
+}
+
+fn dynamic_codes(reader: &mut BitReader) -> Result<(Huffman, Huffman), String> {
+    let literals = reader.bits(5)? as usize + 257;
+    let distances = reader.bits(5)? as usize + 1;
+    let code_lengths = reader.bits(4)? as usize + 4;
+
+    let mut lengths = [0; 19];
+    for &i in &CODE_LENGTH_ORDER[..code_lengths] {
+        lengths[i] = reader.bits(3)? as u8;
+    }
+    let code_length_code = Huffman::new(&lengths);
+
+    // The literal/length and distance code lengths are run length encoded
+    // together
+    let mut lengths = Vec::with_capacity(literals + distances);
+    while lengths.len() < literals + distances {
+        let symbol = code_length_code.decode(reader)?;
+
+        let (value, repeat) = match symbol {
+            0..=15 => (symbol as u8, 1),
+            16 => {
+                let previous =
+                    *lengths.last().ok_or("Repeat with no previous length")?;
+                (previous, 3 + reader.bits(2)?)
+            }
+            17 => (0, 3 + reader.bits(3)?),
+            18 => (0, 11 + reader.bits(7)?),
+            _ => return Err(String::from("Invalid code length")),
+        };
+
+        for _ in 0..repeat {
+            lengths.push(value);
+        }
+    }
+
+    if lengths.len() != literals + distances {
+        return Err(String::from("Too many code lengths"));
+    }
+
+    Ok((
+        Huffman::new(&lengths[..literals]),
+        Huffman::new(&lengths[literals..]),
+    ))
+}
+
+fn compressed_block(
+    reader: &mut BitReader,
+    out: &mut Vec<u8>,
+    lengths: &Huffman,
+    distances: &Huffman,
+) -> Result<(), String> {
+    loop {
+        let symbol = lengths.decode(reader)? as usize;
+
+        if symbol < 256 {
+            out.push(symbol as u8);
+            continue;
+        } else if symbol == 256 {
+            return Ok(());
+        }
+
+        let (base, extra) =
+            *LENGTHS.get(symbol - 257).ok_or("Invalid length code")?;
+        let length = base as usize + reader.bits(extra)? as usize;
+
+        let symbol = distances.decode(reader)? as usize;
+        let (base, extra) =
+            *DISTANCES.get(symbol).ok_or("Invalid distance code")?;
+        let distance = base as usize + reader.bits(extra)? as usize;
+
+        if distance > out.len() {
+            return Err(String::from("Distance is too far back"));
+        }
+
+        // The copy can overlap the bytes it's producing, so it has to be
+        // done a byte at a time
+        let start = out.len() - distance;
+        for i in 0..length {
+            let byte = out[start + i];
+            out.push(byte);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn stored_block() {
+        let bytes = [
+            1, 14, 0, 241, 255, 72, 101, 108, 108, 111, 44, 32, 115, 116, 111,
+            114, 101, 100, 33,
+        ];
+
+        assert_eq!(inflate(&bytes).unwrap(), b"Hello, stored!");
+    }
+
+    #[test]
+    fn fixed_huffman_block() {
+        let bytes = [75, 76, 74, 78, 68, 69, 0];
+
+        assert_eq!(inflate(&bytes).unwrap(), b"abcabcabcabcabcabc");
+    }
+
+    #[test]
+    fn dynamic_huffman_block() {
+        let bytes = [
+            69, 139, 193, 9, 0, 64, 8, 195, 102, 77, 234, 254, 51, 156, 10,
+            114, 125, 132, 54, 80, 64, 38, 89, 238, 240, 211, 16, 181, 186, 57,
+            50, 45, 164, 114, 47, 31,
+        ];
+
+        assert_eq!(
+            inflate(&bytes).unwrap(),
+            &b"aaabaaaaacaaaaaabaaababaaababaabcacbbbdaabbbaaacbcabadcaabaaaaab"[..]
+        );
+    }
+
+    #[test]
+    fn zlib_header() {
+        let bytes = [120, 156, 75, 76, 74, 78, 68, 69, 0];
+
+        assert_eq!(inflate_zlib(&bytes).unwrap(), b"abcabcabcabcabcabc");
+        assert!(inflate_zlib(&[120, 157, 75]).is_err());
+    }
+}
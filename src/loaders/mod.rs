@@ -0,0 +1,123 @@
+//! Loaders for external model formats. Every format is turned into the
+//! same [`MeshData`], which the scene wraps in a [`Mesh`] when it's built.
+//! Voxel grids for volumes are loaded into a [`VoxelGrid`] instead, and
+//! height maps for terrain into a [`HeightMap`].
+//!
+//! [`MeshData`]: struct.MeshData.html
+//! [`Mesh`]: ../objects/struct.Mesh.html
+//! [`VoxelGrid`]: struct.VoxelGrid.html
+//! [`HeightMap`]: struct.HeightMap.html
+use crate::materials::Material;
+use crate::Vector;
+
+use std::path::Path;
+
+mod gltf;
+mod heightmap;
+mod inflate;
+mod obj;
+mod ply;
+mod png;
+mod stl;
+mod vol;
+
+pub use gltf::load_gltf;
+pub use heightmap::{load_heightmap, HeightMap};
+pub use obj::load_obj;
+pub use ply::load_ply;
+pub use stl::load_stl;
+pub use vol::{load_vol, VoxelGrid};
+
+/// Triangulated geometry loaded from a file, along with any materials
+/// the file describes
+#[derive(Debug, Default)]
+pub struct MeshData {
+    pub positions: Vec<Vector>,
+    pub normals: Vec<Vector>,
+    pub uvs: Vec<(f32, f32)>,
+    pub faces: Vec<Face>,
+    pub materials: Vec<Box<dyn Material>>,
+    /// The names of the groups that faces can belong to
+    pub groups: Vec<String>,
+}
+
+/// A triangle made up of indices into the mesh's vertex data
+#[derive(Debug, Copy, Clone)]
+pub struct Face {
+    pub positions: [usize; 3],
+    pub normals: [usize; 3],
+    pub uvs: Option<[usize; 3]>,
+    pub material: Option<usize>,
+    pub group: Option<usize>,
+}
+
+/// Loads a model with the loader for the given format, or for the file's
+/// extension if no format is given
+pub fn load_mesh(
+    path: &Path,
+    format: Option<&str>,
+) -> Result<MeshData, String> {
+    let extension = path
+        .extension()
+        .and_then(|e| e.to_str())
+        .map(str::to_lowercase);
+
+    match format.or_else(|| extension.as_ref().map(String::as_str)) {
+        Some("obj") => load_obj(path),
+        Some("ply") => load_ply(path),
+        Some("stl") => load_stl(path),
+        Some("gltf") | Some("glb") => load_gltf(path).map(|scene| scene.mesh),
+        Some(other) => Err(format!(
+            "Unsupported model format {} for {}",
+            other,
+            path.display()
+        )),
+        None => Err(format!(
+            "Can't tell the model format of {}, give it with \"format\"",
+            path.display()
+        )),
+    }
+}
+
+impl MeshData {
+    /// Computes a normal for each position by summing the normals of the
+    /// faces around it, weighted by their area. These are used by any
+    /// faces that don't have normals, which must have their normal indices
+    /// set to their position indices.
+    fn add_vertex_normals(&mut self, faces_without_normals: &[usize]) {
+        if faces_without_normals.is_empty() {
+            return;
+        }
+
+        let mut sums = vec![Vector::zeros(); self.positions.len()];
+
+        for face in &self.faces {
+            let [i0, i1, i2] = face.positions;
+            let (p0, p1, p2) =
+                (self.positions[i0], self.positions[i1], self.positions[i2]);
+
+            // The cross product's length is twice the face's area
+            let weighted = Vector::cross(p1 - p0, p2 - p0);
+            for &i in &face.positions {
+                sums[i] += weighted;
+            }
+        }
+
+        let offset = self.normals.len();
+        self.normals.extend(sums.into_iter().map(|n| {
+            let length = n.length();
+            if length > 0.0 {
+                n / length
+            } else {
+                n
+            }
+        }));
+
+        for &f in faces_without_normals {
+            let face = &mut self.faces[f];
+            for i in 0..3 {
+                face.normals[i] = offset + face.positions[i];
+            }
+        }
+    }
+}
@@ -0,0 +1,355 @@
+//! Wavefront OBJ and MTL loading. Polygons are split into triangle fans,
+//! and MTL materials are approximated with the renderer's own materials.
+use super::{Face, MeshData};
+use crate::materials::{Dielectric, DiffuseLight, Lambertian, Material, Metal};
+use crate::textures::ConstantTexture;
+use crate::Vector;
+
+use std::collections::HashMap;
+use std::fs;
+use std::path::Path;
+
+/// The properties of an MTL material that have an equivalent here
+#[derive(Debug)]
+struct MtlMaterial {
+    kd: Vector,
+    ks: Vector,
+    ke: Vector,
+    ns: f32,
+    ni: f32,
+    dissolve: f32,
+    illum: u32,
+}
+
+impl Default for MtlMaterial {
+    fn default() -> Self {
+        Self {
+            kd: Vector::new(0.8, 0.8, 0.8),
+            ks: Vector::zeros(),
+            ke: Vector::zeros(),
+            ns: 0.0,
+            ni: 1.5,
+            dissolve: 1.0,
+            illum: 2,
+        }
+    }
+}
+
+impl MtlMaterial {
+    /// Picks the closest material to this one: emissive materials become
+    /// lights, transparent ones glass, mirror-like ones metal, and the
+    /// rest are diffuse
+    fn to_material(&self) -> Box<dyn Material> {
+        let luminance = |c: Vector| 0.2126 * c.x + 0.7152 * c.y + 0.0722 * c.z;
+        let transparent = match self.illum {
+            4 | 6 | 7 | 9 => true,
+            _ => self.dissolve < 1.0,
+        };
+
+        if luminance(self.ke) > 0.0 {
+            Box::new(DiffuseLight::new(Box::new(ConstantTexture::new(self.ke))))
+        } else if transparent {
+            Box::new(Dielectric::new(self.ni))
+        } else if self.illum == 3 || luminance(self.ks) > luminance(self.kd) {
+            // Rougher surfaces have lower specular exponents
+            let fuzz = f32::sqrt(2.0 / (self.ns + 2.0));
+            Box::new(Metal::new(self.ks.x, self.ks.y, self.ks.z, fuzz))
+        } else {
+            Box::new(Lambertian::new(Box::new(ConstantTexture::new(self.kd))))
+        }
+    }
+}
+
+pub fn load_obj(path: &Path) -> Result<MeshData, String> {
+    let contents = fs::read_to_string(path)
+        .map_err(|e| format!("Failed to read {}: {}", path.display(), e))?;
+    let dir = path.parent().unwrap_or_else(|| Path::new("."));
+    let err = |line: usize, msg: &str| {
+        format!("{}:{}: {}", path.display(), line + 1, msg)
+    };
+
+    let mut mesh = MeshData::default();
+    let mut material_ids: HashMap<String, usize> = HashMap::new();
+    let mut material = None;
+    let mut group = None;
+    let mut faces_without_normals = Vec::new();
+
+    for (n, line) in contents.lines().enumerate() {
+        let mut tokens = line.split_whitespace();
+        let keyword = match tokens.next() {
+            Some(k) if !k.starts_with('#') => k,
+            _ => continue,
+        };
+        let args: Vec<&str> = tokens.collect();
+
+        match keyword {
+            "v" => mesh.positions.push(
+                parse_vector(&args)
+                    .ok_or_else(|| err(n, "Invalid vertex position"))?,
+            ),
+            "vn" => mesh.normals.push(
+                parse_vector(&args).ok_or_else(|| err(n, "Invalid normal"))?,
+            ),
+            "vt" => {
+                let u = args.get(0).and_then(|u| u.parse().ok());
+                let v = args.get(1).map_or(Some(0.0), |v| v.parse().ok());
+
+                match (u, v) {
+                    (Some(u), Some(v)) => mesh.uvs.push((u, v)),
+                    _ => return Err(err(n, "Invalid texture coordinate")),
+                }
+            }
+            "f" => {
+                if args.len() < 3 {
+                    return Err(err(n, "Faces need at least 3 vertices"));
+                }
+
+                let vertices = args
+                    .iter()
+                    .map(|v| parse_face_vertex(v, &mesh))
+                    .collect::<Option<Vec<_>>>()
+                    .ok_or_else(|| err(n, "Invalid face vertex"))?;
+
+                let has_normals = vertices.iter().all(|v| v.2.is_some());
+                let has_uvs = vertices.iter().all(|v| v.1.is_some());
+
+                // Split the polygon into a fan of triangles around the
+                // first vertex
+                for i in 1..vertices.len() - 1 {
+                    let tri = [vertices[0], vertices[i], vertices[i + 1]];
+
+                    if !has_normals {
+                        faces_without_normals.push(mesh.faces.len());
+                    }
+
+                    mesh.faces.push(Face {
+                        positions: [tri[0].0, tri[1].0, tri[2].0],
+                        normals: [
+                            tri[0].2.unwrap_or(0),
+                            tri[1].2.unwrap_or(0),
+                            tri[2].2.unwrap_or(0),
+                        ],
+                        uvs: if has_uvs {
+                            Some([
+                                tri[0].1.unwrap(),
+                                tri[1].1.unwrap(),
+                                tri[2].1.unwrap(),
+                            ])
+                        } else {
+                            None
+                        },
+                        material,
+                        group,
+                    });
+                }
+            }
+            "g" | "o" => {
+                let name = args.get(0).copied().unwrap_or("default");
+                group =
+                    Some(match mesh.groups.iter().position(|g| g == name) {
+                        Some(i) => i,
+                        None => {
+                            mesh.groups.push(String::from(name));
+                            mesh.groups.len() - 1
+                        }
+                    });
+            }
+            "mtllib" => {
+                for file in &args {
+                    for (name, mtl) in load_mtl(&dir.join(file))? {
+                        material_ids.insert(name, mesh.materials.len());
+                        mesh.materials.push(mtl.to_material());
+                    }
+                }
+            }
+            "usemtl" => {
+                let name = args.get(0).copied().unwrap_or("");
+                material = Some(*material_ids.get(name).ok_or_else(|| {
+                    err(n, &format!("Unknown material {}", name))
+                })?);
+            }
+            // Smoothing groups, lines, curves, etc. aren't supported
+            _ => {}
+        }
+    }
+
+    mesh.add_vertex_normals(&faces_without_normals);
+    Ok(mesh)
+}
+
+fn load_mtl(path: &Path) -> Result<Vec<(String, MtlMaterial)>, String> {
+    let contents = fs::read_to_string(path)
+        .map_err(|e| format!("Failed to read {}: {}", path.display(), e))?;
+    let mut materials: Vec<(String, MtlMaterial)> = Vec::new();
+
+    for (n, line) in contents.lines().enumerate() {
+        let mut tokens = line.split_whitespace();
+        let keyword = match tokens.next() {
+            Some(k) if !k.starts_with('#') => k,
+            _ => continue,
+        };
+        let args: Vec<&str> = tokens.collect();
+
+        if keyword == "newmtl" {
+            let name = args.join(" ");
+            materials.push((name, MtlMaterial::default()));
+            continue;
+        }
+
+        let err =
+            || format!("{}:{}: Invalid {}", path.display(), n + 1, keyword);
+        let current = match materials.last_mut() {
+            Some((_, m)) => m,
+            None => continue,
+        };
+        let number = || args.get(0).and_then(|a| a.parse::<f32>().ok());
+
+        match keyword {
+            "Kd" => current.kd = parse_vector(&args).ok_or_else(err)?,
+            "Ks" => current.ks = parse_vector(&args).ok_or_else(err)?,
+            "Ke" => current.ke = parse_vector(&args).ok_or_else(err)?,
+            "Ns" => current.ns = number().ok_or_else(err)?,
+            "Ni" => current.ni = number().ok_or_else(err)?,
+            "d" => current.dissolve = number().ok_or_else(err)?,
+            "Tr" => current.dissolve = 1.0 - number().ok_or_else(err)?,
+            "illum" => current.illum = number().ok_or_else(err)? as u32,
+            // Texture maps and the remaining properties are ignored
+            _ => {}
+        }
+    }
+
+    Ok(materials)
+}
+
+fn parse_vector(args: &[&str]) -> Option<Vector> {
+    if args.len() < 3 {
+        return None;
+    }
+
+    Some(Vector::new(
+        args[0].parse().ok()?,
+        args[1].parse().ok()?,
+        args[2].parse().ok()?,
+    ))
+}
+
+/// Parses a face vertex in one of the forms `v`, `v/vt`, `v//vn` or
+/// `v/vt/vn` into zero based (position, uv, normal) indices
+fn parse_face_vertex(
+    vertex: &str,
+    mesh: &MeshData,
+) -> Option<(usize, Option<usize>, Option<usize>)> {
+    let mut parts = vertex.split('/');
+
+    let position = resolve_index(parts.next()?, mesh.positions.len())?;
+    let uv = match parts.next() {
+        Some(i) if !i.is_empty() => Some(resolve_index(i, mesh.uvs.len())?),
+        _ => None,
+    };
+    let normal = match parts.next() {
+        Some(i) if !i.is_empty() => Some(resolve_index(i, mesh.normals.len())?),
+        _ => None,
+    };
+
+    Some((position, uv, normal))
+}
+
+/// OBJ indices start at 1, and negative indices count back from the most
+/// recently declared element
+fn resolve_index(index: &str, len: usize) -> Option<usize> {
+    let index = index.parse::<isize>().ok()?;
+    let resolved = if index < 0 {
+        len as isize + index
+    } else {
+        index - 1
+    };
+
+    if resolved >= 0 && (resolved as usize) < len {
+        Some(resolved as usize)
+    } else {
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::path::PathBuf;
+
+    /// A directory of files for one test, which is removed when the test
+    /// ends whether it passed or not
+    struct TestDir(PathBuf);
+
+    impl TestDir {
+        fn new(test: &str) -> Self {
+            let name = format!("raytracer_obj_{}_{}", test, std::process::id());
+            let dir = std::env::temp_dir().join(name);
+            fs::create_dir_all(&dir).unwrap();
+
+            Self(dir)
+        }
+    }
+
+    impl Drop for TestDir {
+        fn drop(&mut self) {
+            let _ = fs::remove_dir_all(&self.0);
+        }
+    }
+
+    #[test]
+    fn small_mesh() {
+        let test_dir = TestDir::new("small_mesh");
+        let dir = &test_dir.0;
+        fs::write(dir.join("lamp.mtl"), "newmtl glow\nKe 1 1 1\n").unwrap();
+        fs::write(
+            dir.join("lamp.obj"),
+            "# A quad with every attribute, then a triangle with none\n\
+             mtllib lamp.mtl\n\
+             v 0 0 0\nv 1 0 0\nv 1 1 0\nv 0 1 0\n\
+             vt 0 0\nvt 1 0\nvt 1 1\n\
+             vn 0 0 1\n\
+             g panel\nusemtl glow\n\
+             f 1/1/1 2/2/1 3/3/1 4/1/1\n\
+             v 0 0 1\nv 1 0 1\nv 0 1 1\n\
+             g back\n\
+             f -3 -2 -1\n",
+        )
+        .unwrap();
+
+        let mesh = load_obj(&dir.join("lamp.obj")).unwrap();
+        assert_eq!(mesh.positions.len(), 7);
+        assert_eq!(mesh.materials.len(), 1);
+        assert_eq!(mesh.groups, ["panel", "back"]);
+        assert_eq!(mesh.faces.len(), 3);
+
+        // The quad is split into a fan around its first vertex
+        let (a, b) = (mesh.faces[0], mesh.faces[1]);
+        assert_eq!(a.positions, [0, 1, 2]);
+        assert_eq!(b.positions, [0, 2, 3]);
+        assert_eq!(b.uvs, Some([0, 2, 0]));
+        assert_eq!(b.normals, [0, 0, 0]);
+        assert_eq!((b.material, b.group), (Some(0), Some(0)));
+
+        // Negative indices count back from the last vertex, and missing
+        // normals are computed from the faces
+        let c = mesh.faces[2];
+        assert_eq!(c.positions, [4, 5, 6]);
+        assert_eq!(c.uvs, None);
+        assert_eq!((c.material, c.group), (Some(0), Some(1)));
+        for &n in &c.normals {
+            let normal = mesh.normals[n];
+            assert_eq!((normal.x, normal.y, normal.z), (0.0, 0.0, 1.0));
+        }
+    }
+
+    #[test]
+    fn invalid_index() {
+        let test_dir = TestDir::new("invalid_index");
+        let dir = &test_dir.0;
+        fs::write(dir.join("invalid.obj"), "v 0 0 0\nv 1 0 0\nf 1 2 3\n")
+            .unwrap();
+
+        let err = load_obj(&dir.join("invalid.obj")).unwrap_err();
+        assert!(err.ends_with(":3: Invalid face vertex"), "{}", err);
+    }
+}
@@ -0,0 +1,551 @@
+//! Stanford PLY loading, in either the ASCII or binary little endian
+//! encoding. Vertex positions, normals, texture coordinates and colors
+//! are read, and any other elements or properties are skipped.
+use super::{Face, MeshData};
+use crate::materials::Lambertian;
+use crate::textures::ConstantTexture;
+use crate::Vector;
+
+use std::collections::HashMap;
+use std::fs;
+use std::path::Path;
+
+#[derive(Debug, Copy, Clone, PartialEq)]
+enum Encoding {
+    Ascii,
+    BinaryLittleEndian,
+}
+
+#[derive(Debug, Copy, Clone)]
+enum ScalarType {
+    I8,
+    U8,
+    I16,
+    U16,
+    I32,
+    U32,
+    F32,
+    F64,
+}
+
+#[derive(Debug)]
+enum Property {
+    Scalar(String, ScalarType),
+    /// A list property, with the type of the length and of the items
+    List(String, ScalarType, ScalarType),
+}
+
+#[derive(Debug)]
+struct Element {
+    name: String,
+    count: usize,
+    properties: Vec<Property>,
+}
+
+/// Reads values from the body of the file in either encoding
+struct Reader<'a> {
+    encoding: Encoding,
+    bytes: &'a [u8],
+    pos: usize,
+}
+
+impl ScalarType {
+    fn parse(name: &str) -> Option<Self> {
+        Some(match name {
+            "char" | "int8" => ScalarType::I8,
+            "uchar" | "uint8" => ScalarType::U8,
+            "short" | "int16" => ScalarType::I16,
+            "ushort" | "uint16" => ScalarType::U16,
+            "int" | "int32" => ScalarType::I32,
+            "uint" | "uint32" => ScalarType::U32,
+            "float" | "float32" => ScalarType::F32,
+            "double" | "float64" => ScalarType::F64,
+            _ => return None,
+        })
+    }
+
+    fn size(self) -> usize {
+        match self {
+            ScalarType::I8 | ScalarType::U8 => 1,
+            ScalarType::I16 | ScalarType::U16 => 2,
+            ScalarType::I32 | ScalarType::U32 | ScalarType::F32 => 4,
+            ScalarType::F64 => 8,
+        }
+    }
+}
+
+impl<'a> Reader<'a> {
+    fn read(&mut self, ty: ScalarType) -> Result<f64, String> {
+        match self.encoding {
+            Encoding::Ascii => self.read_ascii(),
+            Encoding::BinaryLittleEndian => self.read_binary(ty),
+        }
+    }
+
+    /// Reads a count or a vertex index, which must be a whole number that
+    /// isn't negative
+    fn read_index(&mut self, ty: ScalarType) -> Result<usize, String> {
+        let value = self.read(ty)?;
+
+        if value >= 0.0 && value.fract() == 0.0 && value <= usize::MAX as f64 {
+            Ok(value as usize)
+        } else {
+            Err(format!("Invalid index {}", value))
+        }
+    }
+
+    fn read_ascii(&mut self) -> Result<f64, String> {
+        let rest = &self.bytes[self.pos..];
+        let start = rest
+            .iter()
+            .position(|b| !b.is_ascii_whitespace())
+            .ok_or("Unexpected end of file")?;
+        let len = rest[start..]
+            .iter()
+            .position(|b| b.is_ascii_whitespace())
+            .unwrap_or(rest.len() - start);
+
+        self.pos += start + len;
+
+        std::str::from_utf8(&rest[start..start + len])
+            .ok()
+            .and_then(|token| token.parse().ok())
+            .ok_or_else(|| String::from("Invalid number"))
+    }
+
+    fn read_binary(&mut self, ty: ScalarType) -> Result<f64, String> {
+        let size = ty.size();
+        let b = self
+            .bytes
+            .get(self.pos..self.pos + size)
+            .ok_or("Unexpected end of file")?;
+        self.pos += size;
+
+        let mut buf = [0; 8];
+        buf[..size].copy_from_slice(b);
+
+        Ok(match ty {
+            ScalarType::I8 => b[0] as i8 as f64,
+            ScalarType::U8 => b[0] as f64,
+            ScalarType::I16 => i16::from_le_bytes([b[0], b[1]]) as f64,
+            ScalarType::U16 => u16::from_le_bytes([b[0], b[1]]) as f64,
+            ScalarType::I32 => {
+                i32::from_le_bytes([b[0], b[1], b[2], b[3]]) as f64
+            }
+            ScalarType::U32 => {
+                u32::from_le_bytes([b[0], b[1], b[2], b[3]]) as f64
+            }
+            ScalarType::F32 => {
+                f32::from_le_bytes([b[0], b[1], b[2], b[3]]) as f64
+            }
+            ScalarType::F64 => f64::from_le_bytes(buf),
+        })
+    }
+}
+
+pub fn load_ply(path: &Path) -> Result<MeshData, String> {
+    let bytes = fs::read(path)
+        .map_err(|e| format!("Failed to read {}: {}", path.display(), e))?;
+
+    parse(&bytes).map_err(|e| format!("{}: {}", path.display(), e))
+}
+
+fn parse(bytes: &[u8]) -> Result<MeshData, String> {
+    let (encoding, elements, body_start) = parse_header(bytes)?;
+    let mut reader = Reader {
+        encoding,
+        bytes,
+        pos: body_start,
+    };
+
+    let mut mesh = MeshData::default();
+    let mut colors = Vec::new();
+    let mut has_normals = false;
+    let mut has_uvs = false;
+
+    for element in &elements {
+        for _ in 0..element.count {
+            match element.name.as_str() {
+                "vertex" => {
+                    let mut values = HashMap::new();
+                    for property in &element.properties {
+                        read_property(&mut reader, property, &mut values)?;
+                    }
+
+                    let get = |names: &[&str]| {
+                        names.iter().find_map(|n| values.get(*n)).copied()
+                    };
+                    let vector = |x, y, z| match (get(x), get(y), get(z)) {
+                        (Some(x), Some(y), Some(z)) => {
+                            Some(Vector::new(x as f32, y as f32, z as f32))
+                        }
+                        _ => None,
+                    };
+
+                    mesh.positions.push(
+                        vector(&["x"], &["y"], &["z"])
+                            .ok_or("Vertices need x, y and z properties")?,
+                    );
+
+                    if let Some(n) = vector(&["nx"], &["ny"], &["nz"]) {
+                        mesh.normals.push(n);
+                        has_normals = true;
+                    }
+
+                    let u = get(&["u", "s", "texture_u", "texture_s"]);
+                    let v = get(&["v", "t", "texture_v", "texture_t"]);
+                    if let (Some(u), Some(v)) = (u, v) {
+                        mesh.uvs.push((u as f32, v as f32));
+                        has_uvs = true;
+                    }
+
+                    if let Some(c) = vector(&["red"], &["green"], &["blue"]) {
+                        colors.push(c);
+                    }
+                }
+                "face" => {
+                    let mut values = HashMap::new();
+                    let mut indices = Vec::new();
+
+                    for property in &element.properties {
+                        match property {
+                            Property::List(name, len_ty, item_ty)
+                                if name == "vertex_indices"
+                                    || name == "vertex_index" =>
+                            {
+                                let len = reader.read_index(*len_ty)?;
+                                for _ in 0..len {
+                                    indices.push(reader.read_index(*item_ty)?);
+                                }
+                            }
+                            _ => read_property(
+                                &mut reader,
+                                property,
+                                &mut values,
+                            )?,
+                        }
+                    }
+
+                    add_polygon(&mut mesh, &indices)?;
+                }
+                _ => {
+                    let mut values = HashMap::new();
+                    for property in &element.properties {
+                        read_property(&mut reader, property, &mut values)?;
+                    }
+                }
+            }
+        }
+    }
+
+    let vertices = mesh.positions.len();
+    if (has_normals && mesh.normals.len() != vertices)
+        || (has_uvs && mesh.uvs.len() != vertices)
+    {
+        return Err(String::from("Vertex attributes are only partly given"));
+    }
+
+    if !has_uvs {
+        for face in &mut mesh.faces {
+            face.uvs = None;
+        }
+    }
+
+    if colors.len() == vertices {
+        add_color_materials(&mut mesh, &colors, color_scale(&elements));
+    }
+
+    // The vertex normals share the position indices
+    if !has_normals {
+        let faces: Vec<usize> = (0..mesh.faces.len()).collect();
+        mesh.add_vertex_normals(&faces);
+    }
+
+    Ok(mesh)
+}
+
+/// Parses the header, returning the encoding, the elements and the offset
+/// of the first byte of the body
+fn parse_header(
+    bytes: &[u8],
+) -> Result<(Encoding, Vec<Element>, usize), String> {
+    let mut encoding = None;
+    let mut elements: Vec<Element> = Vec::new();
+    let mut pos = 0;
+
+    loop {
+        let end = bytes[pos..]
+            .iter()
+            .position(|&b| b == b'\n')
+            .ok_or("Missing end_header")?;
+        let line = std::str::from_utf8(&bytes[pos..pos + end])
+            .map_err(|_| "Invalid header")?
+            .trim();
+        pos += end + 1;
+
+        let tokens: Vec<&str> = line.split_whitespace().collect();
+        if let Some(&"comment") | Some(&"obj_info") = tokens.first() {
+            continue;
+        }
+
+        match tokens.as_slice() {
+            ["ply"] | [] => {}
+            ["format", "ascii", _] => encoding = Some(Encoding::Ascii),
+            ["format", "binary_little_endian", _] => {
+                encoding = Some(Encoding::BinaryLittleEndian)
+            }
+            ["format", other, _] => {
+                return Err(format!("Unsupported format {}", other));
+            }
+            ["element", name, count] => elements.push(Element {
+                name: String::from(*name),
+                count: count.parse().map_err(|_| "Invalid element count")?,
+                properties: Vec::new(),
+            }),
+            ["property", "list", len_ty, item_ty, name] => {
+                let property = Property::List(
+                    String::from(*name),
+                    ScalarType::parse(len_ty).ok_or("Invalid list type")?,
+                    ScalarType::parse(item_ty).ok_or("Invalid list type")?,
+                );
+
+                elements
+                    .last_mut()
+                    .ok_or("Property before any element")?
+                    .properties
+                    .push(property);
+            }
+            ["property", ty, name] => {
+                let property = Property::Scalar(
+                    String::from(*name),
+                    ScalarType::parse(ty).ok_or("Invalid property type")?,
+                );
+
+                elements
+                    .last_mut()
+                    .ok_or("Property before any element")?
+                    .properties
+                    .push(property);
+            }
+            ["end_header"] => break,
+            _ => return Err(format!("Invalid header line: {}", line)),
+        }
+    }
+
+    let encoding = encoding.ok_or("Missing format")?;
+    Ok((encoding, elements, pos))
+}
+
+/// Reads a property, storing it by name if it's a scalar
+fn read_property(
+    reader: &mut Reader,
+    property: &Property,
+    values: &mut HashMap<String, f64>,
+) -> Result<(), String> {
+    match property {
+        Property::Scalar(name, ty) => {
+            values.insert(name.clone(), reader.read(*ty)?);
+        }
+        Property::List(_, len_ty, item_ty) => {
+            let len = reader.read(*len_ty)? as usize;
+            for _ in 0..len {
+                reader.read(*item_ty)?;
+            }
+        }
+    }
+
+    Ok(())
+}
+
+/// Splits a polygon into a fan of triangles. Every vertex attribute uses
+/// the same index as the position.
+fn add_polygon(mesh: &mut MeshData, indices: &[usize]) -> Result<(), String> {
+    if indices.len() < 3 {
+        return Err(String::from("Faces need at least 3 vertices"));
+    }
+
+    if indices.iter().any(|&i| i >= mesh.positions.len()) {
+        return Err(String::from("Invalid vertex index"));
+    }
+
+    for i in 1..indices.len() - 1 {
+        let tri = [indices[0], indices[i], indices[i + 1]];
+        mesh.faces.push(Face {
+            positions: tri,
+            normals: tri,
+            uvs: Some(tri),
+            material: None,
+            group: None,
+        });
+    }
+
+    Ok(())
+}
+
+/// Colors stored as integers range from 0 to 255, floats from 0 to 1
+fn color_scale(elements: &[Element]) -> f32 {
+    let red = elements
+        .iter()
+        .filter(|e| e.name == "vertex")
+        .flat_map(|e| &e.properties)
+        .find_map(|p| match p {
+            Property::Scalar(name, ty) if name == "red" => Some(*ty),
+            _ => None,
+        });
+
+    match red {
+        Some(ScalarType::F32) | Some(ScalarType::F64) => 1.0,
+        _ => 1.0 / 255.0,
+    }
+}
+
+/// Gives each face a diffuse material with the average color of its
+/// vertices. Faces with the same color share a material.
+fn add_color_materials(mesh: &mut MeshData, colors: &[Vector], scale: f32) {
+    let mut ids: HashMap<[u8; 3], usize> = HashMap::new();
+
+    for face in &mut mesh.faces {
+        let [i0, i1, i2] = face.positions;
+        let color = (colors[i0] + colors[i1] + colors[i2]) * (scale / 3.0);
+
+        // Quantizing the colors keeps the number of materials bounded
+        let key = [
+            (color.x.max(0.0).min(1.0) * 255.0).round() as u8,
+            (color.y.max(0.0).min(1.0) * 255.0).round() as u8,
+            (color.z.max(0.0).min(1.0) * 255.0).round() as u8,
+        ];
+
+        let materials = &mut mesh.materials;
+        let id = *ids.entry(key).or_insert_with(|| {
+            let albedo = Vector::new(
+                key[0] as f32 / 255.0,
+                key[1] as f32 / 255.0,
+                key[2] as f32 / 255.0,
+            );
+
+            materials.push(Box::new(Lambertian::new(Box::new(
+                ConstantTexture::new(albedo),
+            ))));
+            materials.len() - 1
+        });
+
+        face.material = Some(id);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn ascii_quad() {
+        let bytes = b"ply\n\
+            format ascii 1.0\n\
+            comment A red quad\n\
+            element vertex 4\n\
+            property float x\nproperty float y\nproperty float z\n\
+            property float u\nproperty float v\n\
+            property uchar red\nproperty uchar green\nproperty uchar blue\n\
+            element face 1\n\
+            property list uchar int vertex_indices\n\
+            element edge 1\n\
+            property int vertex1\nproperty int vertex2\n\
+            end_header\n\
+            0 0 0 0 0 255 0 0\n\
+            1 0 0 1 0 255 0 0\n\
+            1 1 0 1 1 255 0 0\n\
+            0 1 0 0 1 255 0 0\n\
+            4 0 1 2 3\n\
+            0 2\n";
+
+        let mesh = parse(bytes).unwrap();
+        assert_eq!(mesh.positions.len(), 4);
+        assert_eq!(mesh.uvs[2], (1.0, 1.0));
+        assert_eq!(mesh.faces.len(), 2);
+        assert_eq!(mesh.faces[0].positions, [0, 1, 2]);
+        assert_eq!(mesh.faces[1].positions, [0, 2, 3]);
+        assert_eq!(mesh.faces[1].uvs, Some([0, 2, 3]));
+
+        // Both triangles have the same color, so they share a material
+        assert_eq!(mesh.materials.len(), 1);
+        assert_eq!(mesh.faces[0].material, Some(0));
+        assert_eq!(mesh.faces[1].material, Some(0));
+
+        // Without normals in the file they're computed for each vertex
+        for &n in &mesh.faces[0].normals {
+            let normal = mesh.normals[n];
+            assert_eq!((normal.x, normal.y, normal.z), (0.0, 0.0, 1.0));
+        }
+    }
+
+    #[test]
+    fn binary_triangle() {
+        let mut bytes = b"ply\n\
+            format binary_little_endian 1.0\n\
+            element vertex 3\n\
+            property float x\nproperty float y\nproperty float z\n\
+            property float nx\nproperty float ny\nproperty float nz\n\
+            element face 1\n\
+            property list uchar uint vertex_indices\n\
+            end_header\n"
+            .to_vec();
+        for position in &[[0.0f32, 0.0, 0.0], [0.0, 0.0, 1.0], [1.0, 0.0, 0.0]]
+        {
+            for &x in position.iter().chain(&[0.0, 1.0, 0.0]) {
+                bytes.extend(&x.to_le_bytes());
+            }
+        }
+        bytes.push(3);
+        for &i in &[0u32, 1, 2] {
+            bytes.extend(&i.to_le_bytes());
+        }
+
+        let mesh = parse(&bytes).unwrap();
+        assert_eq!(mesh.positions.len(), 3);
+        assert_eq!(mesh.positions[1].z, 1.0);
+        assert_eq!(mesh.normals.len(), 3);
+        assert_eq!(mesh.normals[2].y, 1.0);
+        assert_eq!(mesh.faces.len(), 1);
+        assert_eq!(mesh.faces[0].positions, [0, 1, 2]);
+        assert_eq!(mesh.faces[0].normals, [0, 1, 2]);
+        assert_eq!(mesh.faces[0].uvs, None);
+        assert!(mesh.materials.is_empty());
+    }
+
+    #[test]
+    fn invalid_index() {
+        let bytes = b"ply\n\
+            format ascii 1.0\n\
+            element vertex 1\n\
+            property float x\nproperty float y\nproperty float z\n\
+            element face 1\n\
+            property list uchar int vertex_indices\n\
+            end_header\n\
+            0 0 0\n\
+            3 0 1 2\n";
+
+        assert_eq!(parse(bytes).unwrap_err(), "Invalid vertex index");
+    }
+
+    #[test]
+    fn negative_and_fractional_indices() {
+        for face in &["3 0 -1 2", "3 0 0.5 2"] {
+            let bytes = format!(
+                "ply\n\
+                format ascii 1.0\n\
+                element vertex 3\n\
+                property float x\nproperty float y\nproperty float z\n\
+                element face 1\n\
+                property list uchar float vertex_indices\n\
+                end_header\n\
+                0 0 0\n0 0 1\n1 0 0\n\
+                {}\n",
+                face
+            );
+
+            assert!(parse(bytes.as_bytes())
+                .unwrap_err()
+                .starts_with("Invalid index"));
+        }
+    }
+}
@@ -0,0 +1,284 @@
+//! PNG decoding for textures. Every color type and bit depth is supported,
+//! but interlaced images aren't.
+use super::inflate::inflate_zlib;
+
+const SIGNATURE: [u8; 8] = [137, 80, 78, 71, 13, 10, 26, 10];
+
+/// A decoded image with 8 bit RGBA pixels, stored row by row from the top
+#[derive(Debug)]
+pub struct Bitmap {
+    pub width: usize,
+    pub height: usize,
+    pub pixels: Vec<[u8; 4]>,
+}
+
+pub fn decode_png(bytes: &[u8]) -> Result<Bitmap, String> {
+    if !bytes.starts_with(&SIGNATURE) {
+        return Err(String::from("Not a PNG image"));
+    }
+
+    let mut header = None;
+    let mut palette: Vec<[u8; 4]> = Vec::new();
+    let mut data = Vec::new();
+    let mut pos = SIGNATURE.len();
+
+    while pos + 8 <= bytes.len() {
+        let len = u32::from_be_bytes([
+            bytes[pos],
+            bytes[pos + 1],
+            bytes[pos + 2],
+            bytes[pos + 3],
+        ]) as usize;
+        let kind = &bytes[pos + 4..pos + 8];
+        let chunk = bytes
+            .get(pos + 8..pos + 8 + len)
+            .ok_or("Truncated PNG chunk")?;
+        // Skip the chunk's CRC as well
+        pos += 12 + len;
+
+        match kind {
+            b"IHDR" => {
+                if chunk.len() != 13 {
+                    return Err(String::from("Invalid PNG header"));
+                }
+
+                let int = |i: usize| {
+                    u32::from_be_bytes([
+                        chunk[i],
+                        chunk[i + 1],
+                        chunk[i + 2],
+                        chunk[i + 3],
+                    ]) as usize
+                };
+
+                if chunk[12] != 0 {
+                    return Err(String::from(
+                        "Interlaced PNG images aren't supported",
+                    ));
+                }
+
+                header = Some((int(0), int(4), chunk[8], chunk[9]));
+            }
+            b"PLTE" => {
+                if chunk.len() % 3 != 0 {
+                    return Err(String::from("Invalid PNG palette"));
+                }
+
+                palette =
+                    chunk.chunks(3).map(|c| [c[0], c[1], c[2], 255]).collect();
+            }
+            b"tRNS" => {
+                for (entry, &alpha) in palette.iter_mut().zip(chunk) {
+                    entry[3] = alpha;
+                }
+            }
+            b"IDAT" => data.extend_from_slice(chunk),
+            b"IEND" => break,
+            _ => {}
+        }
+    }
+
+    let (width, height, depth, color_type) =
+        header.ok_or("Missing PNG header")?;
+    if width == 0 || height == 0 {
+        return Err(String::from("PNG image is empty"));
+    }
+
+    let channels = match color_type {
+        0 | 3 => 1,
+        2 => 3,
+        4 => 2,
+        6 => 4,
+        _ => return Err(format!("Invalid PNG color type {}", color_type)),
+    };
+
+    match depth {
+        1 | 2 | 4 | 8 | 16 => {}
+        _ => return Err(format!("Invalid PNG bit depth {}", depth)),
+    }
+
+    let data = inflate_zlib(&data)?;
+    let bits_per_pixel = channels * depth as usize;
+    let stride = (width * bits_per_pixel + 7) / 8;
+    let rows = unfilter(&data, stride, height, (bits_per_pixel + 7) / 8)?;
+
+    // Samples are scaled to 8 bits. 16 bit samples just keep their most
+    // significant byte.
+    let sample = |row: &[u8], index: usize| -> u8 {
+        match depth {
+            8 => row[index],
+            16 => row[index * 2],
+            _ => {
+                let depth = depth as usize;
+                let bit = index * depth;
+                let max = (1 << depth) - 1;
+                let value = (row[bit / 8] >> (8 - depth - bit % 8)) & max;
+
+                if color_type == 3 {
+                    value
+                } else {
+                    (value as usize * 255 / max as usize) as u8
+                }
+            }
+        }
+    };
+
+    let mut pixels = Vec::with_capacity(width * height);
+    for row in rows.chunks(stride) {
+        for x in 0..width {
+            let s = |c: usize| sample(row, x * channels + c);
+
+            pixels.push(match color_type {
+                0 => [s(0), s(0), s(0), 255],
+                2 => [s(0), s(1), s(2), 255],
+                3 => *palette
+                    .get(s(0) as usize)
+                    .ok_or("PNG palette index out of range")?,
+                4 => [s(0), s(0), s(0), s(1)],
+                _ => [s(0), s(1), s(2), s(3)],
+            });
+        }
+    }
+
+    Ok(Bitmap {
+        width,
+        height,
+        pixels,
+    })
+}
+
+/// Reverses the filter applied to each row, returning the raw rows without
+/// their filter type bytes
+fn unfilter(
+    data: &[u8],
+    stride: usize,
+    height: usize,
+    bytes_per_pixel: usize,
+) -> Result<Vec<u8>, String> {
+    if data.len() < (stride + 1) * height {
+        return Err(String::from("Not enough PNG image data"));
+    }
+
+    let mut out = vec![0; stride * height];
+
+    for y in 0..height {
+        let filter = data[y * (stride + 1)];
+        let src = &data[y * (stride + 1) + 1..(y + 1) * (stride + 1)];
+        let (before, after) = out.split_at_mut(y * stride);
+        let row = &mut after[..stride];
+        let up = if y == 0 {
+            None
+        } else {
+            Some(&before[(y - 1) * stride..])
+        };
+
+        for x in 0..stride {
+            let a = if x >= bytes_per_pixel {
+                row[x - bytes_per_pixel]
+            } else {
+                0
+            };
+            let b = up.map_or(0, |up| up[x]);
+            let c = match up {
+                Some(up) if x >= bytes_per_pixel => up[x - bytes_per_pixel],
+                _ => 0,
+            };
+
+            row[x] = src[x].wrapping_add(match filter {
+                0 => 0,
+                1 => a,
+                2 => b,
+                3 => ((a as u16 + b as u16) / 2) as u8,
+                4 => paeth(a, b, c),
+                _ => return Err(format!("Invalid PNG filter {}", filter)),
+            });
+        }
+    }
+
+    Ok(out)
+}
+
+fn paeth(a: u8, b: u8, c: u8) -> u8 {
+    let p = a as i16 + b as i16 - c as i16;
+    let pa = (p - a as i16).abs();
+    let pb = (p - b as i16).abs();
+    let pc = (p - c as i16).abs();
+
+    if pa <= pb && pa <= pc {
+        a
+    } else if pb <= pc {
+        b
+    } else {
+        c
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Three rows of two RGB pixels, which each test filters a different way
+    const PIXELS: [u8; 18] = [
+        10, 20, 30, 40, 50, 60, 15, 5, 200, 100, 7, 90, 255, 0, 128, 3, 250, 64,
+    ];
+
+    fn check(filtered: &[u8]) {
+        assert_eq!(unfilter(filtered, 6, 3, 3).unwrap(), &PIXELS[..]);
+    }
+
+    #[test]
+    fn no_filter() {
+        check(&[
+            0, 10, 20, 30, 40, 50, 60, 0, 15, 5, 200, 100, 7, 90, 0, 255, 0,
+            128, 3, 250, 64,
+        ]);
+    }
+
+    #[test]
+    fn sub_filter() {
+        check(&[
+            1, 10, 20, 30, 30, 30, 30, 1, 15, 5, 200, 85, 2, 146, 1, 255, 0,
+            128, 4, 250, 192,
+        ]);
+    }
+
+    #[test]
+    fn up_filter() {
+        check(&[
+            2, 10, 20, 30, 40, 50, 60, 2, 5, 241, 170, 60, 213, 30, 2, 240,
+            251, 184, 159, 243, 230,
+        ]);
+    }
+
+    #[test]
+    fn average_filter() {
+        check(&[
+            3, 10, 20, 30, 35, 40, 45, 3, 10, 251, 185, 73, 236, 216, 3, 248,
+            254, 28, 82, 247, 211,
+        ]);
+    }
+
+    #[test]
+    fn paeth_filter() {
+        check(&[
+            4, 10, 20, 30, 30, 30, 30, 4, 5, 241, 170, 60, 213, 146, 4, 240,
+            251, 184, 4, 250, 230,
+        ]);
+    }
+
+    #[test]
+    fn invalid_filter() {
+        assert!(unfilter(&[5, 0, 0, 0], 3, 1, 3).is_err());
+    }
+
+    #[test]
+    fn invalid_palette() {
+        let mut bytes = SIGNATURE.to_vec();
+        // A palette chunk with a partial entry, followed by a zeroed CRC
+        bytes.extend_from_slice(&[0, 0, 0, 4]);
+        bytes.extend_from_slice(b"PLTE");
+        bytes.extend_from_slice(&[255, 0, 0, 255, 0, 0, 0, 0]);
+
+        assert_eq!(decode_png(&bytes).unwrap_err(), "Invalid PNG palette");
+    }
+}
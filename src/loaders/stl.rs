@@ -0,0 +1,139 @@
+//! Binary STL loading. STL files store each triangle separately with its
+//! facet normal, so the faces are flat shaded and share no vertices.
+use super::{Face, MeshData};
+use crate::Vector;
+
+use std::fs;
+use std::path::Path;
+
+const HEADER_SIZE: usize = 80;
+const TRIANGLE_SIZE: usize = 50;
+
+pub fn load_stl(path: &Path) -> Result<MeshData, String> {
+    let bytes = fs::read(path)
+        .map_err(|e| format!("Failed to read {}: {}", path.display(), e))?;
+
+    parse(&bytes).map_err(|e| format!("{}: {}", path.display(), e))
+}
+
+fn parse(bytes: &[u8]) -> Result<MeshData, String> {
+    let count = match bytes.get(HEADER_SIZE..HEADER_SIZE + 4) {
+        Some(b) => u32::from_le_bytes([b[0], b[1], b[2], b[3]]) as usize,
+        None => return Err(String::from("Missing triangle count")),
+    };
+
+    // ASCII files also begin with a header, so the size is the only
+    // reliable way to tell them apart
+    if bytes.len() != HEADER_SIZE + 4 + count * TRIANGLE_SIZE {
+        return Err(String::from(
+            "File size doesn't match its triangle count, only binary STL \
+             files are supported",
+        ));
+    }
+
+    let mut mesh = MeshData::default();
+    let mut faces_without_normals = Vec::new();
+
+    for triangle in bytes[HEADER_SIZE + 4..].chunks(TRIANGLE_SIZE) {
+        let vector = |i: usize| {
+            let float = |j: usize| {
+                let b = &triangle[i * 12 + j * 4..];
+                f32::from_le_bytes([b[0], b[1], b[2], b[3]])
+            };
+
+            Vector::new(float(0), float(1), float(2))
+        };
+
+        let offset = mesh.positions.len();
+        let positions = [offset, offset + 1, offset + 2];
+        mesh.positions.extend(&[vector(1), vector(2), vector(3)]);
+
+        // Many exporters leave the facet normal zeroed, in which case it's
+        // computed from the vertices instead
+        let normal = vector(0);
+        let length = normal.length();
+        let normals = if length > 0.0 {
+            mesh.normals.push(normal / length);
+            let n = mesh.normals.len() - 1;
+            [n, n, n]
+        } else {
+            faces_without_normals.push(mesh.faces.len());
+            [0, 0, 0]
+        };
+
+        mesh.faces.push(Face {
+            positions,
+            normals,
+            uvs: None,
+            material: None,
+            group: None,
+        });
+    }
+
+    mesh.add_vertex_normals(&faces_without_normals);
+    Ok(mesh)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A binary STL file with the given facet normals and vertices
+    fn stl(triangles: &[[[f32; 3]; 4]]) -> Vec<u8> {
+        let mut bytes = vec![0; HEADER_SIZE];
+        bytes.extend(&(triangles.len() as u32).to_le_bytes());
+
+        for triangle in triangles {
+            for &x in triangle.iter().flatten() {
+                bytes.extend(&x.to_le_bytes());
+            }
+            bytes.extend(&[0, 0]);
+        }
+
+        bytes
+    }
+
+    #[test]
+    fn two_triangles() {
+        let bytes = stl(&[
+            [
+                [0.0, 0.0, 2.0],
+                [0.0, 0.0, 0.0],
+                [1.0, 0.0, 0.0],
+                [0.0, 1.0, 0.0],
+            ],
+            // The second triangle's normal is left out
+            [
+                [0.0, 0.0, 0.0],
+                [0.0, 0.0, 0.0],
+                [0.0, 1.0, 0.0],
+                [1.0, 0.0, 0.0],
+            ],
+        ]);
+
+        let mesh = parse(&bytes).unwrap();
+        assert_eq!(mesh.positions.len(), 6);
+        assert_eq!(mesh.faces.len(), 2);
+        assert_eq!(mesh.faces[0].positions, [0, 1, 2]);
+        assert_eq!(mesh.faces[1].positions, [3, 4, 5]);
+
+        // Facet normals are normalized and shared by the face's vertices
+        assert_eq!(mesh.faces[0].normals, [0, 0, 0]);
+        assert_eq!(mesh.normals[0].z, 1.0);
+
+        // Missing normals are computed from the winding of the vertices
+        for &n in &mesh.faces[1].normals {
+            let normal = mesh.normals[n];
+            assert_eq!((normal.x, normal.y, normal.z), (0.0, 0.0, -1.0));
+        }
+    }
+
+    #[test]
+    fn wrong_size() {
+        let mut bytes = stl(&[[[0.0; 3]; 4]]);
+        bytes.pop();
+
+        assert!(parse(&bytes).is_err());
+        assert!(parse(&bytes[..HEADER_SIZE]).is_err());
+    }
+}
@@ -0,0 +1,152 @@
+//! Loading of voxel grids in Mitsuba's `.vol` format, which is simple to
+//! write from simulation tools. All values are little endian:
+//!
+//! | Bytes | Contents                                                     |
+//! |-------|--------------------------------------------------------------|
+//! | 3     | The characters `VOL`                                         |
+//! | 1     | The version, which is 3                                      |
+//! | 4     | The encoding, 1 for 32 bit floats or 3 for 8 bit integers    |
+//! | 12    | The number of voxels along X, Y and Z as 32 bit integers     |
+//! | 4     | The number of channels, of which only the first is used      |
+//! | 24    | The minimum and maximum corners of the grid as 32 bit floats |
+//! | ...   | The voxels, with X changing fastest and then Y               |
+//!
+//! 8 bit values are scaled to lie between 0 and 1. The voxels sit on the
+//! corners of a lattice spanning the grid's bounds, so the first and last
+//! voxels along each axis lie on the bounds.
+use crate::Vector;
+
+use std::fs;
+use std::path::Path;
+
+const HEADER_SIZE: usize = 48;
+
+/// A grid of values spanning a box, such as the density of smoke
+#[derive(Debug)]
+pub struct VoxelGrid {
+    /// The number of voxels along each axis
+    pub resolution: [usize; 3],
+    pub min: Vector,
+    pub max: Vector,
+    pub values: Vec<f32>,
+}
+
+pub fn load_vol(path: &Path) -> Result<VoxelGrid, String> {
+    let bytes = fs::read(path)
+        .map_err(|e| format!("Failed to read {}: {}", path.display(), e))?;
+
+    parse(&bytes).map_err(|e| format!("{}: {}", path.display(), e))
+}
+
+fn parse(bytes: &[u8]) -> Result<VoxelGrid, String> {
+    if bytes.len() < HEADER_SIZE || &bytes[..3] != b"VOL" {
+        return Err(String::from("Not a VOL file"));
+    }
+    if bytes[3] != 3 {
+        return Err(format!("Unsupported VOL version {}", bytes[3]));
+    }
+
+    let word = |i: usize| [bytes[i], bytes[i + 1], bytes[i + 2], bytes[i + 3]];
+    let int = |i: usize| u32::from_le_bytes(word(i)) as usize;
+    let float = |i: usize| f32::from_le_bytes(word(i));
+
+    let encoding = int(4);
+    let resolution = [int(8), int(12), int(16)];
+    let channels = int(20);
+    let min = Vector::new(float(24), float(28), float(32));
+    let max = Vector::new(float(36), float(40), float(44));
+
+    // Values are interpolated between neighbouring voxels, so there have
+    // to be at least two along each axis
+    if resolution.iter().any(|&n| n < 2) || channels == 0 {
+        return Err(String::from(
+            "Grids need at least two voxels along each axis",
+        ));
+    }
+    if (0..3).any(|a| !(max[a] > min[a])) {
+        return Err(String::from("Grid bounds are empty"));
+    }
+
+    let value_size = match encoding {
+        1 => 4,
+        3 => 1,
+        _ => return Err(format!("Unsupported VOL encoding {}", encoding)),
+    };
+
+    let count = resolution[0] * resolution[1] * resolution[2];
+    let data = &bytes[HEADER_SIZE..];
+    if data.len() != count * channels * value_size {
+        return Err(String::from("File size doesn't match the grid size"));
+    }
+
+    let values = data
+        .chunks(channels * value_size)
+        .map(|voxel| match encoding {
+            1 => f32::from_le_bytes([voxel[0], voxel[1], voxel[2], voxel[3]]),
+            _ => voxel[0] as f32 / 255.0,
+        })
+        .collect();
+
+    Ok(VoxelGrid {
+        resolution,
+        min,
+        max,
+        values,
+    })
+}
+
+impl VoxelGrid {
+    pub fn value(&self, x: usize, y: usize, z: usize) -> f32 {
+        let [nx, ny, _] = self.resolution;
+        self.values[(z * ny + y) * nx + x]
+    }
+
+    /// Converts a point to continuous voxel coordinates, where the voxel
+    /// (x, y, z) lies at the point (x, y, z)
+    pub fn voxel_coords(&self, p: Vector) -> Vector {
+        let mut coords = Vector::zeros();
+        for a in 0..3 {
+            let extent = self.max[a] - self.min[a];
+            coords[a] =
+                (p[a] - self.min[a]) / extent * (self.resolution[a] - 1) as f32;
+        }
+
+        coords
+    }
+
+    /// Trilinearly interpolates the value at a point, which is zero outside
+    /// the grid's bounds
+    pub fn sample(&self, p: Vector) -> f32 {
+        let coords = self.voxel_coords(p);
+        let mut base = [0; 3];
+        let mut frac = [0.0; 3];
+
+        for a in 0..3 {
+            let last = (self.resolution[a] - 1) as f32;
+            if !(coords[a] >= 0.0 && coords[a] <= last) {
+                return 0.0;
+            }
+
+            // Points on the far bounds use the last pair of voxels
+            let floor = f32::min(coords[a].floor(), last - 1.0);
+            base[a] = floor as usize;
+            frac[a] = coords[a] - floor;
+        }
+
+        let mut value = 0.0;
+        for corner in 0..8 {
+            let mut weight = 1.0;
+            let mut index = [0; 3];
+
+            for a in 0..3 {
+                let upper = (corner >> a) & 1 == 1;
+                index[a] = base[a] + upper as usize;
+                weight *= if upper { frac[a] } else { 1.0 - frac[a] };
+            }
+
+            value += weight * self.value(index[0], index[1], index[2]);
+        }
+
+        value
+    }
+}
@@ -1,38 +1,49 @@
 #![feature(const_generics)]
 
 mod aabb;
+mod animation;
+mod atmosphere;
 mod bvh;
 mod camera;
+mod cli;
 mod image;
+mod loaders;
 mod materials;
+mod matrix;
 mod objects;
 mod onb;
 mod pdf;
+mod physics;
+mod progress;
+mod quaternion;
 mod ray;
+mod spline;
 mod textures;
 mod util;
 mod vector3;
 
 // Crates
-use image::{gen_ppm, Pixel};
+use image::{contact_sheet, gen_ppm, Pixel, Y4mWriter};
 use rand::prelude::*;
 use rayon::prelude::*;
 
-use std::env;
 use std::f32;
 use std::path::Path;
 use std::time;
 
 use crate::{
-    materials::Dielectric,
-    objects::{HitRecord, Hittable, RectPlane, Rectangle, Scene},
+    animation::Shutter,
+    cli::Options,
+    materials::Material,
+    objects::{HitRecord, Hittable, Scene, SceneDescription},
     pdf::{HittablePDF, Mixture, Pdf},
+    progress::{Event, Reporter},
     ray::Ray,
     vector3::Vector,
 };
 
 #[allow(unused_imports)]
-use util::{one_by_one, progress_bar, sixteen_by_nine, two_by_one};
+use util::{one_by_one, sixteen_by_nine, two_by_one};
 
 const IMG_WIDTH: usize = 300;
 const IMG_HEIGHT: usize = one_by_one(IMG_WIDTH);
@@ -41,11 +52,167 @@ const SAMPLES: usize = 5000;
 const MAX_RECURSIVE_DEPTH: usize = 50;
 const T_MIN: f32 = 0.005;
 
-const PROG_BAR_WIDTH: usize = 80;
-
 fn main() -> Result<(), String> {
+    let options = Options::from_args()?;
+    let mut reporter = Reporter::new(&options.progress)?;
+
+    let result = render(&options, &mut reporter);
+
+    if let Err(message) = &result {
+        reporter.emit(Event::Error { message });
+    }
+
+    result
+}
+
+fn render(options: &Options, reporter: &mut Reporter) -> Result<(), String> {
+    if options.contact_sheet.is_some() && options.turntable.is_none() {
+        return Err(String::from(
+            "Contact sheets can only be made from turntable renders",
+        ));
+    }
+
     let start_time = time::Instant::now();
 
+    let path = Path::new(&options.scene_file);
+    let mut description = SceneDescription::load(path)?;
+    for (property, value) in &options.overrides {
+        description.set(property, value)?;
+    }
+    let aspect_r = IMG_WIDTH as f32 / IMG_HEIGHT as f32;
+
+    reporter.emit(Event::SceneLoaded {
+        scene: path.file_name().and_then(|p| p.to_str()).unwrap(),
+        width: IMG_WIDTH,
+        height: IMG_HEIGHT,
+        samples: SAMPLES,
+        load_secs: start_time.elapsed().as_secs_f32(),
+    });
+
+    match (&options.frames, &options.turntable) {
+        (None, None) => {
+            let scene = description.build(aspect_r, None, None)?;
+            let image = render_scene(&scene, reporter);
+            write_image(&image, &options.output_file, reporter)
+        }
+        (Some(frames), None) => {
+            // A .y4m output gets every frame streamed into one video
+            // instead of a numbered image per frame
+            let mut video = if is_y4m(&options.output_file) {
+                Some(Y4mWriter::create(
+                    &options.output_file,
+                    IMG_WIDTH,
+                    IMG_HEIGHT,
+                    options.fps,
+                )?)
+            } else {
+                None
+            };
+
+            for frame in frames.clone() {
+                reporter.emit(Event::FrameStarted {
+                    frame,
+                    first: frames.start,
+                    last: frames.end - 1,
+                });
+
+                let shutter =
+                    Shutter::for_frame(frame, options.fps, options.shutter);
+                let scene = description.build(aspect_r, Some(shutter), None)?;
+                let image = render_scene(&scene, reporter);
+
+                match &mut video {
+                    Some(video) => {
+                        video.write_frame(&image)?;
+                        reporter.emit(Event::ImageWritten {
+                            path: video.path(),
+                            elapsed_secs: reporter.elapsed_secs(),
+                        });
+                    }
+                    None => {
+                        let output_file =
+                            frame_path(&options.output_file, frame);
+                        write_image(&image, &output_file, reporter)?;
+                    }
+                }
+            }
+
+            Ok(())
+        }
+        (None, Some(turntable)) => {
+            let mut images = Vec::with_capacity(turntable.views);
+
+            for view in 0..turntable.views {
+                reporter.emit(Event::FrameStarted {
+                    frame: view,
+                    first: 0,
+                    last: turntable.views - 1,
+                });
+
+                let scene = description.build(
+                    aspect_r,
+                    None,
+                    Some(turntable.view(view)),
+                )?;
+                let image = render_scene(&scene, reporter);
+
+                let output_file = frame_path(&options.output_file, view);
+                write_image(&image, &output_file, reporter)?;
+                images.push(image);
+            }
+
+            match &options.contact_sheet {
+                Some(path) => {
+                    write_image(&contact_sheet(&images), path, reporter)
+                }
+                None => Ok(()),
+            }
+        }
+        (Some(_), Some(_)) => Err(String::from(
+            "Turntable renders can't be combined with --frames",
+        )),
+    }
+}
+
+fn is_y4m(output_file: &str) -> bool {
+    Path::new(output_file)
+        .extension()
+        .and_then(|e| e.to_str())
+        .map_or(false, |e| e.eq_ignore_ascii_case("y4m"))
+}
+
+/// Inserts the frame number into an output path, e.g. `out/image.ppm`
+/// becomes `out/image_0012.ppm` for frame 12
+fn frame_path(output_file: &str, frame: usize) -> String {
+    let path = Path::new(output_file);
+    let stem = path.file_stem().and_then(|s| s.to_str()).unwrap_or("frame");
+    let file_name = match path.extension().and_then(|e| e.to_str()) {
+        Some(ext) => format!("{}_{:04}.{}", stem, frame, ext),
+        None => format!("{}_{:04}", stem, frame),
+    };
+
+    path.with_file_name(file_name)
+        .to_string_lossy()
+        .into_owned()
+}
+
+fn write_image(
+    image: &[Vec<Pixel>],
+    output_file: &str,
+    reporter: &mut Reporter,
+) -> Result<(), String> {
+    gen_ppm(image, output_file)?;
+
+    reporter.emit(Event::ImageWritten {
+        path: output_file,
+        elapsed_secs: reporter.elapsed_secs(),
+    });
+
+    Ok(())
+}
+
+/// Renders the scene into an image, reporting progress after each row
+fn render_scene(scene: &Scene, reporter: &mut Reporter) -> Vec<Vec<Pixel>> {
     let mut image = Vec::with_capacity(IMG_HEIGHT);
 
     for y in 0..IMG_HEIGHT {
@@ -62,50 +229,26 @@ fn main() -> Result<(), String> {
         }
     }
 
-    let scene_file = env::args()
-        .nth(1)
-        .unwrap_or_else(|| String::from("./scenes/1.json"));
-
-    let output_file = env::args()
-        .nth(2)
-        .unwrap_or_else(|| String::from("./out/image.ppm"));
-
-    let path = Path::new(&scene_file);
-    let scene = Scene::from_json(path, IMG_WIDTH as f32 / IMG_HEIGHT as f32)?;
-
-    println!(
-        "Scene loaded from {}, rendering {} x {} @ {} samples ({} rays)",
-        path.file_name().and_then(|p| p.to_str()).unwrap(),
-        IMG_WIDTH,
-        IMG_HEIGHT,
-        SAMPLES,
-        IMG_HEIGHT * IMG_WIDTH * SAMPLES,
-    );
+    if scene.stats.bvh_count > 0 {
+        reporter.emit(Event::BvhBuilt {
+            count: scene.stats.bvh_count,
+            build_secs: scene.stats.bvh_build_time.as_secs_f32(),
+        });
+    }
 
+    let render_start = time::Instant::now();
     let mut completed_rows = 0;
     image.iter_mut().for_each(|row| {
-        progress_bar(completed_rows, IMG_HEIGHT, PROG_BAR_WIDTH, "Rendering");
-
         row.par_iter_mut().for_each(|pixel| {
             let mut curr_pixel = Vector::zeros();
 
-            let light_shape = Rectangle::<{ RectPlane::XZ }> {
-                a0: 213.0,
-                a1: 343.0,
-                b0: 227.0,
-                b1: 332.0,
-                k: 554.0,
-                norm: 1.0,
-                material: Box::new(Dielectric::new(1.52)),
-            };
-
             for _ in 0..SAMPLES {
                 let u = (pixel.x as f32 + random::<f32>()) / IMG_WIDTH as f32;
                 let v = (pixel.y as f32 + random::<f32>()) / IMG_HEIGHT as f32;
 
                 let r = scene.camera.get_ray(u, v);
 
-                curr_pixel += color(r, &scene, &light_shape, 0);
+                curr_pixel += color(r, scene, 0);
             }
 
             curr_pixel /= SAMPLES as f32;
@@ -131,72 +274,120 @@ fn main() -> Result<(), String> {
         });
 
         completed_rows += 1;
+
+        let elapsed_secs = render_start.elapsed().as_secs_f32();
+        let rays = completed_rows * IMG_WIDTH * SAMPLES;
+        reporter.emit(Event::RowCompleted {
+            row: completed_rows,
+            rows: IMG_HEIGHT,
+            spp: SAMPLES,
+            elapsed_secs,
+            eta_secs: elapsed_secs / completed_rows as f32
+                * (IMG_HEIGHT - completed_rows) as f32,
+            rays_per_sec: rays as f32 / elapsed_secs,
+        });
+    });
+
+    reporter.emit(Event::RenderFinished {
+        elapsed_secs: render_start.elapsed().as_secs_f32(),
     });
 
-    println!("\nCompleted rendering in {:#?}", start_time.elapsed());
-    gen_ppm(image, output_file)
+    image
 }
 
-fn color(
+fn color(r: Ray, scene: &Scene, depth: usize) -> Vector {
+    let hit = scene.hit(r, T_MIN, f32::MAX);
+
+    // The ray may be scattered by the atmosphere before it gets to the
+    // surface
+    if let Some(atmosphere) = &scene.atmosphere {
+        let t_max = hit.map_or(f32::INFINITY, |(hit_record, _)| hit_record.t);
+        let light = if scene.lights.is_empty() {
+            None
+        } else {
+            Some(scene.lights.random_point(r.origin()))
+        };
+
+        if let Some((hit_record, weight)) =
+            atmosphere.sample(r, T_MIN, t_max, light)
+        {
+            if weight == 0.0 {
+                return Vector::zeros();
+            }
+
+            let material = atmosphere.phase_function();
+            return weight * shade(r, hit_record, material, scene, depth);
+        }
+    }
+
+    match hit {
+        Some((hit_record, material)) => {
+            shade(r, hit_record, material, scene, depth)
+        }
+        None => scene.background,
+    }
+}
+
+/// Finds the light leaving a point that a ray hit, which is emitted there
+/// or scattered from elsewhere
+fn shade(
     r: Ray,
+    hit_record: HitRecord,
+    material: &Box<dyn Material>,
     scene: &Scene,
-    light_shape: &dyn Hittable,
     depth: usize,
 ) -> Vector {
-    if let Some((hit_record, material)) = scene.hit(r, T_MIN, f32::MAX) {
-        let emitted = material.emitted(r, hit_record);
-
-        if depth < MAX_RECURSIVE_DEPTH {
-            if let Some(scatter_record) = material.scatter(r, hit_record) {
-                match scatter_record.pdf {
-                    None => {
-                        return emitted
-                            + scatter_record.attenuation
-                                * material.scattering_pdf(
-                                    r,
-                                    hit_record,
-                                    scatter_record.specular_ray,
-                                )
-                                * color(
-                                    scatter_record.specular_ray,
-                                    scene,
-                                    light_shape,
-                                    depth + 1,
-                                );
-                    }
-                    Some(pdf) => {
-                        let p0 = HittablePDF {
-                            inner: light_shape,
-                            o: hit_record.p,
-                        };
-
-                        let p = Mixture {
-                            pdf1: &p0,
-                            pdf2: &pdf,
-                        };
-
-                        let scattered =
-                            Ray::new(hit_record.p, p.generate(), r.time());
-                        let pdf = p.value(scattered.dir());
-
-                        return emitted
-                            + scatter_record.attenuation
-                                * material
-                                    .scattering_pdf(r, hit_record, scattered)
-                                * color(
-                                    scattered,
-                                    scene,
-                                    light_shape,
-                                    depth + 1,
-                                )
-                                / pdf;
-                    }
+    let emitted = material.emitted(r, hit_record);
+
+    if depth < MAX_RECURSIVE_DEPTH {
+        if let Some(scatter_record) = material.scatter(r, hit_record) {
+            match scatter_record.pdf {
+                None => {
+                    return emitted
+                        + scatter_record.attenuation
+                            * material.scattering_pdf(
+                                r,
+                                hit_record,
+                                scatter_record.specular_ray,
+                            )
+                            * color(
+                                scatter_record.specular_ray,
+                                scene,
+                                depth + 1,
+                            );
+                }
+                Some(pdf) => {
+                    let p0 = HittablePDF {
+                        inner: &scene.lights,
+                        o: hit_record.p,
+                    };
+
+                    // Half of the rays are sent towards the lights, if
+                    // there are any
+                    let mixture = Mixture {
+                        pdf1: &p0,
+                        pdf2: &pdf,
+                    };
+                    let p: &dyn Pdf = if scene.lights.is_empty() {
+                        &pdf
+                    } else {
+                        &mixture
+                    };
+
+                    let scattered =
+                        Ray::new(hit_record.p, p.generate(), r.time());
+                    let pdf = p.value(scattered.dir());
+
+                    return emitted
+                        + scatter_record.attenuation
+                            * material
+                                .scattering_pdf(r, hit_record, scattered)
+                            * color(scattered, scene, depth + 1)
+                            / pdf;
                 }
             }
         }
-
-        emitted
-    } else {
-        Vector::zeros()
     }
+
+    emitted
 }
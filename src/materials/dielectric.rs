@@ -38,25 +38,24 @@ impl Material for Dielectric {
         r_in: Ray,
         hit_record: HitRecord,
     ) -> Option<ScatterRecord> {
-        let reflected = vector_reflect(r_in.dir(), hit_record.normal);
+        let normal = hit_record.shading_normal;
+        let reflected = vector_reflect(r_in.dir(), normal);
 
-        let (outward_normal, ni_over_nt, cosine) =
-            if Vector::dot(r_in.dir(), hit_record.normal) > 0.0 {
-                (
-                    -hit_record.normal,
-                    self.ref_idx,
-                    self.ref_idx
-                        * Vector::dot(r_in.dir(), hit_record.normal)
-                        * r_in.dir().inv_mag(),
-                )
-            } else {
-                (
-                    hit_record.normal,
-                    1.0 / self.ref_idx,
-                    -Vector::dot(r_in.dir(), hit_record.normal)
-                        * r_in.dir().inv_mag(),
-                )
-            };
+        let (outward_normal, ni_over_nt, cosine) = if !hit_record.front_face {
+            (
+                -normal,
+                self.ref_idx,
+                self.ref_idx
+                    * Vector::dot(r_in.dir(), normal)
+                    * r_in.dir().inv_mag(),
+            )
+        } else {
+            (
+                normal,
+                1.0 / self.ref_idx,
+                -Vector::dot(r_in.dir(), normal) * r_in.dir().inv_mag(),
+            )
+        };
 
         let refracted = vector_refract(r_in.dir(), outward_normal, ni_over_nt);
 
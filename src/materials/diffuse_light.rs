@@ -18,9 +18,9 @@ impl Material for DiffuseLight {
         None
     }
 
-    fn emitted(&self, r_in: Ray, hit_record: HitRecord) -> Vector {
+    fn emitted(&self, _r_in: Ray, hit_record: HitRecord) -> Vector {
         let (u, v, p) = (hit_record.u, hit_record.v, hit_record.p);
-        if Vector::dot(hit_record.normal, r_in.dir()) < 0.0 {
+        if hit_record.front_face {
             self.emit.value(u, v, p)
         } else {
             Vector::zeros()
@@ -0,0 +1,84 @@
+use crate::{
+    materials::{Material, ScatterRecord},
+    pdf::{henyey_greenstein, HenyeyGreensteinPdf, Pdf},
+    textures::Texture,
+    HitRecord, Ray, Vector,
+};
+
+/// The phase function of a participating medium which scatters light
+/// unevenly, such as clouds which scatter mostly forwards. The medium can
+/// also glow, like the hot gases in a fire.
+#[derive(Debug, Clone)]
+pub struct HenyeyGreenstein {
+    /// The fraction of light that's scattered rather than absorbed
+    albedo: Box<dyn Texture>,
+    /// The mean cosine of the scattering angle, between -1 and 1
+    g: f32,
+    emission: Option<Box<dyn Texture>>,
+}
+
+impl HenyeyGreenstein {
+    pub fn new(
+        albedo: Box<dyn Texture>,
+        g: f32,
+        emission: Option<Box<dyn Texture>>,
+    ) -> Self {
+        Self {
+            albedo,
+            // The phase function is singular at exactly -1 and 1
+            g: g.max(-0.99).min(0.99),
+            emission,
+        }
+    }
+}
+
+impl Material for HenyeyGreenstein {
+    fn scatter(
+        &self,
+        r_in: Ray,
+        hit_record: HitRecord,
+    ) -> Option<ScatterRecord> {
+        let pdf = HenyeyGreensteinPdf::new(r_in.dir(), self.g);
+        let scattered = Ray::new(hit_record.p, pdf.generate(), r_in.time());
+
+        Some(ScatterRecord {
+            specular_ray: scattered,
+            attenuation: self.albedo.value(
+                hit_record.u,
+                hit_record.v,
+                hit_record.p,
+            ),
+            pdf: Some(Box::new(pdf)),
+        })
+    }
+
+    /// Light is emitted by the absorbing part of the medium, so the albedo
+    /// of a collision decides between emission and scattering
+    fn emitted(&self, _r_in: Ray, hit_record: HitRecord) -> Vector {
+        let (u, v, p) = (hit_record.u, hit_record.v, hit_record.p);
+
+        let emission = match (&self.emission, hit_record.temperature) {
+            (Some(emission), Some(temperature)) => {
+                emission.value_at_temperature(u, v, p, temperature)
+            }
+            (Some(emission), None) => emission.value(u, v, p),
+            (None, _) => return Vector::zeros(),
+        };
+
+        (Vector::ones() - self.albedo.value(u, v, p)) * emission
+    }
+
+    fn scattering_pdf(
+        &self,
+        r_in: Ray,
+        _hit_record: HitRecord,
+        scattered: Ray,
+    ) -> f32 {
+        let cos_theta = Vector::dot(
+            r_in.dir() / r_in.dir().length(),
+            scattered.dir() / scattered.dir().length(),
+        );
+
+        henyey_greenstein(cos_theta, self.g)
+    }
+}
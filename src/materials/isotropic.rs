@@ -0,0 +1,52 @@
+use crate::{
+    materials::{Material, ScatterRecord},
+    pdf::UniformSphere,
+    textures::Texture,
+    util::random_on_unit_sphere,
+    HitRecord, Ray,
+};
+
+use std::f32::consts::PI;
+
+/// The phase function of a participating medium which scatters light
+/// equally in every direction
+#[derive(Debug, Clone)]
+pub struct Isotropic {
+    albedo: Box<dyn Texture>,
+}
+
+impl Isotropic {
+    pub fn new(albedo: Box<dyn Texture>) -> Self {
+        Self { albedo }
+    }
+}
+
+impl Material for Isotropic {
+    fn scatter(
+        &self,
+        r_in: Ray,
+        hit_record: HitRecord,
+    ) -> Option<ScatterRecord> {
+        let scattered =
+            Ray::new(hit_record.p, random_on_unit_sphere(), r_in.time());
+
+        Some(ScatterRecord {
+            specular_ray: scattered,
+            attenuation: self.albedo.value(
+                hit_record.u,
+                hit_record.v,
+                hit_record.p,
+            ),
+            pdf: Some(Box::new(UniformSphere)),
+        })
+    }
+
+    fn scattering_pdf(
+        &self,
+        _r_in: Ray,
+        _hit_record: HitRecord,
+        _scattered: Ray,
+    ) -> f32 {
+        1.0 / (4.0 * PI)
+    }
+}
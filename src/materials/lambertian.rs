@@ -27,7 +27,8 @@ impl Material for Lambertian {
         r_in: Ray,
         hit_record: HitRecord,
     ) -> Option<ScatterRecord> {
-        let uvw = Onb::build_from_w(hit_record.normal);
+        let normal = hit_record.facing_normal();
+        let uvw = Onb::build_from_w(normal);
         let dir = uvw.local(random_cosine_dir());
 
         let scattered = Ray::new(hit_record.p, dir.normalize(), r_in.time());
@@ -39,7 +40,7 @@ impl Material for Lambertian {
                 hit_record.v,
                 hit_record.p,
             ),
-            pdf: Some(Box::new(Cosine::new(hit_record.normal))),
+            pdf: Some(Box::new(Cosine::new(normal))),
         })
     }
 
@@ -49,7 +50,10 @@ impl Material for Lambertian {
         hit_record: HitRecord,
         scattered: Ray,
     ) -> f32 {
-        let dot = Vector::dot(hit_record.normal, scattered.dir().normalize());
+        let dot = Vector::dot(
+            hit_record.facing_normal(),
+            scattered.dir().normalize(),
+        );
         return if dot < 0.0 { 0.0 } else { dot / PI };
     }
 }
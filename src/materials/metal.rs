@@ -27,14 +27,19 @@ impl Material for Metal {
         hit_record: HitRecord,
     ) -> Option<ScatterRecord> {
         let reflected =
-            vector_reflect(r_in.dir().normalize(), hit_record.normal);
+            vector_reflect(r_in.dir().normalize(), hit_record.facing_normal());
         let scattered = Ray::new(
             hit_record.p,
             reflected + self.fuzz * random_in_unit_sphere(),
             r_in.time(),
         );
 
-        if Vector::dot(scattered.dir(), hit_record.normal) > 0.0 {
+        // Rays can't leave through the other side of the actual surface,
+        // even if the shading normal would allow it
+        let side = if hit_record.front_face { 1.0 } else { -1.0 };
+        if Vector::dot(scattered.dir(), side * hit_record.geometric_normal)
+            > 0.0
+        {
             Some(ScatterRecord {
                 specular_ray: scattered,
                 attenuation: self.albedo,
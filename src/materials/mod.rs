@@ -14,6 +14,12 @@ pub use dielectric::*;
 mod diffuse_light;
 pub use diffuse_light::*;
 
+mod henyey_greenstein;
+pub use henyey_greenstein::*;
+
+mod isotropic;
+pub use isotropic::*;
+
 pub trait MaterialClone {
     fn clone_box(&self) -> Box<dyn Material>;
 }
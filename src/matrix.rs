@@ -0,0 +1,187 @@
+//! 4x4 matrices for affine transformations of points and vectors. Matrices
+//! act on column vectors, so `a * b` applies `b` first and then `a`.
+use crate::animation::Lerp;
+use crate::Vector;
+
+use std::f32;
+use std::ops;
+
+#[derive(Debug, Copy, Clone)]
+pub struct Matrix4 {
+    /// The entries in row-major order
+    pub m: [[f32; 4]; 4],
+}
+
+/// Constructor-like
+impl Matrix4 {
+    pub fn identity() -> Self {
+        let mut m = [[0.0; 4]; 4];
+        for (i, row) in m.iter_mut().enumerate() {
+            row[i] = 1.0;
+        }
+
+        Self { m }
+    }
+
+    /// Creates a matrix from 16 values in row-major order
+    pub fn from_rows(values: &[f32]) -> Result<Self, String> {
+        if values.len() != 16 {
+            return Err(format!(
+                "A matrix needs 16 values but {} were given",
+                values.len()
+            ));
+        }
+
+        let mut m = [[0.0; 4]; 4];
+        for (i, row) in m.iter_mut().enumerate() {
+            row.copy_from_slice(&values[i * 4..i * 4 + 4]);
+        }
+
+        Ok(Self { m })
+    }
+
+    pub fn translation(offset: Vector) -> Self {
+        let mut ret = Self::identity();
+        for i in 0..3 {
+            ret.m[i][3] = offset[i];
+        }
+
+        ret
+    }
+
+    pub fn scale(factors: Vector) -> Self {
+        let mut ret = Self::identity();
+        for i in 0..3 {
+            ret.m[i][i] = factors[i];
+        }
+
+        ret
+    }
+
+    /// Creates a rotation of `angle` degrees counter-clockwise about the
+    /// given axis (looking down the axis towards the origin)
+    pub fn rotation(axis: Vector, angle: f32) -> Self {
+        let a = axis.normalized_exact();
+        let rads = angle * f32::consts::PI / 180.0;
+        let (sin, cos) = (f32::sin(rads), f32::cos(rads));
+        let k = 1.0 - cos;
+
+        let mut ret = Self::identity();
+        ret.m[0][0] = cos + a.x * a.x * k;
+        ret.m[0][1] = a.x * a.y * k - a.z * sin;
+        ret.m[0][2] = a.x * a.z * k + a.y * sin;
+        ret.m[1][0] = a.y * a.x * k + a.z * sin;
+        ret.m[1][1] = cos + a.y * a.y * k;
+        ret.m[1][2] = a.y * a.z * k - a.x * sin;
+        ret.m[2][0] = a.z * a.x * k - a.y * sin;
+        ret.m[2][1] = a.z * a.y * k + a.x * sin;
+        ret.m[2][2] = cos + a.z * a.z * k;
+
+        ret
+    }
+}
+
+impl Matrix4 {
+    /// Transforms a point, including the translation
+    pub fn transform_point(&self, p: Vector) -> Vector {
+        let m = &self.m;
+        Vector::new(
+            m[0][0] * p.x + m[0][1] * p.y + m[0][2] * p.z + m[0][3],
+            m[1][0] * p.x + m[1][1] * p.y + m[1][2] * p.z + m[1][3],
+            m[2][0] * p.x + m[2][1] * p.y + m[2][2] * p.z + m[2][3],
+        )
+    }
+
+    /// Transforms a direction, ignoring the translation
+    pub fn transform_vector(&self, v: Vector) -> Vector {
+        let m = &self.m;
+        Vector::new(
+            m[0][0] * v.x + m[0][1] * v.y + m[0][2] * v.z,
+            m[1][0] * v.x + m[1][1] * v.y + m[1][2] * v.z,
+            m[2][0] * v.x + m[2][1] * v.y + m[2][2] * v.z,
+        )
+    }
+
+    pub fn transpose(&self) -> Self {
+        let mut m = [[0.0; 4]; 4];
+        for (i, row) in m.iter_mut().enumerate() {
+            for (j, value) in row.iter_mut().enumerate() {
+                *value = self.m[j][i];
+            }
+        }
+
+        Self { m }
+    }
+
+    /// Computes the inverse using Gauss-Jordan elimination with partial
+    /// pivoting. Returns None if the matrix is singular.
+    pub fn inverse(&self) -> Option<Self> {
+        let mut a = self.m;
+        let mut inv = Self::identity().m;
+
+        for col in 0..4 {
+            let pivot = (col..4)
+                .max_by(|&i, &j| {
+                    a[i][col].abs().partial_cmp(&a[j][col].abs()).unwrap()
+                })
+                .unwrap();
+
+            if a[pivot][col].abs() < 1e-12 {
+                return None;
+            }
+
+            a.swap(col, pivot);
+            inv.swap(col, pivot);
+
+            let scale = 1.0 / a[col][col];
+            for j in 0..4 {
+                a[col][j] *= scale;
+                inv[col][j] *= scale;
+            }
+
+            for row in 0..4 {
+                if row == col {
+                    continue;
+                }
+
+                let factor = a[row][col];
+                for j in 0..4 {
+                    a[row][j] -= factor * a[col][j];
+                    inv[row][j] -= factor * inv[col][j];
+                }
+            }
+        }
+
+        Some(Self { m: inv })
+    }
+}
+
+impl ops::Mul<Matrix4> for Matrix4 {
+    type Output = Matrix4;
+
+    fn mul(self, rhs: Matrix4) -> Matrix4 {
+        let mut m = [[0.0; 4]; 4];
+        for (i, row) in m.iter_mut().enumerate() {
+            for (j, value) in row.iter_mut().enumerate() {
+                *value = (0..4).map(|k| self.m[i][k] * rhs.m[k][j]).sum();
+            }
+        }
+
+        Matrix4 { m }
+    }
+}
+
+/// Interpolates each entry separately, which is only a reasonable rotation
+/// between matrices that are close together
+impl Lerp for Matrix4 {
+    fn lerp(a: Self, b: Self, t: f32) -> Self {
+        let mut m = a.m;
+        for (row, b_row) in m.iter_mut().zip(&b.m) {
+            for (value, b_value) in row.iter_mut().zip(b_row) {
+                *value += (b_value - *value) * t;
+            }
+        }
+
+        Matrix4 { m }
+    }
+}
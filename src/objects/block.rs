@@ -1,11 +1,13 @@
-use super::{HitRecord, Hittable, RectPlane, Rectangle, Scene};
+use super::{HitRecord, Hittable};
 use crate::{aabb::Aabb, materials::Material, Ray, Vector};
 
+/// An axis aligned box. The faces are intersected directly rather than
+/// as six Rectangles, so the material only needs to be stored once.
 #[derive(Debug)]
 pub struct Block {
     p_min: Vector,
     p_max: Vector,
-    sides: Scene,
+    material: Box<dyn Material>,
 }
 
 impl Hittable for Block {
@@ -15,7 +17,71 @@ impl Hittable for Block {
         t_min: f32,
         t_max: f32,
     ) -> Option<(HitRecord, &Box<dyn Material>)> {
-        self.sides.hit(r, t_min, t_max)
+        // The nearest face the ray enters and the furthest face it leaves
+        // through, as (t, axis, side)
+        let mut enter = (-f32::MAX, 0, -1.0);
+        let mut exit = (f32::MAX, 0, 1.0);
+
+        let bounds = Aabb::new(self.p_min, self.p_max);
+        for a in 0..3 {
+            let (t0, t1) = bounds.slab(r, a);
+            // Rays going the negative way enter through the positive face
+            let side = if r.dir()[a].is_sign_negative() {
+                1.0
+            } else {
+                -1.0
+            };
+
+            if t0 > enter.0 {
+                enter = (t0, a, side);
+            }
+            if t1 < exit.0 {
+                exit = (t1, a, -side);
+            }
+        }
+
+        if exit.0 < enter.0 {
+            return None;
+        }
+
+        // Rays starting inside the block hit the face they leave through
+        let (t, axis, side) = if enter.0 >= t_min && enter.0 <= t_max {
+            enter
+        } else if exit.0 >= t_min && exit.0 <= t_max {
+            exit
+        } else {
+            return None;
+        };
+
+        // The UVs match those of the equivalent Rectangle
+        let (a_ax, b_ax) = match axis {
+            0 => (1, 2),
+            1 => (0, 2),
+            _ => (0, 1),
+        };
+
+        let p = r.point_at_parameter(t);
+        let size = self.p_max - self.p_min;
+        let mut normal = Vector::zeros();
+        let mut dpdu = Vector::zeros();
+        let mut dpdv = Vector::zeros();
+        normal[axis] = side;
+        dpdu[a_ax] = size[a_ax];
+        dpdv[b_ax] = size[b_ax];
+
+        Some((
+            HitRecord::new(
+                r,
+                t,
+                (
+                    (p[a_ax] - self.p_min[a_ax]) / size[a_ax],
+                    (p[b_ax] - self.p_min[b_ax]) / size[b_ax],
+                ),
+                normal,
+                (dpdu, dpdv),
+            ),
+            &self.material,
+        ))
     }
 
     fn bounding_box(&self, _t0: f32, _t1: f32) -> Option<Aabb> {
@@ -25,67 +91,10 @@ impl Hittable for Block {
 
 impl Block {
     pub fn new(p0: Vector, p1: Vector, material: Box<dyn Material>) -> Self {
-        let objects: Vec<Box<dyn Hittable>> = vec![
-            Box::new(Rectangle::<{ RectPlane::XY }> {
-                a0: p0.x,
-                a1: p1.x,
-                b0: p0.y,
-                b1: p1.y,
-                k: p1.z,
-                norm: 1.0,
-                material: material.clone(),
-            }),
-            Box::new(Rectangle::<{ RectPlane::XY }> {
-                a0: p0.x,
-                a1: p1.x,
-                b0: p0.y,
-                b1: p1.y,
-                k: p0.z,
-                norm: -1.0,
-                material: material.clone(),
-            }),
-            Box::new(Rectangle::<{ RectPlane::XZ }> {
-                a0: p0.x,
-                a1: p1.x,
-                b0: p0.z,
-                b1: p1.z,
-                k: p1.y,
-                norm: 1.0,
-                material: material.clone(),
-            }),
-            Box::new(Rectangle::<{ RectPlane::XZ }> {
-                a0: p0.x,
-                a1: p1.x,
-                b0: p0.z,
-                b1: p1.z,
-                k: p0.y,
-                norm: -1.0,
-                material: material.clone(),
-            }),
-            Box::new(Rectangle::<{ RectPlane::YZ }> {
-                a0: p0.y,
-                a1: p1.y,
-                b0: p0.z,
-                b1: p1.z,
-                k: p1.x,
-                norm: 1.0,
-                material: material.clone(),
-            }),
-            Box::new(Rectangle::<{ RectPlane::YZ }> {
-                a0: p0.y,
-                a1: p1.y,
-                b0: p0.z,
-                b1: p1.z,
-                k: p0.x,
-                norm: -1.0,
-                material: material.clone(),
-            }),
-        ];
-
         Self {
             p_min: p0,
             p_max: p1,
-            sides: Scene::from_objects(objects, 0.0),
+            material,
         }
     }
 }
@@ -0,0 +1,157 @@
+//! A cone standing on its base with its apex above it, optionally closed
+//! off by a disk at the base
+
+use super::{disk::DiskSurface, surface_pdf, HitRecord, Hittable};
+use crate::{
+    aabb::Aabb,
+    materials::Material,
+    util::{azimuth, solve_quadratic},
+    Ray, Vector,
+};
+
+use rand::prelude::*;
+use std::f32::consts::PI;
+
+#[derive(Debug)]
+pub struct Cone {
+    base: Vector,
+    radius: f32,
+    height: f32,
+    cap: Option<DiskSurface>,
+    material: Box<dyn Material>,
+}
+
+impl Hittable for Cone {
+    fn hit(
+        &self,
+        r: Ray,
+        t_min: f32,
+        t_max: f32,
+    ) -> Option<(HitRecord, &Box<dyn Material>)> {
+        let side = self.hit_side(r, t_min, t_max);
+        let t_max = side.map_or(t_max, |hit_record| hit_record.t);
+        let cap = self.cap.and_then(|cap| cap.hit(r, t_min, t_max));
+
+        cap.or(side).map(|hit_record| (hit_record, &self.material))
+    }
+
+    fn bounding_box(&self, _t0: f32, _t1: f32) -> Option<Aabb> {
+        Some(Aabb::new(
+            self.base - Vector::new(self.radius, 0.0, self.radius),
+            self.base + Vector::new(self.radius, self.height, self.radius),
+        ))
+    }
+
+    fn pdf_value(&self, o: Vector, v: Vector) -> f32 {
+        surface_pdf(self, self.area(), o, v)
+    }
+
+    fn random(&self, o: Vector) -> Vector {
+        let side_area = self.side_area();
+        let point = match &self.cap {
+            Some(cap) if random::<f32>() * self.area() > side_area => {
+                cap.random_point()
+            }
+            _ => {
+                // The area of the cone below a given height grows with the
+                // square of the distance from the apex
+                let s = random::<f32>().sqrt();
+                let phi = 2.0 * PI * random::<f32>();
+                self.base
+                    + Vector::new(
+                        self.radius * s * phi.cos(),
+                        self.height * (1.0 - s),
+                        self.radius * s * phi.sin(),
+                    )
+            }
+        };
+
+        point - o
+    }
+}
+
+impl Cone {
+    pub fn new(
+        base: Vector,
+        radius: f32,
+        height: f32,
+        capped: bool,
+        material: Box<dyn Material>,
+    ) -> Self {
+        let cap = if capped {
+            Some(DiskSurface::new(base, radius, 0.0, -1.0))
+        } else {
+            None
+        };
+
+        Self {
+            base,
+            radius,
+            height,
+            cap,
+            material,
+        }
+    }
+
+    /// u goes around the cone and v goes up from the base to the apex
+    fn hit_side(&self, r: Ray, t_min: f32, t_max: f32) -> Option<HitRecord> {
+        let o = r.origin() - self.base;
+        let d = r.dir();
+        let k = self.radius / self.height;
+        let k_squared = k * k;
+        // The height of the ray's origin below the apex
+        let h = self.height - o.y;
+
+        let (t0, t1) = solve_quadratic(
+            d.x * d.x + d.z * d.z - k_squared * d.y * d.y,
+            2.0 * (o.x * d.x + o.z * d.z + k_squared * h * d.y),
+            o.x * o.x + o.z * o.z - k_squared * h * h,
+        )?;
+
+        for &t in &[t0, t1] {
+            if t <= t_min || t >= t_max {
+                continue;
+            }
+
+            // This also rejects the mirrored cone above the apex
+            let p = o + t * d;
+            if p.y < 0.0 || p.y > self.height {
+                continue;
+            }
+
+            let normal = Vector::new(p.x, k_squared * (self.height - p.y), p.z);
+            let length = normal.length();
+            let normal = if length > 0.0 {
+                normal / length
+            } else {
+                Vector::new(0.0, 1.0, 0.0)
+            };
+
+            let phi = azimuth(p.x, p.z);
+            let u = phi / (2.0 * PI);
+            let v = p.y / self.height;
+
+            let dpdu = 2.0 * PI * Vector::new(-p.z, 0.0, p.x);
+            let dpdv = Vector::new(
+                -self.radius * phi.cos(),
+                self.height,
+                -self.radius * phi.sin(),
+            );
+
+            return Some(HitRecord::new(r, t, (u, v), normal, (dpdu, dpdv)));
+        }
+
+        None
+    }
+
+    fn side_area(&self) -> f32 {
+        PI * self.radius * self.radius.hypot(self.height)
+    }
+
+    fn area(&self) -> f32 {
+        match self.cap {
+            Some(_) => self.side_area() + PI * self.radius * self.radius,
+            None => self.side_area(),
+        }
+    }
+}
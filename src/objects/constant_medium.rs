@@ -0,0 +1,81 @@
+//! A volume of fog or smoke with the same density everywhere inside it
+
+use super::{HitRecord, Hittable};
+use crate::{aabb::Aabb, materials::Material, Ray};
+
+use rand::prelude::*;
+
+#[derive(Debug)]
+pub struct ConstantMedium {
+    /// A closed surface whose normals face outwards
+    boundary: Box<dyn Hittable>,
+    density: f32,
+    /// The phase function, which is usually [`Isotropic`]
+    ///
+    /// [`Isotropic`]: ../materials/struct.Isotropic.html
+    phase_function: Box<dyn Material>,
+}
+
+impl Hittable for ConstantMedium {
+    /// Finds where a ray scatters inside the medium, if it does before
+    /// `t_max`. The ray can start inside the medium, and the boundary can
+    /// be concave, in which case the ray may pass through the medium more
+    /// than once.
+    fn hit(
+        &self,
+        r: Ray,
+        t_min: f32,
+        t_max: f32,
+    ) -> Option<(HitRecord, &Box<dyn Material>)> {
+        let length = r.dir().length();
+
+        // The distance the ray travels through the medium before it
+        // scatters
+        let mut distance = -random::<f32>().ln() / self.density;
+        let mut t = t_min;
+
+        // Whether the ray is inside the medium is decided by whether it
+        // leaves or enters through the next boundary crossing. Past the last
+        // crossing the ray must be outside.
+        for (hit_record, _) in self.boundary.crossings(r, t_min, f32::MAX) {
+            let (inside, next) = (!hit_record.front_face, hit_record.t);
+
+            if inside {
+                let traveled = (f32::min(next, t_max) - t) * length;
+                if distance < traveled {
+                    let t = t + distance / length;
+
+                    let hit_record = HitRecord::in_medium(r, t);
+                    return Some((hit_record, &self.phase_function));
+                }
+
+                distance -= traveled;
+            }
+
+            if next >= t_max {
+                return None;
+            }
+            t = next;
+        }
+
+        None
+    }
+
+    fn bounding_box(&self, t0: f32, t1: f32) -> Option<Aabb> {
+        self.boundary.bounding_box(t0, t1)
+    }
+}
+
+impl ConstantMedium {
+    pub fn new(
+        boundary: Box<dyn Hittable>,
+        density: f32,
+        phase_function: Box<dyn Material>,
+    ) -> Self {
+        Self {
+            boundary,
+            density,
+            phase_function,
+        }
+    }
+}
@@ -0,0 +1,144 @@
+//! Constructive solid geometry, which combines closed objects by the parts
+//! of space they enclose, such as a block with a hole drilled through it
+
+use super::{HitRecord, Hittable};
+use crate::{aabb::Aabb, materials::Material, Ray, Vector};
+
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum CsgOperation {
+    /// The space inside either object
+    Union,
+    /// The space inside both objects
+    Intersection,
+    /// The space inside the left object but not the right one
+    Difference,
+}
+
+impl CsgOperation {
+    fn contains(self, in_left: bool, in_right: bool) -> bool {
+        match self {
+            CsgOperation::Union => in_left || in_right,
+            CsgOperation::Intersection => in_left && in_right,
+            CsgOperation::Difference => in_left && !in_right,
+        }
+    }
+}
+
+/// Two closed objects combined by a [`CsgOperation`]. The surface of the
+/// result is made up of the parts of each object's surface that border
+/// the combined space, and keeps the material of the object it came from.
+///
+/// [`CsgOperation`]: enum.CsgOperation.html
+#[derive(Debug)]
+pub struct Csg {
+    operation: CsgOperation,
+    left: Box<dyn Hittable>,
+    right: Box<dyn Hittable>,
+}
+
+impl Csg {
+    pub fn new(
+        operation: CsgOperation,
+        left: Box<dyn Hittable>,
+        right: Box<dyn Hittable>,
+    ) -> Self {
+        Self {
+            operation,
+            left,
+            right,
+        }
+    }
+}
+
+impl Hittable for Csg {
+    /// Walks through the crossings of both objects in order, keeping track
+    /// of whether the ray is inside each of them, until the ray enters or
+    /// leaves the combined space. Each object's crossings are found one at
+    /// a time, so the walk only goes as far along the ray as it has to.
+    fn hit(
+        &self,
+        r: Ray,
+        t_min: f32,
+        t_max: f32,
+    ) -> Option<(HitRecord, &Box<dyn Material>)> {
+        // The first crossings are looked for past t_max as well, since
+        // they tell whether the ray starts inside each object: it does if
+        // it leaves the object first
+        let mut left = self.left.crossings(r, t_min, f32::MAX).peekable();
+        let mut right = self.right.crossings(r, t_min, f32::MAX).peekable();
+        let starts_inside = |next: Option<&(HitRecord, _)>| match next {
+            Some((hit_record, _)) => !hit_record.front_face,
+            None => false,
+        };
+
+        let mut in_left = starts_inside(left.peek());
+        let mut in_right = starts_inside(right.peek());
+        let inside = self.operation.contains(in_left, in_right);
+
+        loop {
+            let from_left = match (left.peek(), right.peek()) {
+                (Some(l), Some(r)) => l.0.t <= r.0.t,
+                (Some(_), None) => true,
+                (None, Some(_)) => false,
+                (None, None) => return None,
+            };
+
+            let (mut hit_record, material) = if from_left {
+                left.next().unwrap()
+            } else {
+                right.next().unwrap()
+            };
+
+            if hit_record.t > t_max {
+                return None;
+            }
+
+            if from_left {
+                in_left = hit_record.front_face;
+            } else {
+                in_right = hit_record.front_face;
+            }
+
+            if self.operation.contains(in_left, in_right) == inside {
+                continue;
+            }
+
+            // The inside of the subtracted object is outside of the result,
+            // so its surface faces the other way
+            if !from_left && self.operation == CsgOperation::Difference {
+                hit_record.geometric_normal = -hit_record.geometric_normal;
+                hit_record.shading_normal = -hit_record.shading_normal;
+                hit_record.front_face = !hit_record.front_face;
+            }
+
+            return Some((hit_record, material));
+        }
+    }
+
+    fn bounding_box(&self, t0: f32, t1: f32) -> Option<Aabb> {
+        let left = self.left.bounding_box(t0, t1);
+        let right = self.right.bounding_box(t0, t1);
+
+        match self.operation {
+            CsgOperation::Union => Some(Aabb::surrounding_box(left?, right?)),
+            CsgOperation::Intersection => match (left, right) {
+                (Some(left), Some(right)) => {
+                    let min = Vector::new(
+                        f32::max(left.min.x, right.min.x),
+                        f32::max(left.min.y, right.min.y),
+                        f32::max(left.min.z, right.min.z),
+                    );
+                    let max = Vector::new(
+                        f32::min(left.max.x, right.max.x),
+                        f32::min(left.max.y, right.max.y),
+                        f32::min(left.max.z, right.max.z),
+                    );
+
+                    Some(Aabb::new(min, max))
+                }
+                (left, right) => left.or(right),
+            },
+            CsgOperation::Difference => left,
+        }
+    }
+}
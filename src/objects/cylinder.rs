@@ -0,0 +1,149 @@
+//! A cylinder standing upright on its base, optionally closed off by disks
+//! at either end
+
+use super::{disk::DiskSurface, surface_pdf, HitRecord, Hittable};
+use crate::{
+    aabb::Aabb,
+    materials::Material,
+    util::{azimuth, solve_quadratic},
+    Ray, Vector,
+};
+
+use rand::prelude::*;
+use std::f32::consts::PI;
+
+#[derive(Debug)]
+pub struct Cylinder {
+    base: Vector,
+    radius: f32,
+    height: f32,
+    /// The bottom and top caps
+    caps: Option<[DiskSurface; 2]>,
+    material: Box<dyn Material>,
+}
+
+impl Hittable for Cylinder {
+    fn hit(
+        &self,
+        r: Ray,
+        t_min: f32,
+        t_max: f32,
+    ) -> Option<(HitRecord, &Box<dyn Material>)> {
+        let mut closest = self.hit_side(r, t_min, t_max);
+
+        if let Some(caps) = &self.caps {
+            for cap in caps {
+                let t_max = closest.map_or(t_max, |hit_record| hit_record.t);
+                if let Some(hit_record) = cap.hit(r, t_min, t_max) {
+                    closest = Some(hit_record);
+                }
+            }
+        }
+
+        closest.map(|hit_record| (hit_record, &self.material))
+    }
+
+    fn bounding_box(&self, _t0: f32, _t1: f32) -> Option<Aabb> {
+        Some(Aabb::new(
+            self.base - Vector::new(self.radius, 0.0, self.radius),
+            self.base + Vector::new(self.radius, self.height, self.radius),
+        ))
+    }
+
+    fn pdf_value(&self, o: Vector, v: Vector) -> f32 {
+        surface_pdf(self, self.area(), o, v)
+    }
+
+    fn random(&self, o: Vector) -> Vector {
+        let side_area = self.side_area();
+        let point = match &self.caps {
+            Some(caps) if random::<f32>() * self.area() > side_area => {
+                caps[random::<bool>() as usize].random_point()
+            }
+            _ => {
+                let phi = 2.0 * PI * random::<f32>();
+                self.base
+                    + Vector::new(
+                        self.radius * phi.cos(),
+                        self.height * random::<f32>(),
+                        self.radius * phi.sin(),
+                    )
+            }
+        };
+
+        point - o
+    }
+}
+
+impl Cylinder {
+    pub fn new(
+        base: Vector,
+        radius: f32,
+        height: f32,
+        capped: bool,
+        material: Box<dyn Material>,
+    ) -> Self {
+        let caps = if capped {
+            let top = base + Vector::new(0.0, height, 0.0);
+            Some([
+                DiskSurface::new(base, radius, 0.0, -1.0),
+                DiskSurface::new(top, radius, 0.0, 1.0),
+            ])
+        } else {
+            None
+        };
+
+        Self {
+            base,
+            radius,
+            height,
+            caps,
+            material,
+        }
+    }
+
+    /// u goes around the cylinder and v goes up from the base
+    fn hit_side(&self, r: Ray, t_min: f32, t_max: f32) -> Option<HitRecord> {
+        let o = r.origin() - self.base;
+        let d = r.dir();
+
+        let (t0, t1) = solve_quadratic(
+            d.x * d.x + d.z * d.z,
+            2.0 * (o.x * d.x + o.z * d.z),
+            o.x * o.x + o.z * o.z - self.radius * self.radius,
+        )?;
+
+        for &t in &[t0, t1] {
+            if t <= t_min || t >= t_max {
+                continue;
+            }
+
+            let p = o + t * d;
+            if p.y < 0.0 || p.y > self.height {
+                continue;
+            }
+
+            let normal = Vector::new(p.x, 0.0, p.z) / self.radius;
+            let u = azimuth(p.x, p.z) / (2.0 * PI);
+            let v = p.y / self.height;
+
+            let dpdu = 2.0 * PI * Vector::new(-p.z, 0.0, p.x);
+            let dpdv = Vector::new(0.0, self.height, 0.0);
+
+            return Some(HitRecord::new(r, t, (u, v), normal, (dpdu, dpdv)));
+        }
+
+        None
+    }
+
+    fn side_area(&self) -> f32 {
+        2.0 * PI * self.radius * self.height
+    }
+
+    fn area(&self) -> f32 {
+        match self.caps {
+            Some(_) => self.side_area() + 2.0 * PI * self.radius * self.radius,
+            None => self.side_area(),
+        }
+    }
+}
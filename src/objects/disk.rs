@@ -0,0 +1,149 @@
+//! Flat disks and annuli (disks with a hole in the middle) lying parallel
+//! to the XZ plane
+
+use super::{surface_pdf, HitRecord, Hittable};
+use crate::{aabb::Aabb, materials::Material, util::azimuth, Ray, Vector};
+
+use rand::prelude::*;
+use std::f32::consts::PI;
+
+/// Padding for the bounding box along the disk's normal
+const BOX_PADDING: f32 = 0.0001;
+
+#[derive(Debug)]
+pub struct Disk {
+    surface: DiskSurface,
+    material: Box<dyn Material>,
+}
+
+/// The shape of a disk without a material, which the other quadrics also
+/// use for their caps
+#[derive(Debug, Copy, Clone)]
+pub(super) struct DiskSurface {
+    center: Vector,
+    radius: f32,
+    inner_radius: f32,
+    /// The Y component of the normal, either 1 or -1
+    normal: f32,
+}
+
+impl Hittable for Disk {
+    fn hit(
+        &self,
+        r: Ray,
+        t_min: f32,
+        t_max: f32,
+    ) -> Option<(HitRecord, &Box<dyn Material>)> {
+        self.surface
+            .hit(r, t_min, t_max)
+            .map(|hit_record| (hit_record, &self.material))
+    }
+
+    fn bounding_box(&self, _t0: f32, _t1: f32) -> Option<Aabb> {
+        Some(self.surface.bounding_box())
+    }
+
+    fn pdf_value(&self, o: Vector, v: Vector) -> f32 {
+        surface_pdf(self, self.surface.area(), o, v)
+    }
+
+    fn random(&self, o: Vector) -> Vector {
+        self.surface.random_point() - o
+    }
+}
+
+impl Disk {
+    /// Creates a disk facing up, or down if flipped. An inner radius above
+    /// zero makes it an annulus.
+    pub fn new(
+        center: Vector,
+        radius: f32,
+        inner_radius: f32,
+        flip: bool,
+        material: Box<dyn Material>,
+    ) -> Self {
+        let normal = if flip { -1.0 } else { 1.0 };
+
+        Self {
+            surface: DiskSurface::new(center, radius, inner_radius, normal),
+            material,
+        }
+    }
+}
+
+impl DiskSurface {
+    pub(super) fn new(
+        center: Vector,
+        radius: f32,
+        inner_radius: f32,
+        normal: f32,
+    ) -> Self {
+        Self {
+            center,
+            radius,
+            inner_radius,
+            normal,
+        }
+    }
+
+    /// u goes around the disk and v goes inwards from the outer edge
+    pub(super) fn hit(
+        &self,
+        r: Ray,
+        t_min: f32,
+        t_max: f32,
+    ) -> Option<HitRecord> {
+        let t = (self.center.y - r.origin().y) / r.dir().y;
+
+        // This also rejects rays parallel to the disk, where t isn't finite
+        if !(t >= t_min && t <= t_max) {
+            return None;
+        }
+
+        let d = r.point_at_parameter(t) - self.center;
+        let dist_squared = d.x * d.x + d.z * d.z;
+        if dist_squared > self.radius * self.radius
+            || dist_squared < self.inner_radius * self.inner_radius
+        {
+            return None;
+        }
+
+        let dist = dist_squared.sqrt();
+        let u = azimuth(d.x, d.z) / (2.0 * PI);
+        let v = (self.radius - dist) / (self.radius - self.inner_radius);
+
+        let dpdu = 2.0 * PI * Vector::new(-d.z, 0.0, d.x);
+        let dpdv = if dist > 0.0 {
+            (self.inner_radius - self.radius) / dist
+                * Vector::new(d.x, 0.0, d.z)
+        } else {
+            Vector::zeros()
+        };
+
+        Some(HitRecord::new(
+            r,
+            t,
+            (u, v),
+            Vector::new(0.0, self.normal, 0.0),
+            (dpdu, dpdv),
+        ))
+    }
+
+    pub(super) fn bounding_box(&self) -> Aabb {
+        let extent = Vector::new(self.radius, BOX_PADDING, self.radius);
+        Aabb::new(self.center - extent, self.center + extent)
+    }
+
+    pub(super) fn area(&self) -> f32 {
+        PI * (self.radius * self.radius - self.inner_radius * self.inner_radius)
+    }
+
+    /// Picks a point uniformly over the disk's area
+    pub(super) fn random_point(&self) -> Vector {
+        let (r0, r1) = (self.inner_radius, self.radius);
+        let dist = f32::sqrt(r0 * r0 + random::<f32>() * (r1 * r1 - r0 * r0));
+        let phi = 2.0 * PI * random::<f32>();
+
+        self.center + Vector::new(dist * phi.cos(), 0.0, dist * phi.sin())
+    }
+}
@@ -0,0 +1,224 @@
+//! A volume of smoke, cloud or fire whose density varies across a voxel
+//! grid
+
+use super::{HitRecord, Hittable};
+use crate::{aabb::Aabb, loaders::VoxelGrid, materials::Material, Ray, Vector};
+
+use rand::prelude::*;
+use std::sync::Arc;
+
+/// The number of majorant cells along each axis, unless the grid has fewer
+/// voxels than that
+const MAJORANT_CELLS: usize = 16;
+
+#[derive(Debug)]
+pub struct GridVolume {
+    density: Arc<VoxelGrid>,
+    /// Multiplies every value in the density grid
+    density_scale: f32,
+    /// Temperatures in kelvin, which are passed to the phase function in
+    /// the hit record
+    temperature: Option<Arc<VoxelGrid>>,
+    temperature_scale: f32,
+    majorants: Majorants,
+    phase_function: Box<dyn Material>,
+}
+
+/// A coarse grid over the volume holding the highest density within each
+/// cell, so that tracking can take long steps through thin regions
+#[derive(Debug)]
+struct Majorants {
+    resolution: [usize; 3],
+    values: Vec<f32>,
+}
+
+impl Hittable for GridVolume {
+    /// Finds where a ray scatters in the volume using delta tracking. Each
+    /// step samples a tentative collision as if the volume were as dense as
+    /// the cell's majorant, which is a real collision with a probability of
+    /// the actual density over the majorant.
+    fn hit(
+        &self,
+        r: Ray,
+        t_min: f32,
+        t_max: f32,
+    ) -> Option<(HitRecord, &Box<dyn Material>)> {
+        let length = r.dir().length();
+        let mut collision = None;
+
+        self.traverse(r, t_min, t_max, |start, end, majorant| {
+            let mut t = start;
+            loop {
+                t -= (1.0 - random::<f32>()).ln() / (majorant * length);
+                if t >= end {
+                    return false;
+                }
+
+                let density = self.density_at(r.point_at_parameter(t));
+                if random::<f32>() * majorant < density {
+                    collision = Some(t);
+                    return true;
+                }
+            }
+        });
+
+        let t = collision?;
+        let mut hit_record = HitRecord::in_medium(r, t);
+        hit_record.temperature = self.temperature.as_ref().map(|grid| {
+            grid.sample(r.point_at_parameter(t)) * self.temperature_scale
+        });
+
+        Some((hit_record, &self.phase_function))
+    }
+
+    fn bounding_box(&self, _t0: f32, _t1: f32) -> Option<Aabb> {
+        Some(Aabb::new(self.density.min, self.density.max))
+    }
+}
+
+impl GridVolume {
+    pub fn new(
+        density: Arc<VoxelGrid>,
+        density_scale: f32,
+        temperature: Option<(Arc<VoxelGrid>, f32)>,
+        phase_function: Box<dyn Material>,
+    ) -> Self {
+        let majorants = Majorants::new(&density);
+        let (temperature, temperature_scale) = match temperature {
+            Some((grid, scale)) => (Some(grid), scale),
+            None => (None, 0.0),
+        };
+
+        Self {
+            density,
+            density_scale,
+            temperature,
+            temperature_scale,
+            majorants,
+            phase_function,
+        }
+    }
+
+    fn density_at(&self, p: Vector) -> f32 {
+        self.density.sample(p) * self.density_scale
+    }
+
+    /// Walks the majorant cells that the ray passes through between
+    /// `t_min` and `t_max` in order, calling `f` with the start and end of
+    /// the ray's span in each cell and the cell's scaled majorant. Empty
+    /// cells are skipped. Stops early if `f` returns true.
+    fn traverse<F>(&self, r: Ray, t_min: f32, t_max: f32, mut f: F)
+    where
+        F: FnMut(f32, f32, f32) -> bool,
+    {
+        let grid = &self.density;
+        let resolution = self.majorants.resolution;
+
+        // Clip the ray to the grid's bounds
+        let (t0, t1) = Aabb::new(grid.min, grid.max).clip(r, t_min, t_max);
+        if !(t0 < t1) {
+            return;
+        }
+
+        // The ray in the majorant grid's cell coordinates
+        let mut origin = Vector::zeros();
+        let mut dir = Vector::zeros();
+        for a in 0..3 {
+            let scale = resolution[a] as f32 / (grid.max[a] - grid.min[a]);
+            origin[a] = (r.origin()[a] - grid.min[a]) * scale;
+            dir[a] = r.dir()[a] * scale;
+        }
+
+        let mut cell = [0; 3];
+        let mut step = [0; 3];
+        let mut t_next = [f32::INFINITY; 3];
+        let mut t_delta = [f32::INFINITY; 3];
+
+        for a in 0..3 {
+            let start = origin[a] + t0 * dir[a];
+            let last = resolution[a] - 1;
+            cell[a] = usize::min(start.max(0.0) as usize, last) as isize;
+
+            if dir[a] > 0.0 {
+                step[a] = 1;
+                t_next[a] = (cell[a] as f32 + 1.0 - origin[a]) / dir[a];
+                t_delta[a] = 1.0 / dir[a];
+            } else if dir[a] < 0.0 {
+                step[a] = -1;
+                t_next[a] = (cell[a] as f32 - origin[a]) / dir[a];
+                t_delta[a] = -1.0 / dir[a];
+            }
+        }
+
+        let mut t = t0;
+        loop {
+            let axis = (0..3)
+                .min_by(|&a, &b| t_next[a].partial_cmp(&t_next[b]).unwrap())
+                .unwrap();
+            let end = f32::min(t_next[axis], t1);
+
+            let majorant = self.majorants.value(cell) * self.density_scale;
+            if majorant > 0.0 && end > t && f(t, end, majorant) {
+                return;
+            }
+
+            if end >= t1 {
+                return;
+            }
+
+            t = end;
+            cell[axis] += step[axis];
+            if cell[axis] < 0 || cell[axis] >= resolution[axis] as isize {
+                return;
+            }
+            t_next[axis] += t_delta[axis];
+        }
+    }
+}
+
+impl Majorants {
+    fn new(grid: &VoxelGrid) -> Self {
+        let mut resolution = [0; 3];
+        for a in 0..3 {
+            resolution[a] = usize::min(MAJORANT_CELLS, grid.resolution[a] - 1);
+        }
+
+        // The voxels that can affect the interpolated density within a
+        // cell along one axis
+        let span = |a: usize, cell: usize| {
+            let voxels = (grid.resolution[a] - 1) as f32;
+            let cells = resolution[a] as f32;
+            let lo = (cell as f32 / cells * voxels).floor() as usize;
+            let hi = ((cell + 1) as f32 / cells * voxels).ceil() as usize;
+
+            lo..=usize::min(hi, grid.resolution[a] - 1)
+        };
+
+        let mut values = Vec::with_capacity(resolution.iter().product());
+        for z in 0..resolution[2] {
+            for y in 0..resolution[1] {
+                for x in 0..resolution[0] {
+                    let mut max = 0.0;
+                    for k in span(2, z) {
+                        for j in span(1, y) {
+                            for i in span(0, x) {
+                                max = f32::max(max, grid.value(i, j, k));
+                            }
+                        }
+                    }
+
+                    values.push(max);
+                }
+            }
+        }
+
+        Self { resolution, values }
+    }
+
+    fn value(&self, cell: [isize; 3]) -> f32 {
+        let [nx, ny, _] = self.resolution;
+        let [x, y, z] = cell;
+
+        self.values[(z as usize * ny + y as usize) * nx + x as usize]
+    }
+}
@@ -0,0 +1,362 @@
+//! Terrain whose heights are taken from a grayscale image
+
+use super::{intersect_triangle, HitRecord, Hittable};
+use crate::{aabb::Aabb, loaders::HeightMap, materials::Material, Ray, Vector};
+
+use std::sync::Arc;
+
+/// Padding for the bounds of flat parts of the terrain
+const BOX_PADDING: f32 = 0.0001;
+
+/// A sheet of terrain spanning a box from `corner` to `corner + size`. The
+/// pixels of the height map are spread evenly over X and Z, with the first
+/// row at the smallest Z, and are raised by their value times the size's
+/// Y component.
+///
+/// Each cell between four pixels is split into two triangles, which are
+/// found by walking a hierarchy of the lowest and highest heights over
+/// ever larger blocks of cells rather than by putting them in a BVH.
+/// Normals are interpolated from the slope at each pixel so the terrain
+/// looks smooth, and the UV coordinates map the unit square over the whole
+/// terrain the same way up as the height map.
+#[derive(Debug)]
+pub struct Heightfield {
+    map: Arc<HeightMap>,
+    corner: Vector,
+    size: Vector,
+    /// The shading normal at each pixel
+    normals: Vec<Vector>,
+    /// The range of heights in each block of cells, starting with single
+    /// cells. Each level halves the number of blocks along each side until
+    /// one block covers the whole map.
+    levels: Vec<Level>,
+    material: Box<dyn Material>,
+}
+
+#[derive(Debug)]
+struct Level {
+    width: usize,
+    height: usize,
+    /// The lowest and highest values in each block, row by row
+    ranges: Vec<(f32, f32)>,
+}
+
+/// Where a ray hits the terrain, as the pixels at the corners of the
+/// triangle that was hit and the hit's barycentric coordinates
+struct CellHit {
+    t: f32,
+    pixels: [(usize, usize); 3],
+    b1: f32,
+    b2: f32,
+}
+
+impl Hittable for Heightfield {
+    fn hit(
+        &self,
+        r: Ray,
+        t_min: f32,
+        t_max: f32,
+    ) -> Option<(HitRecord, &Box<dyn Material>)> {
+        let top = self.levels.len() - 1;
+        self.block_entry(r, top, 0, 0, t_min, t_max)?;
+
+        let mut closest = None;
+        let mut t_max = t_max;
+        self.traverse(r, top, (0, 0), t_min, &mut t_max, &mut closest);
+        let CellHit { t, pixels, b1, b2 } = closest?;
+        let b0 = 1.0 - b1 - b2;
+
+        let [p0, p1, p2] = [
+            self.position(pixels[0]),
+            self.position(pixels[1]),
+            self.position(pixels[2]),
+        ];
+        let normal_at =
+            |(x, y): (usize, usize)| self.normals[y * self.map.width + x];
+        let shading_normal = b0 * normal_at(pixels[0])
+            + b1 * normal_at(pixels[1])
+            + b2 * normal_at(pixels[2]);
+        let shading_normal = shading_normal.normalized_exact();
+
+        let mut normal = Vector::cross(p1 - p0, p2 - p0);
+        normal = normal.normalized_exact();
+        if Vector::dot(normal, shading_normal) < 0.0 {
+            normal = -normal;
+        }
+
+        let p = r.point_at_parameter(t);
+        let u = (p.x - self.corner.x) / self.size.x;
+        let v = 1.0 - (p.z - self.corner.z) / self.size.z;
+
+        // The slope of the triangle's plane gives how the position changes
+        // along the texture coordinates
+        let (slope_x, slope_z) = (-normal.x / normal.y, -normal.z / normal.y);
+        let dpdu = self.size.x * Vector::new(1.0, slope_x, 0.0);
+        let dpdv = -self.size.z * Vector::new(0.0, slope_z, 1.0);
+
+        let mut hit_record = HitRecord::new(r, t, (u, v), normal, (dpdu, dpdv));
+        hit_record.shading_normal = shading_normal;
+
+        Some((hit_record, &self.material))
+    }
+
+    fn bounding_box(&self, _t0: f32, _t1: f32) -> Option<Aabb> {
+        let top = self.levels.len() - 1;
+        let (min, max) = self.block_bounds(top, 0, 0);
+
+        Some(Aabb::new(min, max))
+    }
+}
+
+impl Heightfield {
+    pub fn new(
+        map: Arc<HeightMap>,
+        corner: Vector,
+        size: Vector,
+        material: Box<dyn Material>,
+    ) -> Self {
+        let (width, height) = (map.width, map.height);
+
+        // The slope at each pixel is found from its neighbours on either
+        // side, or from the pixel itself at the edges
+        let spacing_x = size.x / (width - 1) as f32;
+        let spacing_z = size.z / (height - 1) as f32;
+        let mut normals = Vec::with_capacity(width * height);
+        for y in 0..height {
+            for x in 0..width {
+                let (x0, x1) =
+                    (x.saturating_sub(1), usize::min(x + 1, width - 1));
+                let (y0, y1) =
+                    (y.saturating_sub(1), usize::min(y + 1, height - 1));
+
+                let slope_x = (map.value(x1, y) - map.value(x0, y)) * size.y
+                    / ((x1 - x0) as f32 * spacing_x);
+                let slope_z = (map.value(x, y1) - map.value(x, y0)) * size.y
+                    / ((y1 - y0) as f32 * spacing_z);
+
+                let normal = Vector::new(-slope_x, 1.0, -slope_z);
+                normals.push(normal.normalized_exact());
+            }
+        }
+
+        let mut cells = Level {
+            width: width - 1,
+            height: height - 1,
+            ranges: Vec::with_capacity((width - 1) * (height - 1)),
+        };
+        for y in 0..height - 1 {
+            for x in 0..width - 1 {
+                let corners = [
+                    map.value(x, y),
+                    map.value(x + 1, y),
+                    map.value(x, y + 1),
+                    map.value(x + 1, y + 1),
+                ];
+
+                cells.ranges.push((
+                    corners.iter().cloned().fold(f32::INFINITY, f32::min),
+                    corners.iter().cloned().fold(f32::NEG_INFINITY, f32::max),
+                ));
+            }
+        }
+
+        let mut levels = vec![cells];
+        loop {
+            let below = levels.last().unwrap();
+            if below.width == 1 && below.height == 1 {
+                break;
+            }
+
+            let mut level = Level {
+                width: (below.width + 1) / 2,
+                height: (below.height + 1) / 2,
+                ranges: Vec::new(),
+            };
+            for y in 0..level.height {
+                for x in 0..level.width {
+                    let mut range = (f32::INFINITY, f32::NEG_INFINITY);
+                    for (cx, cy) in below.children(x, y) {
+                        let (lo, hi) = below.range(cx, cy);
+                        range = (f32::min(range.0, lo), f32::max(range.1, hi));
+                    }
+
+                    level.ranges.push(range);
+                }
+            }
+
+            levels.push(level);
+        }
+
+        Self {
+            map,
+            corner,
+            size,
+            normals,
+            levels,
+            material,
+        }
+    }
+
+    fn position(&self, (x, y): (usize, usize)) -> Vector {
+        let (width, height) = (self.map.width, self.map.height);
+
+        self.corner
+            + Vector::new(
+                x as f32 / (width - 1) as f32 * self.size.x,
+                self.map.value(x, y) * self.size.y,
+                y as f32 / (height - 1) as f32 * self.size.z,
+            )
+    }
+
+    /// Visits the blocks within a block that the ray passes through, the
+    /// nearest first, and keeps the closest hit with the cells inside them.
+    /// The ray must pass through the block itself.
+    fn traverse(
+        &self,
+        r: Ray,
+        level: usize,
+        (x, y): (usize, usize),
+        t_min: f32,
+        t_max: &mut f32,
+        closest: &mut Option<CellHit>,
+    ) {
+        if level == 0 {
+            if let Some(hit) = self.hit_cell(r, x, y, t_min, *t_max) {
+                *t_max = hit.t;
+                *closest = Some(hit);
+            }
+            return;
+        }
+
+        let mut children: Vec<_> = self.levels[level - 1]
+            .children(x, y)
+            .filter_map(|(cx, cy)| {
+                self.block_entry(r, level - 1, cx, cy, t_min, *t_max)
+                    .map(|t| (t, cx, cy))
+            })
+            .collect();
+        children.sort_by(|a, b| a.0.partial_cmp(&b.0).unwrap());
+
+        for (entry, cx, cy) in children {
+            // Once a hit is found, blocks which the ray enters beyond it
+            // can't hold a closer one
+            if entry > *t_max {
+                break;
+            }
+
+            self.traverse(r, level - 1, (cx, cy), t_min, t_max, closest);
+        }
+    }
+
+    /// Intersects the ray with the two triangles making up a cell
+    fn hit_cell(
+        &self,
+        r: Ray,
+        x: usize,
+        y: usize,
+        t_min: f32,
+        t_max: f32,
+    ) -> Option<CellHit> {
+        // Both triangles are wound so that their normals point up
+        let triangles = [
+            [(x, y), (x, y + 1), (x + 1, y + 1)],
+            [(x, y), (x + 1, y + 1), (x + 1, y)],
+        ];
+
+        let mut closest = None;
+        let mut t_max = t_max;
+        for &pixels in &triangles {
+            let [p0, p1, p2] = [
+                self.position(pixels[0]),
+                self.position(pixels[1]),
+                self.position(pixels[2]),
+            ];
+
+            if let Some((t, b1, b2)) =
+                intersect_triangle(r, p0, p1, p2, t_min, t_max)
+            {
+                t_max = t;
+                closest = Some(CellHit { t, pixels, b1, b2 });
+            }
+        }
+
+        closest
+    }
+
+    /// The bounds of a block of cells
+    fn block_bounds(
+        &self,
+        level: usize,
+        x: usize,
+        y: usize,
+    ) -> (Vector, Vector) {
+        let (lo, hi) = self.levels[level].range(x, y);
+        let cells_x = self.levels[0].width;
+        let cells_z = self.levels[0].height;
+
+        // The cells covered by the block, which is cut short at the far
+        // edges of the map
+        let x0 = x << level;
+        let x1 = usize::min((x + 1) << level, cells_x);
+        let y0 = y << level;
+        let y1 = usize::min((y + 1) << level, cells_z);
+
+        let a = self.corner
+            + Vector::new(
+                x0 as f32 / cells_x as f32 * self.size.x,
+                lo * self.size.y,
+                y0 as f32 / cells_z as f32 * self.size.z,
+            );
+        let b = self.corner
+            + Vector::new(
+                x1 as f32 / cells_x as f32 * self.size.x,
+                hi * self.size.y,
+                y1 as f32 / cells_z as f32 * self.size.z,
+            );
+
+        let min = Vector::new(a.x.min(b.x), a.y.min(b.y), a.z.min(b.z));
+        let max = Vector::new(a.x.max(b.x), a.y.max(b.y), a.z.max(b.z));
+
+        (min + -BOX_PADDING, max + BOX_PADDING)
+    }
+
+    /// Returns where the ray enters a block of cells, if it passes through
+    /// it between `t_min` and `t_max`
+    fn block_entry(
+        &self,
+        r: Ray,
+        level: usize,
+        x: usize,
+        y: usize,
+        t_min: f32,
+        t_max: f32,
+    ) -> Option<f32> {
+        let (min, max) = self.block_bounds(level, x, y);
+        let (t0, t1) = Aabb::new(min, max).clip(r, t_min, t_max);
+
+        if t0 <= t1 {
+            Some(t0)
+        } else {
+            None
+        }
+    }
+}
+
+impl Level {
+    fn range(&self, x: usize, y: usize) -> (f32, f32) {
+        self.ranges[y * self.width + x]
+    }
+
+    /// The blocks on this level covered by a block on the level above
+    fn children(
+        &self,
+        x: usize,
+        y: usize,
+    ) -> impl Iterator<Item = (usize, usize)> {
+        let (width, height) = (self.width, self.height);
+
+        (0..4)
+            .map(move |i| (2 * x + i % 2, 2 * y + i / 2))
+            .filter(move |&(cx, cy)| cx < width && cy < height)
+    }
+}
@@ -0,0 +1,56 @@
+use super::{HitRecord, Hittable};
+use crate::{aabb::Aabb, materials::Material, Ray, Vector};
+
+use std::sync::Arc;
+
+/// A copy of a named object definition. The geometry (including any BVH
+/// inside it) is shared between every instance, only the optional
+/// material override belongs to the instance itself. Instances are
+/// positioned by wrapping them in a [`Transform`].
+///
+/// [`Transform`]: struct.Transform.html
+#[derive(Debug)]
+pub struct Instance {
+    geometry: Arc<dyn Hittable>,
+    material: Option<Box<dyn Material>>,
+}
+
+impl Hittable for Instance {
+    fn hit(
+        &self,
+        r: Ray,
+        t_min: f32,
+        t_max: f32,
+    ) -> Option<(HitRecord, &Box<dyn Material>)> {
+        self.geometry
+            .hit(r, t_min, t_max)
+            .map(|(hit_record, material)| {
+                (hit_record, self.material.as_ref().unwrap_or(material))
+            })
+    }
+
+    fn bounding_box(&self, t0: f32, t1: f32) -> Option<Aabb> {
+        self.geometry.bounding_box(t0, t1)
+    }
+
+    fn pdf_value(&self, o: Vector, v: Vector) -> f32 {
+        self.geometry.pdf_value(o, v)
+    }
+
+    fn random(&self, o: Vector) -> Vector {
+        self.geometry.random(o)
+    }
+
+    fn random_point(&self, o: Vector) -> Vector {
+        self.geometry.random_point(o)
+    }
+}
+
+impl Instance {
+    pub fn new(
+        geometry: Arc<dyn Hittable>,
+        material: Option<Box<dyn Material>>,
+    ) -> Self {
+        Self { geometry, material }
+    }
+}
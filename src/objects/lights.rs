@@ -0,0 +1,65 @@
+use super::{HitRecord, Hittable};
+use crate::{aabb::Aabb, materials::Material, Ray, Vector};
+
+use rand::prelude::*;
+
+/// The emissive objects of a scene that rays are sent towards to find the
+/// light reaching a surface. Each light is picked with the same chance, so
+/// the PDF is the average of theirs.
+#[derive(Debug, Default)]
+pub struct Lights {
+    lights: Vec<Box<dyn Hittable>>,
+}
+
+impl Lights {
+    pub fn new(lights: Vec<Box<dyn Hittable>>) -> Self {
+        Self { lights }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.lights.is_empty()
+    }
+}
+
+impl Hittable for Lights {
+    fn hit(
+        &self,
+        r: Ray,
+        t_min: f32,
+        t_max: f32,
+    ) -> Option<(HitRecord, &Box<dyn Material>)> {
+        let mut result = None;
+        let mut closest_so_far = t_max;
+
+        for light in &self.lights {
+            if let Some((hr, mat)) = light.hit(r, t_min, closest_so_far) {
+                result = Some((hr, mat));
+                closest_so_far = hr.t;
+            }
+        }
+
+        result
+    }
+
+    fn bounding_box(&self, _t0: f32, _t1: f32) -> Option<Aabb> {
+        None
+    }
+
+    fn pdf_value(&self, o: Vector, v: Vector) -> f32 {
+        let sum: f32 = self.lights.iter().map(|l| l.pdf_value(o, v)).sum();
+
+        sum / self.lights.len() as f32
+    }
+
+    fn random(&self, o: Vector) -> Vector {
+        let i = thread_rng().gen_range(0, self.lights.len());
+
+        self.lights[i].random(o)
+    }
+
+    fn random_point(&self, o: Vector) -> Vector {
+        let i = thread_rng().gen_range(0, self.lights.len());
+
+        self.lights[i].random_point(o)
+    }
+}
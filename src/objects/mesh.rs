@@ -0,0 +1,164 @@
+use super::{intersect_triangle, triangle_box, HitRecord, Hittable};
+use crate::{
+    aabb::Aabb,
+    loaders::MeshData,
+    materials::{Lambertian, Material},
+    textures::ConstantTexture,
+    Ray, Vector,
+};
+
+use std::sync::Arc;
+
+/// A loaded mesh along with the materials its faces use. The vertex data
+/// is shared with every other Mesh made from the same file.
+#[derive(Debug)]
+pub struct Mesh {
+    data: Arc<MeshData>,
+    /// Used for every face instead of the materials from the file
+    material: Option<Box<dyn Material>>,
+    /// Used for faces that the file doesn't give a material
+    default_material: Box<dyn Material>,
+}
+
+/// A single face of a [`Mesh`]. These are put into a BVH rather than being
+/// intersected by the Mesh itself.
+///
+/// [`Mesh`]: struct.Mesh.html
+#[derive(Debug)]
+struct MeshTriangle {
+    mesh: Arc<Mesh>,
+    face: usize,
+}
+
+impl Hittable for MeshTriangle {
+    fn hit(
+        &self,
+        r: Ray,
+        t_min: f32,
+        t_max: f32,
+    ) -> Option<(HitRecord, &Box<dyn Material>)> {
+        let data = &self.mesh.data;
+        let face = &data.faces[self.face];
+        let [p0, p1, p2] = self.vertices();
+
+        let (t, b1, b2) = intersect_triangle(r, p0, p1, p2, t_min, t_max)?;
+        let b0 = 1.0 - b1 - b2;
+
+        // The vertex normals are interpolated for smooth shading
+        let [n0, n1, n2] = face.normals;
+        let shading_normal = b0 * data.normals[n0]
+            + b1 * data.normals[n1]
+            + b2 * data.normals[n2];
+        let shading_normal = shading_normal.normalized_exact();
+
+        // The winding order of a face doesn't always agree with its vertex
+        // normals, which are taken to be the outside
+        let (e1, e2) = (p1 - p0, p2 - p0);
+        let mut normal = Vector::cross(e1, e2);
+        normal = normal.normalized_exact();
+        if Vector::dot(normal, shading_normal) < 0.0 {
+            normal = -normal;
+        }
+
+        let (uv, partials) = match face.uvs {
+            Some([i0, i1, i2]) => {
+                let (uv0, uv1, uv2) =
+                    (data.uvs[i0], data.uvs[i1], data.uvs[i2]);
+                let uv = (
+                    b0 * uv0.0 + b1 * uv1.0 + b2 * uv2.0,
+                    b0 * uv0.1 + b1 * uv1.1 + b2 * uv2.1,
+                );
+
+                (uv, uv_partials(e1, e2, uv0, uv1, uv2))
+            }
+            None => ((b1, b2), None),
+        };
+
+        let mut hit_record =
+            HitRecord::new(r, t, uv, normal, partials.unwrap_or((e1, e2)));
+        hit_record.shading_normal = shading_normal;
+
+        Some((hit_record, self.mesh.material(self.face)))
+    }
+
+    fn bounding_box(&self, _t0: f32, _t1: f32) -> Option<Aabb> {
+        Some(triangle_box(self.vertices()))
+    }
+}
+
+impl MeshTriangle {
+    fn vertices(&self) -> [Vector; 3] {
+        let data = &self.mesh.data;
+        let [i0, i1, i2] = data.faces[self.face].positions;
+
+        [data.positions[i0], data.positions[i1], data.positions[i2]]
+    }
+}
+
+/// Finds the derivatives of the position with respect to the texture
+/// coordinates across a face, given its edges from the first vertex.
+/// Returns None if the texture coordinates are degenerate.
+fn uv_partials(
+    e1: Vector,
+    e2: Vector,
+    uv0: (f32, f32),
+    uv1: (f32, f32),
+    uv2: (f32, f32),
+) -> Option<(Vector, Vector)> {
+    let (du1, dv1) = (uv1.0 - uv0.0, uv1.1 - uv0.1);
+    let (du2, dv2) = (uv2.0 - uv0.0, uv2.1 - uv0.1);
+    let determinant = du1 * dv2 - dv1 * du2;
+
+    if determinant.abs() < 1e-12 {
+        return None;
+    }
+
+    let inv = 1.0 / determinant;
+    Some(((dv2 * e1 - dv1 * e2) * inv, (du1 * e2 - du2 * e1) * inv))
+}
+
+impl Mesh {
+    pub fn new(
+        data: Arc<MeshData>,
+        material: Option<Box<dyn Material>>,
+    ) -> Self {
+        let gray = ConstantTexture::new(Vector::new(0.8, 0.8, 0.8));
+
+        Self {
+            data,
+            material,
+            default_material: Box::new(Lambertian::new(Box::new(gray))),
+        }
+    }
+
+    /// Splits the mesh into a hittable for each of its faces, optionally
+    /// keeping only the faces in the given groups
+    pub fn triangles(
+        self: Arc<Self>,
+        groups: Option<&[String]>,
+    ) -> Vec<Box<dyn Hittable>> {
+        let keep = |group: Option<usize>| match (groups, group) {
+            (None, _) => true,
+            (Some(groups), Some(g)) => groups.contains(&self.data.groups[g]),
+            (Some(_), None) => false,
+        };
+
+        (0..self.data.faces.len())
+            .filter(|&face| keep(self.data.faces[face].group))
+            .map(|face| {
+                Box::new(MeshTriangle {
+                    mesh: self.clone(),
+                    face,
+                }) as Box<dyn Hittable>
+            })
+            .collect()
+    }
+
+    fn material(&self, face: usize) -> &Box<dyn Material> {
+        match (&self.material, self.data.faces[face].material) {
+            (Some(material), _) => material,
+            (None, Some(i)) => &self.data.materials[i],
+            (None, None) => &self.default_material,
+        }
+    }
+}
@@ -5,20 +5,54 @@ use crate::{Ray, Vector};
 use std::fmt::Debug;
 
 mod block;
+mod cone;
+mod constant_medium;
+mod csg;
+mod cylinder;
+mod disk;
+mod grid_volume;
+mod heightfield;
+mod instance;
+mod lights;
+mod mesh;
 mod moving_sphere;
+mod paraboloid;
+mod plane;
+mod quad;
 mod rectangle;
 mod rotate;
 mod scene;
+mod sdf;
 mod sphere;
+mod torus;
+mod transform;
 mod translate;
+mod triangle;
 
 pub use block::*;
+pub use cone::*;
+pub use constant_medium::*;
+pub use csg::*;
+pub use cylinder::*;
+pub use disk::*;
+pub use grid_volume::*;
+pub use heightfield::*;
+pub use instance::*;
+pub use lights::*;
+pub use mesh::*;
 pub use moving_sphere::*;
+pub use paraboloid::*;
+pub use plane::*;
+pub use quad::*;
 pub use rectangle::*;
 pub use rotate::*;
 pub use scene::*;
+pub use sdf::*;
 pub use sphere::*;
+pub use torus::*;
+pub use transform::*;
 pub use translate::*;
+pub use triangle::*;
 
 /// A HitRecord describes an interaction between an incoming [`Ray`]
 /// and an object.
@@ -30,7 +64,131 @@ pub struct HitRecord {
     pub v: f32,
     pub t: f32,
     pub p: Vector,
-    pub normal: Vector,
+    /// The unit normal of the actual surface, pointing outwards
+    pub geometric_normal: Vector,
+    /// The unit normal used for shading, which differs from the geometric
+    /// normal when normals are interpolated across a face. This is on the
+    /// same side of the surface as the geometric normal.
+    pub shading_normal: Vector,
+    /// Whether the ray hit the outside of the surface
+    pub front_face: bool,
+    /// How the hit point changes with u and v, which are tangent to the
+    /// surface
+    pub dpdu: Vector,
+    pub dpdv: Vector,
+    /// The temperature in kelvin where a ray scattered inside a volume
+    /// with a temperature grid, which decides how brightly it glows
+    pub temperature: Option<f32>,
+}
+
+impl HitRecord {
+    /// Creates a record for a hit at `t` along the ray on a surface with no
+    /// separate shading normal
+    pub fn new(
+        r: Ray,
+        t: f32,
+        (u, v): (f32, f32),
+        normal: Vector,
+        (dpdu, dpdv): (Vector, Vector),
+    ) -> Self {
+        Self {
+            u,
+            v,
+            t,
+            p: r.point_at_parameter(t),
+            geometric_normal: normal,
+            shading_normal: normal,
+            front_face: Vector::dot(r.dir(), normal) < 0.0,
+            dpdu,
+            dpdv,
+            temperature: None,
+        }
+    }
+
+    /// Creates a record of a ray scattering inside a medium. Scattering
+    /// doesn't happen on a surface, so the normal is arbitrary.
+    pub fn in_medium(r: Ray, t: f32) -> Self {
+        Self::new(
+            r,
+            t,
+            (0.0, 0.0),
+            Vector::new(1.0, 0.0, 0.0),
+            (Vector::zeros(), Vector::zeros()),
+        )
+    }
+
+    /// The shading normal on the side of the surface the ray came from
+    pub fn facing_normal(&self) -> Vector {
+        if self.front_face {
+            self.shading_normal
+        } else {
+            -self.shading_normal
+        }
+    }
+}
+
+/// The distance skipped past each crossing of a surface when looking for
+/// the next one, so that the same crossing isn't found again
+const CROSSING_STEP: f32 = 0.0001;
+
+/// The crossings of a ray with the surface of a hittable, nearest first.
+/// Each one is found when it's asked for by hitting the object again just
+/// past the one before, so callers only pay for the crossings they use.
+pub struct Crossings<'a> {
+    hittable: &'a dyn Hittable,
+    r: Ray,
+    /// The distance skipped past each crossing, in units of the ray's
+    /// parameter
+    step: f32,
+    t_min: f32,
+    t_max: f32,
+}
+
+impl<'a> Iterator for Crossings<'a> {
+    type Item = (HitRecord, &'a Box<dyn Material>);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let hit = self.hittable.hit(self.r, self.t_min, self.t_max)?;
+        self.t_min = hit.0.t + self.step;
+
+        Some(hit)
+    }
+}
+
+impl<'a> dyn Hittable + 'a {
+    /// Returns every crossing of the ray with the object's surface between
+    /// `t_min` and `t_max`, for objects such as CSG operands and the
+    /// boundaries of media where more than the closest hit matters
+    pub fn crossings(&self, r: Ray, t_min: f32, t_max: f32) -> Crossings<'_> {
+        Crossings {
+            hittable: self,
+            r,
+            step: CROSSING_STEP / r.dir().length(),
+            t_min,
+            t_max,
+        }
+    }
+}
+
+/// The value of the PDF for sampling the direction `v` from `o`, when the
+/// hittable is sampled by picking points uniformly over its surface. Every
+/// point along `v` that lies on the surface could have been picked.
+fn surface_pdf(
+    hittable: &dyn Hittable,
+    area: f32,
+    o: Vector,
+    v: Vector,
+) -> f32 {
+    hittable
+        .crossings(Ray::new(o, v, 0.0), 0.001, f32::MAX)
+        .map(|(hit_record, _)| {
+            let dist_squared = hit_record.t * hit_record.t * v.length_squared();
+            let cosine = f32::abs(Vector::dot(v, hit_record.geometric_normal))
+                / v.length();
+
+            dist_squared / (cosine * area)
+        })
+        .sum()
 }
 
 /// The Hittable trait describes any object in the scene that a light
@@ -65,4 +223,11 @@ pub trait Hittable: Debug + Send + Sync {
     fn random(&self, o: Vector) -> Vector {
         Vector::new(1.0, 0.0, 0.0)
     }
+
+    /// Returns a point on the hittable in a direction picked by `random`.
+    /// Hittables that sample points on their surface return the direction
+    /// to that point, so only those that sample directions need their own.
+    fn random_point(&self, o: Vector) -> Vector {
+        o + self.random(o)
+    }
 }
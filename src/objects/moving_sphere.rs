@@ -1,7 +1,12 @@
 //! A simple moving Sphere object
 
 use super::{HitRecord, Hittable};
-use crate::{aabb::Aabb, materials::Material, util::sphere_uv, Ray, Vector};
+use crate::{
+    aabb::Aabb,
+    materials::Material,
+    util::{sphere_partials, sphere_uv},
+    Ray, Vector,
+};
 
 #[derive(Debug)]
 pub struct MovingSphere {
@@ -36,20 +41,17 @@ impl Hittable for MovingSphere {
             }
 
             if q_eq < t_max && q_eq > t_min {
-                let point_at_parameter = r.point_at_parameter(q_eq);
-                let (u, v) = sphere_uv(
-                    (point_at_parameter - self.center(r.time())) / self.radius,
-                );
+                let offset = r.point_at_parameter(q_eq) - self.center(r.time());
+                let normal = offset / self.radius;
 
                 return Some((
-                    HitRecord {
-                        u,
-                        v,
-                        t: q_eq,
-                        p: point_at_parameter,
-                        normal: (point_at_parameter - self.center(r.time()))
-                            / self.radius,
-                    },
+                    HitRecord::new(
+                        r,
+                        q_eq,
+                        sphere_uv(normal),
+                        normal,
+                        sphere_partials(offset),
+                    ),
                     &self.material,
                 ));
             }
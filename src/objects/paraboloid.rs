@@ -0,0 +1,155 @@
+//! A paraboloid opening upwards from its lowest point, optionally closed
+//! off by a disk at the top
+
+use super::{disk::DiskSurface, surface_pdf, HitRecord, Hittable};
+use crate::{
+    aabb::Aabb,
+    materials::Material,
+    util::{azimuth, solve_quadratic},
+    Ray, Vector,
+};
+
+use rand::prelude::*;
+use std::f32::consts::PI;
+
+#[derive(Debug)]
+pub struct Paraboloid {
+    base: Vector,
+    radius: f32,
+    height: f32,
+    cap: Option<DiskSurface>,
+    material: Box<dyn Material>,
+}
+
+impl Hittable for Paraboloid {
+    fn hit(
+        &self,
+        r: Ray,
+        t_min: f32,
+        t_max: f32,
+    ) -> Option<(HitRecord, &Box<dyn Material>)> {
+        let side = self.hit_side(r, t_min, t_max);
+        let t_max = side.map_or(t_max, |hit_record| hit_record.t);
+        let cap = self.cap.and_then(|cap| cap.hit(r, t_min, t_max));
+
+        cap.or(side).map(|hit_record| (hit_record, &self.material))
+    }
+
+    fn bounding_box(&self, _t0: f32, _t1: f32) -> Option<Aabb> {
+        Some(Aabb::new(
+            self.base - Vector::new(self.radius, 0.0, self.radius),
+            self.base + Vector::new(self.radius, self.height, self.radius),
+        ))
+    }
+
+    fn pdf_value(&self, o: Vector, v: Vector) -> f32 {
+        surface_pdf(self, self.area(), o, v)
+    }
+
+    fn random(&self, o: Vector) -> Vector {
+        let side_area = self.side_area();
+        let point = match &self.cap {
+            Some(cap) if random::<f32>() * self.area() > side_area => {
+                cap.random_point()
+            }
+            _ => {
+                // Inverts the area of the paraboloid within a given distance
+                // of its axis
+                let k = 2.0 * self.height / (self.radius * self.radius);
+                let max = (1.0 + k * k * self.radius * self.radius).powf(1.5);
+                let rho = f32::sqrt(
+                    (1.0 + random::<f32>() * (max - 1.0)).powf(2.0 / 3.0) - 1.0,
+                ) / k;
+                let phi = 2.0 * PI * random::<f32>();
+
+                self.base
+                    + Vector::new(
+                        rho * phi.cos(),
+                        self.height * rho * rho / (self.radius * self.radius),
+                        rho * phi.sin(),
+                    )
+            }
+        };
+
+        point - o
+    }
+}
+
+impl Paraboloid {
+    pub fn new(
+        base: Vector,
+        radius: f32,
+        height: f32,
+        capped: bool,
+        material: Box<dyn Material>,
+    ) -> Self {
+        let cap = if capped {
+            let top = base + Vector::new(0.0, height, 0.0);
+            Some(DiskSurface::new(top, radius, 0.0, 1.0))
+        } else {
+            None
+        };
+
+        Self {
+            base,
+            radius,
+            height,
+            cap,
+            material,
+        }
+    }
+
+    /// u goes around the paraboloid and v goes up from the base to the rim
+    fn hit_side(&self, r: Ray, t_min: f32, t_max: f32) -> Option<HitRecord> {
+        let o = r.origin() - self.base;
+        let d = r.dir();
+        let k = self.radius * self.radius / self.height;
+
+        let (t0, t1) = solve_quadratic(
+            d.x * d.x + d.z * d.z,
+            2.0 * (o.x * d.x + o.z * d.z) - k * d.y,
+            o.x * o.x + o.z * o.z - k * o.y,
+        )?;
+
+        for &t in &[t0, t1] {
+            if t <= t_min || t >= t_max {
+                continue;
+            }
+
+            let p = o + t * d;
+            if p.y < 0.0 || p.y > self.height {
+                continue;
+            }
+
+            let normal = Vector::new(2.0 * p.x, -k, 2.0 * p.z);
+            let normal = normal.normalized_exact();
+
+            let phi = azimuth(p.x, p.z);
+            let u = phi / (2.0 * PI);
+            let v = p.y / self.height;
+
+            // The radius grows with the square root of v, so dpdv blows up
+            // at the base
+            let scale = self.radius / (2.0 * f32::max(v.sqrt(), 1e-4));
+            let dpdu = 2.0 * PI * Vector::new(-p.z, 0.0, p.x);
+            let dpdv =
+                Vector::new(scale * phi.cos(), self.height, scale * phi.sin());
+
+            return Some(HitRecord::new(r, t, (u, v), normal, (dpdu, dpdv)));
+        }
+
+        None
+    }
+
+    fn side_area(&self) -> f32 {
+        let (r, h) = (self.radius, self.height);
+        PI * r / (6.0 * h * h) * ((r * r + 4.0 * h * h).powf(1.5) - r * r * r)
+    }
+
+    fn area(&self) -> f32 {
+        match self.cap {
+            Some(_) => self.side_area() + PI * self.radius * self.radius,
+            None => self.side_area(),
+        }
+    }
+}
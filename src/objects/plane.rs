@@ -0,0 +1,79 @@
+//! An infinite plane, which is mostly useful as a ground that stretches to
+//! the horizon
+
+use super::{HitRecord, Hittable};
+use crate::{aabb::Aabb, materials::Material, Ray, Vector};
+
+#[derive(Debug)]
+pub struct Plane {
+    point: Vector,
+    normal: Vector,
+    /// Unit vectors in the plane which the u and v coordinates follow
+    tangents: (Vector, Vector),
+    material: Box<dyn Material>,
+}
+
+impl Hittable for Plane {
+    fn hit(
+        &self,
+        r: Ray,
+        t_min: f32,
+        t_max: f32,
+    ) -> Option<(HitRecord, &Box<dyn Material>)> {
+        let t = Vector::dot(self.point - r.origin(), self.normal)
+            / Vector::dot(r.dir(), self.normal);
+
+        // This also rejects rays parallel to the plane, where t isn't finite
+        if !(t > t_min && t < t_max) {
+            return None;
+        }
+
+        // The coordinates are distances from the plane's point, so a texture
+        // that repeats every unit tiles the plane with unit squares
+        let offset = r.point_at_parameter(t) - self.point;
+        let (tangent, bitangent) = self.tangents;
+        let u = Vector::dot(offset, tangent);
+        let v = Vector::dot(offset, bitangent);
+
+        Some((
+            HitRecord::new(r, t, (u, v), self.normal, self.tangents),
+            &self.material,
+        ))
+    }
+
+    /// A plane is unbounded, so it has no bounding box and can't go in a
+    /// BVH
+    fn bounding_box(&self, _t0: f32, _t1: f32) -> Option<Aabb> {
+        None
+    }
+}
+
+impl Plane {
+    /// Creates the plane through `point` facing towards `normal`. On a plane
+    /// facing up u follows the X axis and v follows the Z axis.
+    pub fn new(
+        point: Vector,
+        normal: Vector,
+        material: Box<dyn Material>,
+    ) -> Self {
+        let normal = normal.normalized_exact();
+
+        // Whichever of the X and Z axes is further from the normal is
+        // projected onto the plane
+        let axis = if normal.x.abs() > 0.9 {
+            Vector::new(0.0, 0.0, 1.0)
+        } else {
+            Vector::new(1.0, 0.0, 0.0)
+        };
+        let tangent = axis - Vector::dot(axis, normal) * normal;
+        let tangent = tangent.normalized_exact();
+        let bitangent = Vector::cross(tangent, normal);
+
+        Self {
+            point,
+            normal,
+            tangents: (tangent, bitangent),
+            material,
+        }
+    }
+}
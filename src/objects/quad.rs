@@ -0,0 +1,122 @@
+//! A parallelogram in any orientation, defined by one corner and the two
+//! edges leaving it
+
+use super::{HitRecord, Hittable};
+use crate::{aabb::Aabb, materials::Material, Ray, Vector};
+
+use rand::prelude::*;
+use std::f32;
+
+/// Padding for bounding boxes of quads lying in an axis aligned plane
+const BOX_PADDING: f32 = 0.0001;
+
+#[derive(Debug)]
+pub struct Quad {
+    corner: Vector,
+    u: Vector,
+    v: Vector,
+    /// The geometric normal, which is the cross product of `u` and `v`
+    normal: Vector,
+    /// The cross product of the edges scaled by its inverse squared
+    /// length, for finding the hit point's position along the edges
+    w: Vector,
+    area: f32,
+    material: Box<dyn Material>,
+}
+
+impl Hittable for Quad {
+    fn hit(
+        &self,
+        r: Ray,
+        t_min: f32,
+        t_max: f32,
+    ) -> Option<(HitRecord, &Box<dyn Material>)> {
+        let t = Vector::dot(self.corner - r.origin(), self.normal)
+            / Vector::dot(r.dir(), self.normal);
+
+        // This also rejects rays parallel to the quad, where t isn't finite
+        if !(t > t_min && t < t_max) {
+            return None;
+        }
+
+        let offset = r.point_at_parameter(t) - self.corner;
+        let alpha = Vector::dot(self.w, Vector::cross(offset, self.v));
+        let beta = Vector::dot(self.w, Vector::cross(self.u, offset));
+
+        if alpha < 0.0 || alpha > 1.0 || beta < 0.0 || beta > 1.0 {
+            return None;
+        }
+
+        Some((
+            HitRecord::new(r, t, (alpha, beta), self.normal, (self.u, self.v)),
+            &self.material,
+        ))
+    }
+
+    fn bounding_box(&self, _t0: f32, _t1: f32) -> Option<Aabb> {
+        let corners = [
+            self.corner + self.u,
+            self.corner + self.v,
+            self.corner + self.u + self.v,
+        ];
+        let mut min = self.corner;
+        let mut max = self.corner;
+
+        for p in &corners {
+            for a in 0..3 {
+                min[a] = f32::min(min[a], p[a]);
+                max[a] = f32::max(max[a], p[a]);
+            }
+        }
+
+        Some(Aabb::new(min + -BOX_PADDING, max + BOX_PADDING))
+    }
+
+    fn pdf_value(&self, o: Vector, v: Vector) -> f32 {
+        match self.hit(Ray::new(o, v, 0.0), 0.001, f32::MAX) {
+            Some((hit_record, _)) => {
+                let dist_squared =
+                    hit_record.t * hit_record.t * v.length_squared();
+                let cosine =
+                    f32::abs(Vector::dot(v, hit_record.geometric_normal))
+                        / v.length();
+
+                dist_squared / (cosine * self.area)
+            }
+            None => 0.0,
+        }
+    }
+
+    fn random(&self, o: Vector) -> Vector {
+        let random_point =
+            self.corner + random::<f32>() * self.u + random::<f32>() * self.v;
+
+        random_point - o
+    }
+}
+
+impl Quad {
+    /// Creates a quad with corners at `corner`, `corner + u`,
+    /// `corner + u + v` and `corner + v`. Its front side is the one that
+    /// `u` × `v` points out of.
+    pub fn new(
+        corner: Vector,
+        u: Vector,
+        v: Vector,
+        material: Box<dyn Material>,
+    ) -> Self {
+        let cross = Vector::cross(u, v);
+        let length_squared = cross.length_squared();
+        let length = length_squared.sqrt();
+
+        Self {
+            corner,
+            u,
+            v,
+            normal: cross / length,
+            w: cross / length_squared,
+            area: length,
+            material,
+        }
+    }
+}
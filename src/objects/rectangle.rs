@@ -50,14 +50,22 @@ impl<const P: RectPlane> Hittable for Rectangle<{ P }> {
             RectPlane::XZ => Vector::new(0.0, 1.0, 0.0),
         };
 
+        let mut dpdu = Vector::zeros();
+        let mut dpdv = Vector::zeros();
+        dpdu[a_ax] = self.a1 - self.a0;
+        dpdv[b_ax] = self.b1 - self.b0;
+
         Some((
-            HitRecord {
+            HitRecord::new(
+                r,
                 t,
-                u: (x - self.a0) / (self.a1 - self.a0),
-                v: (y - self.b0) / (self.b1 - self.b0),
-                p: r.point_at_parameter(t),
-                normal: normal * self.norm,
-            },
+                (
+                    (x - self.a0) / (self.a1 - self.a0),
+                    (y - self.b0) / (self.b1 - self.b0),
+                ),
+                normal * self.norm,
+                (dpdu, dpdv),
+            ),
             &self.material,
         ))
     }
@@ -85,8 +93,8 @@ impl<const P: RectPlane> Hittable for Rectangle<{ P }> {
         {
             let area = (self.a1 - self.a0) * (self.b1 - self.b0);
             let dist_squared = hit_record.t * hit_record.t * v.length_squared();
-            let cosine =
-                f32::abs(Vector::dot(v, hit_record.normal)) / v.length();
+            let cosine = f32::abs(Vector::dot(v, hit_record.geometric_normal))
+                / v.length();
 
             return dist_squared / (cosine * area);
         } else {
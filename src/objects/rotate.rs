@@ -2,6 +2,11 @@ use super::{HitRecord, Hittable};
 use crate::{aabb::Aabb, materials::Material, Ray, Vector};
 use std::f32;
 
+/// The number of steps a moving rotation is split into when computing
+/// the bounding box of the whole motion
+const MOTION_BOX_STEPS: usize = 16;
+
+#[derive(Debug, Copy, Clone)]
 pub enum RotationAxis {
     X,
     Y,
@@ -13,9 +18,30 @@ pub struct Rotate<const A: RotationAxis> {
     hittable: Box<dyn Hittable>,
     sin_theta: f32,
     cos_theta: f32,
+    motion: Option<RotationMotion>,
     bounding_box: Option<Aabb>,
 }
 
+/// The start and end angles, in radians, of a Rotate object that rotates
+/// during the time interval [t0, t1]
+#[derive(Debug)]
+struct RotationMotion {
+    start: f32,
+    end: f32,
+    t0: f32,
+    t1: f32,
+}
+
+/// Returns the indices of the rotation axis and the two axes that make
+/// up the plane of rotation
+fn axes(axis: &RotationAxis) -> (usize, usize, usize) {
+    match axis {
+        RotationAxis::X => (0, 1, 2),
+        RotationAxis::Y => (1, 2, 0),
+        RotationAxis::Z => (2, 0, 1),
+    }
+}
+
 impl<const A: RotationAxis> Hittable for Rotate<{ A }> {
     fn hit(
         &self,
@@ -23,44 +49,39 @@ impl<const A: RotationAxis> Hittable for Rotate<{ A }> {
         t_min: f32,
         t_max: f32,
     ) -> Option<(HitRecord, &Box<dyn Material>)> {
-        let (_, a_axis, b_axis) = match A {
-            RotationAxis::X => (0, 1, 2),
-            RotationAxis::Y => (1, 2, 0),
-            RotationAxis::Z => (2, 0, 1),
-        };
+        let (_, a_axis, b_axis) = axes(&A);
+        let (sin_theta, cos_theta) = self.sin_cos(r.time());
 
         let mut origin = r.origin();
         let mut dir = r.dir();
 
-        origin[b_axis] = self.cos_theta * r.origin()[b_axis]
-            - self.sin_theta * r.origin()[a_axis];
-        origin[a_axis] = self.sin_theta * r.origin()[b_axis]
-            + self.cos_theta * r.origin()[a_axis];
+        origin[b_axis] =
+            cos_theta * r.origin()[b_axis] - sin_theta * r.origin()[a_axis];
+        origin[a_axis] =
+            sin_theta * r.origin()[b_axis] + cos_theta * r.origin()[a_axis];
 
-        dir[b_axis] =
-            self.cos_theta * r.dir()[b_axis] - self.sin_theta * r.dir()[a_axis];
-        dir[a_axis] =
-            self.sin_theta * r.dir()[b_axis] + self.cos_theta * r.dir()[a_axis];
+        dir[b_axis] = cos_theta * r.dir()[b_axis] - sin_theta * r.dir()[a_axis];
+        dir[a_axis] = sin_theta * r.dir()[b_axis] + cos_theta * r.dir()[a_axis];
 
         let rotated_r = Ray::new(origin, dir, r.time());
 
         self.hittable.hit(rotated_r, t_min, t_max).map(
             |(mut hit_record, material)| {
-                let mut p = hit_record.p;
-                let mut normal = hit_record.normal;
-
-                p[b_axis] = self.cos_theta * hit_record.p[b_axis]
-                    + self.sin_theta * hit_record.p[a_axis];
-                p[a_axis] = -self.sin_theta * hit_record.p[b_axis]
-                    + self.cos_theta * hit_record.p[a_axis];
+                let rotate = |v: Vector| {
+                    let mut rotated = v;
+                    rotated[b_axis] =
+                        cos_theta * v[b_axis] + sin_theta * v[a_axis];
+                    rotated[a_axis] =
+                        -sin_theta * v[b_axis] + cos_theta * v[a_axis];
+                    rotated
+                };
 
-                normal[b_axis] = self.cos_theta * hit_record.normal[b_axis]
-                    + self.sin_theta * hit_record.normal[a_axis];
-                normal[a_axis] = -self.sin_theta * hit_record.normal[b_axis]
-                    + self.cos_theta * hit_record.normal[a_axis];
-
-                hit_record.p = p;
-                hit_record.normal = normal;
+                hit_record.p = rotate(hit_record.p);
+                hit_record.geometric_normal =
+                    rotate(hit_record.geometric_normal);
+                hit_record.shading_normal = rotate(hit_record.shading_normal);
+                hit_record.dpdu = rotate(hit_record.dpdu);
+                hit_record.dpdv = rotate(hit_record.dpdv);
                 (hit_record, material)
             },
         )
@@ -77,60 +98,159 @@ impl<const A: RotationAxis> Rotate<{ A }> {
         let sin_theta = f32::sin(rads);
         let cos_theta = f32::cos(rads);
 
-        let (r_axis, a_axis, b_axis) = match A {
-            RotationAxis::X => (0, 1, 2),
-            RotationAxis::Y => (1, 2, 0),
-            RotationAxis::Z => (2, 0, 1),
-        };
-
-        let bounding_box = hittable.bounding_box(0.0, 1.0).map(|mut bbox| {
-            let mut min = Vector::new(f32::MAX, f32::MAX, f32::MAX);
-            let mut max = Vector::new(-f32::MAX, -f32::MAX, -f32::MAX);
-            for i in 0..2 {
-                for j in 0..2 {
-                    for k in 0..2 {
-                        let b = i as f32 * bbox.max[b_axis]
-                            + (1 - i) as f32 * bbox.min[b_axis];
-                        let r = j as f32 * bbox.max[r_axis]
-                            + (1 - j) as f32 * bbox.min[r_axis];
-                        let a = k as f32 * bbox.max[a_axis]
-                            + (1 - k) as f32 * bbox.min[a_axis];
-
-                        let new_b = cos_theta * b + sin_theta * a;
-                        let new_a = -sin_theta * b + cos_theta * a;
-
-                        if new_a < min[a_axis] {
-                            min[a_axis] = new_a
-                        }
-                        if new_b < min[b_axis] {
-                            min[b_axis] = new_b
-                        }
-                        if r < min[r_axis] {
-                            min[r_axis] = r
-                        }
-
-                        if new_a > max[a_axis] {
-                            max[a_axis] = new_a
-                        }
-                        if new_b > max[b_axis] {
-                            max[b_axis] = new_b
-                        }
-                        if r > max[r_axis] {
-                            max[r_axis] = r
-                        }
-                    }
-                }
-            }
-            bbox.min = min;
-            bbox.max = max;
-            bbox
-        });
+        let bounding_box = hittable
+            .bounding_box(0.0, 1.0)
+            .map(|bbox| rotated_box::<{ A }>(bbox, sin_theta, cos_theta));
 
         Self {
             hittable,
             sin_theta,
             cos_theta,
+            motion: None,
             bounding_box,
         }
     }
+
+    /// Creates a Rotate object which turns from `angle0` to `angle1` at a
+    /// steady rate over the time interval [t0, t1]. The angles aren't
+    /// wrapped, so going from 0 to 360 degrees is a full turn.
+    pub fn new_moving(
+        hittable: Box<dyn Hittable>,
+        angle0: f32,
+        angle1: f32,
+        t0: f32,
+        t1: f32,
+    ) -> Self {
+        let motion = RotationMotion {
+            start: angle0.to_radians(),
+            end: angle1.to_radians(),
+            t0,
+            t1,
+        };
+
+        let mut ret = Self {
+            hittable,
+            sin_theta: 0.0,
+            cos_theta: 1.0,
+            motion: Some(motion),
+            bounding_box: None,
+        };
+
+        ret.bounding_box = ret.motion_box(t0, t1);
+        ret
+    }
+
+    /// Returns the sine and cosine of the rotation angle at the given time
+    fn sin_cos(&self, time: f32) -> (f32, f32) {
+        match &self.motion {
+            None => (self.sin_theta, self.cos_theta),
+            Some(m) => {
+                let s = if m.t1 > m.t0 {
+                    ((time - m.t0) / (m.t1 - m.t0)).max(0.0).min(1.0)
+                } else {
+                    0.0
+                };
+
+                let angle = m.start + (m.end - m.start) * s;
+                angle.sin_cos()
+            }
+        }
+    }
+
+    /// Computes a bounding box which contains the object throughout its
+    /// rotation. The rotation is sampled at several steps and the union
+    /// of those boxes is padded to account for the arcs that the corners
+    /// trace out between the steps.
+    fn motion_box(&self, t0: f32, t1: f32) -> Option<Aabb> {
+        let bbox = self.hittable.bounding_box(t0, t1)?;
+        let (_, a_axis, b_axis) = axes(&A);
+
+        // The angle changes at a steady rate, so it turns through the same
+        // amount between each pair of steps
+        let max_step = self
+            .motion
+            .as_ref()
+            .map_or(0.0, |m| (m.end - m.start).abs() / MOTION_BOX_STEPS as f32);
+
+        let mut ret: Option<Aabb> = None;
+        for i in 0..=MOTION_BOX_STEPS {
+            let time = t0 + (t1 - t0) * i as f32 / MOTION_BOX_STEPS as f32;
+            let (sin_theta, cos_theta) = self.sin_cos(time);
+
+            let rotated = rotated_box::<{ A }>(bbox, sin_theta, cos_theta);
+            ret = Some(match ret {
+                Some(b) => Aabb::surrounding_box(b, rotated),
+                None => rotated,
+            });
+        }
+
+        // The furthest any corner gets from the rotation axis bounds how
+        // far its arc can bulge past the chord between two steps
+        let mut radius: f32 = 0.0;
+        for a in &[bbox.min[a_axis], bbox.max[a_axis]] {
+            for b in &[bbox.min[b_axis], bbox.max[b_axis]] {
+                radius = radius.max(f32::sqrt(a * a + b * b));
+            }
+        }
+
+        let pad = radius * (1.0 - f32::cos(0.5 * max_step));
+
+        ret.map(|mut b| {
+            for axis in &[a_axis, b_axis] {
+                b.min[*axis] -= pad;
+                b.max[*axis] += pad;
+            }
+            b
+        })
+    }
+}
+
+/// Returns the bounding box of the given box after rotating it
+fn rotated_box<const A: RotationAxis>(
+    mut bbox: Aabb,
+    sin_theta: f32,
+    cos_theta: f32,
+) -> Aabb {
+    let (r_axis, a_axis, b_axis) = axes(&A);
+
+    let mut min = Vector::new(f32::MAX, f32::MAX, f32::MAX);
+    let mut max = Vector::new(-f32::MAX, -f32::MAX, -f32::MAX);
+    for i in 0..2 {
+        for j in 0..2 {
+            for k in 0..2 {
+                let b = i as f32 * bbox.max[b_axis]
+                    + (1 - i) as f32 * bbox.min[b_axis];
+                let r = j as f32 * bbox.max[r_axis]
+                    + (1 - j) as f32 * bbox.min[r_axis];
+                let a = k as f32 * bbox.max[a_axis]
+                    + (1 - k) as f32 * bbox.min[a_axis];
+
+                let new_b = cos_theta * b + sin_theta * a;
+                let new_a = -sin_theta * b + cos_theta * a;
+
+                if new_a < min[a_axis] {
+                    min[a_axis] = new_a
+                }
+                if new_b < min[b_axis] {
+                    min[b_axis] = new_b
+                }
+                if r < min[r_axis] {
+                    min[r_axis] = r
+                }
+
+                if new_a > max[a_axis] {
+                    max[a_axis] = new_a
+                }
+                if new_b > max[b_axis] {
+                    max[b_axis] = new_b
+                }
+                if r > max[r_axis] {
+                    max[r_axis] = r
+                }
+            }
+        }
+    }
+    bbox.min = min;
+    bbox.max = max;
+    bbox
 }
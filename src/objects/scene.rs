@@ -1,23 +1,297 @@
 use crate::aabb::Aabb;
+use crate::animation::{Animatable, Animated, Keyframe, Lerp, Shutter, Track};
+use crate::atmosphere::Atmosphere;
 use crate::bvh::Bvh;
-use crate::camera::{Camera, CameraConstructor};
-use crate::materials::{Dielectric, DiffuseLight, Lambertian, Material, Metal};
+use crate::camera::{Camera, CameraConstructor, CameraPath};
+use crate::loaders::{self, HeightMap, MeshData, VoxelGrid};
+use crate::materials::{
+    Dielectric, DiffuseLight, HenyeyGreenstein, Isotropic, Lambertian,
+    Material, Metal,
+};
+use crate::matrix::Matrix4;
+use crate::physics::{self, Trajectories, World};
+use crate::quaternion::Quaternion;
+use crate::spline::{Spline, SplineKind};
 use crate::textures::*;
 use crate::{Ray, Vector};
 
 use super::{
-    Block, HitRecord, Hittable, MovingSphere, RectPlane, Rectangle, Rotate,
-    RotationAxis, Sphere, Translate,
+    Block, Cone, ConstantMedium, Csg, CsgOperation, Cylinder, Disk, GridVolume,
+    Heightfield, HitRecord, Hittable, Instance, Lights, Mesh, MovingSphere,
+    Paraboloid, Plane, Quad, RectPlane, Rectangle, Rotate, RotationAxis, Sdf,
+    SdfNode, Sphere, Torus, Transform, Translate, Triangle,
 };
 
 use serde::{Deserialize, Serialize};
+use serde_json::json;
+use std::collections::HashMap;
 use std::fs;
 use std::path::Path;
+use std::sync::Arc;
+use std::time;
 
 #[derive(Debug)]
 pub struct Scene {
     objects: Vec<Box<dyn Hittable>>,
     pub camera: Camera,
+    /// Copies of the emissive objects, which are sampled directly
+    pub lights: Lights,
+    pub stats: SceneStats,
+    /// The medium filling the space between objects, if there is one
+    pub atmosphere: Option<Atmosphere>,
+    /// The color seen by rays that don't hit anything
+    pub background: Vector,
+}
+
+/// Statistics gathered while constructing a scene from a JSON file
+#[derive(Debug, Default, Copy, Clone)]
+pub struct SceneStats {
+    /// The number of BVHs that were constructed
+    pub bvh_count: usize,
+    /// The total time spent constructing BVHs
+    pub bvh_build_time: time::Duration,
+}
+
+/// The geometry of each named object definition, shared by its instances
+type Definitions = HashMap<String, Arc<dyn Hittable>>;
+
+/// Transforms for named groups which replace the transforms given in the
+/// group itself. These come from animation tracks and overrides.
+type GroupTransforms = HashMap<String, Animated<Vec<SchemaTransform>>>;
+
+/// Loaded mesh files, keyed by the path and format given in the scene file,
+/// since the same file could be read as more than one format
+type Meshes = HashMap<(String, Option<String>), Arc<MeshData>>;
+
+/// Loaded voxel grids, keyed by the path given in the scene file
+type Grids = HashMap<String, Arc<VoxelGrid>>;
+
+/// Loaded height maps, keyed by the path given in the scene file
+type HeightMaps = HashMap<String, Arc<HeightMap>>;
+
+/// The state shared by every object parsed while building a scene
+struct BuildContext<'a> {
+    shutter: Shutter,
+    definitions: Definitions,
+    group_transforms: &'a GroupTransforms,
+    meshes: &'a Meshes,
+    grids: &'a Grids,
+    height_maps: &'a HeightMaps,
+    stats: &'a mut SceneStats,
+}
+
+/// A scene as described by a JSON file. Since properties in the file can
+/// be animated, a single description can be turned into any number of
+/// renderable [`Scene`]s at different points in time.
+///
+/// [`Scene`]: struct.Scene.html
+#[derive(Debug)]
+pub struct SceneDescription {
+    scene: SchemaScene,
+    trajectories: Option<Trajectories>,
+    camera_path: Option<CameraPath>,
+    /// The IDs of every group in the scene
+    groups: Vec<String>,
+    group_transforms: GroupTransforms,
+    meshes: Meshes,
+    grids: Grids,
+    height_maps: HeightMaps,
+}
+
+impl SceneDescription {
+    /// Loads a scene file, which is either one of our JSON scenes or a
+    /// glTF file
+    pub fn load(path: &Path) -> Result<Self, String> {
+        match path.extension().and_then(|e| e.to_str()) {
+            Some("gltf") | Some("glb") => Self::from_gltf(path),
+            _ => Self::from_json(path),
+        }
+    }
+
+    pub fn from_json(path: &Path) -> Result<Self, String> {
+        let scene = fs::read_to_string(path)
+            .map_err(|e| format!("Failed to read JSON file: {}", e))
+            .and_then(|json| {
+                serde_json::from_str::<SchemaScene>(&json)
+                    .map_err(|e| format!("Failed to parse JSON: {}", e))
+            })?;
+
+        let dir = path.parent().unwrap_or_else(|| Path::new("."));
+        Self::from_schema(scene, dir, Meshes::new())
+    }
+
+    /// Imports a glTF file as a scene containing just its meshes, viewed
+    /// from its first camera if it has one. glTF files don't have lights,
+    /// so the scene is only lit by emissive materials.
+    pub fn from_gltf(path: &Path) -> Result<Self, String> {
+        let gltf = loaders::load_gltf(path)?;
+        let file = path.file_name().and_then(|f| f.to_str()).unwrap();
+        let vector = |v: Vector| json!({ "x": v.x, "y": v.y, "z": v.z });
+
+        let mut scene = json!({
+            "objects": [{ "name": "Mesh", "file": file }],
+        });
+        if let Some(camera) = gltf.cameras.first() {
+            scene["camera"] = json!({
+                "look_from": vector(camera.look_from),
+                "look_at": vector(camera.look_at),
+                "vup": vector(camera.vup),
+                "vfov": camera.vfov,
+            });
+        }
+
+        let scene = serde_json::from_value(scene).unwrap();
+        let mut meshes = Meshes::new();
+        meshes.insert((String::from(file), None), Arc::new(gltf.mesh));
+
+        let dir = path.parent().unwrap_or_else(|| Path::new("."));
+        Self::from_schema(scene, dir, meshes)
+    }
+
+    /// Prepares a parsed scene for rendering. Any meshes it uses that
+    /// aren't already loaded are loaded relative to `dir`.
+    fn from_schema(
+        scene: SchemaScene,
+        dir: &Path,
+        mut meshes: Meshes,
+    ) -> Result<Self, String> {
+        check_instances(&scene)?;
+        check_blackbodies(&scene)?;
+        check_sdfs(&scene)?;
+        check_item_lists(&scene)?;
+        check_tracks(&scene)?;
+        check_transforms(&scene)?;
+        let groups = group_ids(&scene)?;
+
+        // Model files are loaded once up front since they never change
+        load_meshes(&scene, dir, &mut meshes)?;
+        let grids = load_grids(&scene, dir)?;
+        let height_maps = load_height_maps(&scene, dir)?;
+
+        // The simulation only needs to be run once, each frame samples
+        // the recorded trajectories
+        let trajectories = match &scene.simulation {
+            Some(simulation) => Some(run_simulation(simulation)?),
+            None => None,
+        };
+
+        let camera_path =
+            match scene.camera.as_ref().and_then(|c| c.path.as_ref()) {
+                Some(path) => Some(parse_camera_path(path)?),
+                None => None,
+            };
+
+        let mut description = Self {
+            scene,
+            trajectories,
+            camera_path,
+            groups,
+            group_transforms: GroupTransforms::new(),
+            meshes,
+            grids,
+            height_maps,
+        };
+
+        for track in description.scene.tracks.take().into_iter().flatten() {
+            let keyframes = track.keyframes;
+            description.set_group_transform(
+                &track.target,
+                Animated::Track(Track { keyframes }),
+            )?;
+        }
+
+        Ok(description)
+    }
+
+    /// Overrides a property of the scene, where the value is given as
+    /// JSON. The only properties that can be set are group transforms,
+    /// e.g. `groups.table.transform`, whose value is a list of
+    /// transformations or a keyframe track of them.
+    pub fn set(&mut self, path: &str, value: &str) -> Result<(), String> {
+        let value = serde_json::from_str(value)
+            .map_err(|e| format!("Invalid value for {}: {}", path, e))?;
+
+        self.set_group_transform(path, value)
+    }
+
+    fn set_group_transform(
+        &mut self,
+        path: &str,
+        transforms: Animated<Vec<SchemaTransform>>,
+    ) -> Result<(), String> {
+        let parts: Vec<&str> = path.split('.').collect();
+        let id = match parts.as_slice() {
+            ["groups", id, "transform"] => *id,
+            _ => return Err(format!("Unknown property {}", path)),
+        };
+
+        if !self.groups.iter().any(|g| g == id) {
+            return Err(format!("Unknown group {} in {}", id, path));
+        }
+
+        let check = |transforms: &[SchemaTransform]| {
+            check_transform_list(transforms)
+                .map_err(|e| format!("{}: {}", path, e))
+        };
+
+        // Interpolating between keyframes needs their steps to line up
+        match &transforms {
+            Animated::Static(transforms) => check(transforms)?,
+            Animated::Track(track) => {
+                track.check().map_err(|e| format!("{}: {}", path, e))?;
+                for keyframe in &track.keyframes {
+                    check(&keyframe.value)?;
+                }
+            }
+        }
+
+        if let Animated::Track(track) = &transforms {
+            let layout = |k: &Keyframe<Vec<SchemaTransform>>| {
+                k.value
+                    .value()
+                    .iter()
+                    .map(std::mem::discriminant)
+                    .collect::<Vec<_>>()
+            };
+
+            let first = track.keyframes.first().map(layout);
+            if track.keyframes.iter().any(|k| Some(layout(k)) != first) {
+                return Err(format!(
+                    "Keyframes for {} must have the same transformation \
+                     steps",
+                    path
+                ));
+            }
+        }
+
+        self.group_transforms.insert(String::from(id), transforms);
+        Ok(())
+    }
+
+    /// Builds the scene for the given shutter interval. Animated properties
+    /// are evaluated when the shutter opens, except for those that support
+    /// motion blur which also use the value when the shutter closes.
+    ///
+    /// If no shutter is given the camera's `t0` and `t1` are used. Fails if
+    /// a transformation can't be inverted at that time.
+    pub fn build(
+        &self,
+        aspect_r: f32,
+        shutter: Option<Shutter>,
+        view: Option<TurntableView>,
+    ) -> Result<Scene, String> {
+        schema_scene_to_scene(self, aspect_r, shutter, view)
+    }
+}
+
+/// A single view of a turntable render, which shows the scene from an
+/// angle (in degrees) around the camera's `look_at` point
+#[derive(Debug, Copy, Clone)]
+pub enum TurntableView {
+    /// Orbit the camera around `look_at`, about the camera's up vector
+    Camera { angle: f32 },
+    /// Rotate the whole scene about the given axis through `look_at`
+    Scene { axis: RotationAxis, angle: f32 },
 }
 
 impl Scene {
@@ -28,6 +302,10 @@ impl Scene {
         Scene {
             objects,
             camera: Camera::default(aspect_r),
+            lights: Lights::default(),
+            stats: SceneStats::default(),
+            atmosphere: None,
+            background: Vector::zeros(),
         }
     }
 
@@ -35,74 +313,555 @@ impl Scene {
         objects: Vec<Box<dyn Hittable>>,
         camera: Camera,
     ) -> Self {
-        Self { objects, camera }
+        Self {
+            objects,
+            camera,
+            lights: Lights::default(),
+            stats: SceneStats::default(),
+            atmosphere: None,
+            background: Vector::zeros(),
+        }
     }
+}
 
-    pub fn from_json(path: &Path, aspect_r: f32) -> Result<Self, String> {
-        fs::read_to_string(path)
-            .map_err(|e| format!("Failed to read JSON file: {}", e))
-            .and_then(|json| {
-                serde_json::from_str::<SchemaScene>(&json)
-                    .map_err(|e| format!("Failed to parse JSON: {}", e))
-                    .and_then(|scene| {
-                        Ok(schema_scene_to_scene(scene, aspect_r))
-                    })
-            })
+impl Animatable for SchemaVector {
+    type Value = Vector;
+    fn value(&self) -> Vector {
+        Vector::new(self.x, self.y, self.z)
     }
 }
 
+/// The number of segments a moving camera's path is split into during
+/// the shutter interval
+const CAMERA_MOTION_SEGMENTS: usize = 8;
+
+const DEFAULT_SDF_EPSILON: f32 = 0.001;
+const DEFAULT_SDF_MAX_STEPS: usize = 256;
+
+const DEFAULT_RESTITUTION: f32 = 0.8;
+const DEFAULT_FRICTION: f32 = 0.3;
+
+/// Evaluates a required animated property at the given time
+fn at<T: Animatable>(property: &Option<Animated<T>>, time: f32) -> T::Value {
+    property.as_ref().unwrap().at(time)
+}
+
 /// Converts a scene grabbed from a JSON file into a real scene
 /// usable by the main renderer. The reason this function is necessary
 /// is because the JSON schema for the scene files isn't directly
 /// translatable to Rust types.
-fn schema_scene_to_scene(scene: SchemaScene, aspect_r: f32) -> Scene {
-    match scene.camera {
+fn schema_scene_to_scene(
+    description: &SceneDescription,
+    aspect_r: f32,
+    shutter: Option<Shutter>,
+    view: Option<TurntableView>,
+) -> Result<Scene, String> {
+    let scene = &description.scene;
+    let mut stats = SceneStats::default();
+
+    // Scenes turned for a turntable view are turned by this matrix, along
+    // with their lights
+    let mut turn = None;
+
+    let (mut ret, shutter) = match &scene.camera {
         Some(c) => {
-            let look_from =
-                Vector::new(c.look_from.x, c.look_from.y, c.look_from.z);
-            let look_at = Vector::new(c.look_at.x, c.look_at.y, c.look_at.z);
-
-            let focus_dist = c
-                .focus_dist
-                .unwrap_or_else(|| (look_from - look_at).length());
-
-            let aperture = c.aperture.unwrap_or(0.0001);
-
-            let t0 = c.t0.unwrap_or(0.0);
-            let t1 = c.t1.unwrap_or(0.0);
-
-            let objects = parse_objects(scene.objects, t0, t1);
-
-            let camera_settings = CameraConstructor {
-                look_from,
-                look_at,
-                vup: Vector::new(c.vup.x, c.vup.y, c.vup.z),
-                vfov: c.vfov,
-                aspect_r,
-                aperture,
-                focus_dist,
-                t0,
-                t1,
+            let shutter = shutter.unwrap_or(Shutter {
+                open: c.t0.unwrap_or(0.0),
+                close: c.t1.unwrap_or(0.0),
+            });
+
+            let camera_path = description.camera_path.as_ref();
+            let settings = |time| {
+                camera_settings(c, camera_path, aspect_r, time, shutter, view)
+            };
+
+            let mut objects =
+                parse_scene_objects(description, shutter, &mut stats)?;
+
+            if let Some(TurntableView::Scene { axis, angle }) = view {
+                let pivot = settings(shutter.open).look_at;
+                let matrix = turntable_matrix(pivot, axis, angle);
+                objects = vec![turn_scene(objects, matrix)];
+                turn = Some(matrix);
+            }
+
+            // A moving camera is blurred by sampling its position at
+            // several points across the shutter interval
+            let camera = if shutter.is_open() && camera_is_animated(c) {
+                let settings: Vec<CameraConstructor> = (0
+                    ..=CAMERA_MOTION_SEGMENTS)
+                    .map(|i| {
+                        let frac = i as f32 / CAMERA_MOTION_SEGMENTS as f32;
+                        settings(Lerp::lerp(shutter.open, shutter.close, frac))
+                    })
+                    .collect();
+
+                Camera::new_moving(&settings)
+            } else {
+                Camera::new(settings(shutter.open))
             };
 
-            let camera = Camera::new(camera_settings);
+            (Scene::from_objects_and_cam(objects, camera), shutter)
+        }
+        None => {
+            let shutter = shutter.unwrap_or(Shutter {
+                open: 0.0,
+                close: 0.0,
+            });
+
+            let mut objects =
+                parse_scene_objects(description, shutter, &mut stats)?;
+
+            if let Some(TurntableView::Scene { axis, angle }) = view {
+                let matrix = turntable_matrix(Vector::zeros(), axis, angle);
+                objects = vec![turn_scene(objects, matrix)];
+                turn = Some(matrix);
+            }
+
+            (Scene::from_objects(objects, aspect_r), shutter)
+        }
+    };
+
+    ret.lights = parse_lights(description, shutter)?;
+    if let Some(matrix) = turn {
+        if !ret.lights.is_empty() {
+            let lights = std::mem::take(&mut ret.lights);
+            let turned = Transform::new(Box::new(lights), matrix)
+                .expect("Rotations can be inverted");
+            ret.lights = Lights::new(vec![Box::new(turned)]);
+        }
+    }
+
+    ret.stats = stats;
+    ret.atmosphere = scene
+        .atmosphere
+        .as_ref()
+        .map(|a| parse_atmosphere(a, shutter.open));
+    ret.background = scene
+        .background
+        .as_ref()
+        .map_or_else(Vector::zeros, |b| b.at(shutter.open));
+    Ok(ret)
+}
+
+fn parse_atmosphere(atmosphere: &SchemaAtmosphere, time: f32) -> Atmosphere {
+    let albedo = atmosphere
+        .albedo
+        .as_ref()
+        .map_or_else(Vector::ones, |a| a.at(time));
+    let anisotropy = atmosphere.anisotropy.as_ref().map_or(0.0, |g| g.at(time));
+    let phase_function = Box::new(HenyeyGreenstein::new(
+        Box::new(ConstantTexture::new(albedo)),
+        anisotropy,
+        None,
+    ));
+
+    Atmosphere::new(
+        atmosphere.density.at(time),
+        atmosphere.falloff.as_ref().map(|f| f.at(time)),
+        atmosphere.base_height.as_ref().map_or(0.0, |h| h.at(time)),
+        phase_function,
+    )
+}
+
+/// Converts a tree of SDF nodes. Sizes are given in full but the
+/// functions work with half sizes, and twists are given in degrees per
+/// unit of height.
+fn parse_sdf(node: &SchemaSdf) -> Result<SdfNode, String> {
+    let items = || {
+        let items = node.items.as_ref().unwrap();
+        if items.is_empty() {
+            return Err(format!("SDF {} needs at least one item", node.name));
+        }
+
+        items.iter().map(parse_sdf).collect()
+    };
+    let inner = || parse_sdf(node.inner.as_ref().unwrap()).map(Box::new);
+
+    Ok(match node.name.as_str() {
+        "Sphere" => SdfNode::Sphere {
+            radius: node.radius.unwrap(),
+        },
+        "Box" => SdfNode::Box {
+            half_size: node.size.as_ref().unwrap().value() / 2.0,
+            rounding: node.rounding.unwrap_or(0.0),
+        },
+        "Capsule" => SdfNode::Capsule {
+            a: node.a.as_ref().unwrap().value(),
+            b: node.b.as_ref().unwrap().value(),
+            radius: node.radius.unwrap(),
+        },
+        "Torus" => SdfNode::Torus {
+            major_radius: node.major_radius.unwrap(),
+            minor_radius: node.minor_radius.unwrap(),
+        },
+        "Cylinder" => SdfNode::Cylinder {
+            radius: node.radius.unwrap(),
+            half_height: node.height.unwrap() / 2.0,
+        },
+        "Union" => SdfNode::Union(items()?),
+        "Intersection" => SdfNode::Intersection(items()?),
+        "Difference" => SdfNode::Difference(items()?),
+        "SmoothUnion" => SdfNode::SmoothUnion {
+            items: items()?,
+            smoothness: node.smoothness.unwrap(),
+        },
+        "Translate" => SdfNode::Translate {
+            offset: node.offset.as_ref().unwrap().value(),
+            inner: inner()?,
+        },
+        "Scale" => {
+            let factor = node.factor.unwrap();
+            if !(factor > 0.0) {
+                return Err(format!(
+                    "SDF scale factors must be positive: {}",
+                    factor
+                ));
+            }
+
+            SdfNode::Scale {
+                factor,
+                inner: inner()?,
+            }
+        }
+        "Twist" => SdfNode::Twist {
+            rate: node.angle.unwrap().to_radians(),
+            inner: inner()?,
+        },
+        "Repeat" => SdfNode::Repeat {
+            period: node.period.as_ref().unwrap().value(),
+            limit: node.limit.as_ref().map(SchemaVector::value),
+            inner: inner()?,
+        },
+        _ => unreachable!("Unknown SDF node found"),
+    })
+}
+
+/// The rotation of a turntable view about an axis through the pivot
+fn turntable_matrix(pivot: Vector, axis: RotationAxis, angle: f32) -> Matrix4 {
+    let axis = match axis {
+        RotationAxis::X => Vector::new(1.0, 0.0, 0.0),
+        RotationAxis::Y => Vector::new(0.0, 1.0, 0.0),
+        RotationAxis::Z => Vector::new(0.0, 0.0, 1.0),
+    };
 
-            Scene::from_objects_and_cam(objects, camera)
+    Matrix4::translation(pivot)
+        * Matrix4::rotation(axis, angle)
+        * Matrix4::translation(-pivot)
+}
+
+/// Rotates all of the objects in a scene for a turntable view
+fn turn_scene(
+    objects: Vec<Box<dyn Hittable>>,
+    matrix: Matrix4,
+) -> Box<dyn Hittable> {
+    let scene = Box::new(Scene::from_objects(objects, 0.0));
+
+    Box::new(Transform::new(scene, matrix).expect("Rotations can be inverted"))
+}
+
+/// Evaluates the camera's properties at the given time
+fn camera_settings(
+    c: &SchemaCamera,
+    camera_path: Option<&CameraPath>,
+    aspect_r: f32,
+    time: f32,
+    shutter: Shutter,
+    view: Option<TurntableView>,
+) -> CameraConstructor {
+    let mut settings = CameraConstructor {
+        look_from: c
+            .look_from
+            .as_ref()
+            .map_or_else(Vector::zeros, |v| v.at(time)),
+        look_at: c
+            .look_at
+            .as_ref()
+            .map_or_else(|| Vector::new(0.0, 0.0, -1.0), |v| v.at(time)),
+        vup: c.vup.at(time),
+        vfov: c.vfov.at(time),
+        aspect_r,
+        aperture: c.aperture.as_ref().map(|a| a.at(time)).unwrap_or(0.0001),
+        focus_dist: 1.0,
+        t0: shutter.open,
+        t1: shutter.close,
+    };
+
+    if let Some(path) = camera_path {
+        path.apply(time, &mut settings);
+    }
+
+    if let Some(TurntableView::Camera { angle }) = view {
+        let orbit = Quaternion::from_axis_angle(settings.vup, angle);
+        settings.look_from = settings.look_at
+            + orbit.rotate(settings.look_from - settings.look_at);
+    }
+
+    settings.focus_dist = match (&c.focus_target, &c.focus_dist) {
+        (Some(target), _) => (settings.look_from - target.at(time)).length(),
+        (None, Some(dist)) => dist.at(time),
+        (None, None) => (settings.look_from - settings.look_at).length(),
+    };
+
+    settings
+}
+
+fn camera_is_animated(c: &SchemaCamera) -> bool {
+    let animated = |v: &Option<SchemaAnimatedVector>| {
+        v.as_ref().map_or(false, Animated::is_animated)
+    };
+
+    c.path.is_some()
+        || animated(&c.look_from)
+        || animated(&c.look_at)
+        || animated(&c.focus_target)
+        || c.vup.is_animated()
+        || c.vfov.is_animated()
+        || c.focus_dist.as_ref().map_or(false, Animated::is_animated)
+}
+
+fn parse_camera_path(path: &SchemaCameraPath) -> Result<CameraPath, String> {
+    let spline = |s: &SchemaSpline| {
+        Spline::new(s.kind, s.points.iter().map(SchemaVector::value).collect())
+    };
+
+    Ok(CameraPath {
+        path: spline(&path.path)?,
+        target: match &path.target {
+            Some(target) => Some(spline(target)?),
+            None => None,
+        },
+        look_along_tangent: path.look_along_tangent.unwrap_or(false),
+        start: path.start.unwrap_or(0.0),
+        duration: path.duration,
+    })
+}
+
+/// Shapes that can be sampled directly when they're used as lights
+const SAMPLED_LIGHTS: [&str; 9] = [
+    "Rectangle",
+    "Sphere",
+    "Triangle",
+    "Quad",
+    "Disk",
+    "Annulus",
+    "Cylinder",
+    "Cone",
+    "Paraboloid",
+];
+
+/// Builds a second copy of the scene's emissive shapes for sampling
+/// lights. Only shapes at the top level of the scene or inside BVHs are
+/// found, since anything else may have been moved or repeated. Spheres
+/// that move while the shutter is open are left out too, since where
+/// they are depends on the time of each ray.
+fn parse_lights(
+    description: &SceneDescription,
+    shutter: Shutter,
+) -> Result<Lights, String> {
+    fn find<'a>(
+        objects: &'a [Box<SchemaObject>],
+        shutter: Shutter,
+        found: &mut Vec<&'a Box<SchemaObject>>,
+    ) {
+        for object in objects {
+            let moving = object
+                .center
+                .as_ref()
+                .map_or(false, |c| c.is_animated() && shutter.is_open());
+
+            if object.name == "BVH" {
+                find(object.items.as_ref().unwrap(), shutter, found);
+            } else if SAMPLED_LIGHTS.contains(&object.name.as_str())
+                && object.material.as_ref().unwrap().name == "DiffuseLight"
+                && !moving
+            {
+                found.push(object);
+            }
+        }
+    }
+
+    let mut found = Vec::new();
+    find(&description.scene.objects, shutter, &mut found);
+
+    let mut ctx = BuildContext {
+        shutter,
+        definitions: Definitions::new(),
+        group_transforms: &description.group_transforms,
+        meshes: &description.meshes,
+        grids: &description.grids,
+        height_maps: &description.height_maps,
+        stats: &mut SceneStats::default(),
+    };
+
+    let mut lights = Vec::new();
+    for object in found {
+        lights.append(&mut parse_objects(
+            std::slice::from_ref(object),
+            &mut ctx,
+        )?);
+    }
+
+    Ok(Lights::new(lights))
+}
+
+/// Parses the top level objects of the scene along with any spheres
+/// from the physics simulation
+fn parse_scene_objects(
+    description: &SceneDescription,
+    shutter: Shutter,
+    stats: &mut SceneStats,
+) -> Result<Vec<Box<dyn Hittable>>, String> {
+    let scene = &description.scene;
+    let mut ctx = BuildContext {
+        shutter,
+        definitions: Definitions::new(),
+        group_transforms: &description.group_transforms,
+        meshes: &description.meshes,
+        grids: &description.grids,
+        height_maps: &description.height_maps,
+        stats,
+    };
+
+    // Each definition is built once and can use the definitions before it
+    for definition in scene.definitions.iter().flatten() {
+        let object =
+            parse_objects(std::slice::from_ref(&definition.object), &mut ctx)?
+                .pop()
+                .unwrap();
+
+        ctx.definitions
+            .insert(definition.name.clone(), Arc::from(object));
+    }
+
+    let mut objects = parse_objects(&scene.objects, &mut ctx)?;
+
+    if let (Some(simulation), Some(trajectories)) =
+        (&scene.simulation, &description.trajectories)
+    {
+        objects.append(&mut simulated_objects(
+            simulation,
+            trajectories,
+            shutter,
+        ));
+    }
+
+    Ok(objects)
+}
+
+fn run_simulation(
+    simulation: &SchemaSimulation,
+) -> Result<Trajectories, String> {
+    let dt = simulation.dt.unwrap_or(0.001);
+    if !(dt > 0.0) {
+        return Err(format!("Simulation time step must be positive: {}", dt));
+    }
+    if !(simulation.duration >= 0.0) {
+        return Err(format!(
+            "Simulation duration can't be negative: {}",
+            simulation.duration
+        ));
+    }
+
+    let gravity = simulation
+        .gravity
+        .as_ref()
+        .map(SchemaVector::value)
+        .unwrap_or_else(|| Vector::new(0.0, -9.81, 0.0));
+
+    let planes = simulation
+        .planes
+        .iter()
+        .flatten()
+        .map(|p| physics::Plane {
+            point: p.point.value(),
+            normal: p.normal.value().normalize(),
+            restitution: p.restitution.unwrap_or(DEFAULT_RESTITUTION),
+            friction: p.friction.unwrap_or(DEFAULT_FRICTION),
+        })
+        .collect();
+
+    for (i, body) in simulation.bodies.iter().enumerate() {
+        if let Some(mass) = body.mass {
+            if !(mass > 0.0) {
+                return Err(format!(
+                    "Simulated body {} must have a positive mass: {}",
+                    i, mass
+                ));
+            }
         }
-        None => Scene::from_objects(
-            parse_objects(scene.objects, 0.0, 0.0),
-            aspect_r,
+    }
+
+    let bodies = simulation
+        .bodies
+        .iter()
+        .map(|b| physics::Body {
+            center: b.center.value(),
+            velocity: b
+                .velocity
+                .as_ref()
+                .map(SchemaVector::value)
+                .unwrap_or_else(Vector::zeros),
+            radius: b.radius,
+            mass: b.mass.unwrap_or(1.0),
+            restitution: b.restitution.unwrap_or(DEFAULT_RESTITUTION),
+            friction: b.friction.unwrap_or(DEFAULT_FRICTION),
+        })
+        .collect();
+
+    let world = World {
+        gravity,
+        bodies,
+        planes,
+    };
+
+    Ok(
+        world.simulate(
+            simulation.start.unwrap_or(0.0),
+            simulation.duration,
+            dt,
         ),
+    )
+}
+
+/// Creates a sphere for each simulated body. If the shutter is open the
+/// sphere moves between its positions at the start and end of the
+/// interval so that it is motion blurred.
+fn simulated_objects(
+    simulation: &SchemaSimulation,
+    trajectories: &Trajectories,
+    shutter: Shutter,
+) -> Vec<Box<dyn Hittable>> {
+    let mut objects: Vec<Box<dyn Hittable>> = Vec::new();
+
+    for (i, body) in simulation.bodies.iter().enumerate() {
+        let material = parse_material(&body.material, shutter.open);
+        let center0 = trajectories.position(i, shutter.open);
+
+        if shutter.is_open() {
+            objects.push(Box::new(MovingSphere::new(
+                center0,
+                trajectories.position(i, shutter.close),
+                shutter.open,
+                shutter.close,
+                body.radius,
+                material,
+            )));
+        } else {
+            objects.push(Box::new(Sphere::new(center0, body.radius, material)));
+        }
     }
+
+    objects
 }
 
-fn parse_texture(texture: SchemaTexture) -> Box<dyn Texture> {
+fn parse_texture(texture: &SchemaTexture, time: f32) -> Box<dyn Texture> {
     match texture.name.as_str() {
-        "Constant" => {
-            let values = texture.values.unwrap();
-            Box::new(ConstantTexture::new(Vector::new(
-                values.x, values.y, values.z,
-            )))
+        "Constant" => Box::new(ConstantTexture::new(at(&texture.values, time))),
+        "Blackbody" => {
+            let temperature = texture.temperature.as_ref().map(|t| t.at(time));
+            let scale = texture.scale.as_ref().map_or(1.0, |s| s.at(time));
+            Box::new(Blackbody::new(temperature, scale))
         }
         _ => {
             unreachable!(
@@ -113,26 +872,40 @@ fn parse_texture(texture: SchemaTexture) -> Box<dyn Texture> {
     }
 }
 
-fn parse_material(material: SchemaMaterial) -> Box<dyn Material> {
+fn parse_material(material: &SchemaMaterial, time: f32) -> Box<dyn Material> {
     match material.name.as_str() {
         "Metal" => {
-            let albedo = material.albedo.unwrap();
-            let fuzz = material.fuzz.unwrap();
+            let albedo = at(&material.albedo, time);
+            let fuzz = at(&material.fuzz, time);
             Box::new(Metal::new(albedo.x, albedo.y, albedo.z, fuzz))
         }
         "Lambertian" => {
-            let albedo = material.albedo.unwrap();
-            Box::new(Lambertian::new(Box::new(ConstantTexture::new(
-                Vector::new(albedo.x, albedo.y, albedo.z),
-            ))))
+            let albedo = at(&material.albedo, time);
+            Box::new(Lambertian::new(Box::new(ConstantTexture::new(albedo))))
         }
         "Dielectric" => {
-            let ref_idx = material.ref_idx.unwrap();
+            let ref_idx = at(&material.ref_idx, time);
             Box::new(Dielectric::new(ref_idx))
         }
         "DiffuseLight" => {
-            let texture = material.texture.unwrap();
-            Box::new(DiffuseLight::new(parse_texture(texture)))
+            let texture = material.texture.as_ref().unwrap();
+            Box::new(DiffuseLight::new(parse_texture(texture, time)))
+        }
+        "Isotropic" => {
+            let albedo = at(&material.albedo, time);
+            Box::new(Isotropic::new(Box::new(ConstantTexture::new(albedo))))
+        }
+        "HenyeyGreenstein" => {
+            let albedo = at(&material.albedo, time);
+            let anisotropy =
+                material.anisotropy.as_ref().map_or(0.0, |g| g.at(time));
+            let emission =
+                material.emission.as_ref().map(|e| parse_texture(e, time));
+            Box::new(HenyeyGreenstein::new(
+                Box::new(ConstantTexture::new(albedo)),
+                anisotropy,
+                emission,
+            ))
         }
         _ => {
             unreachable!(
@@ -144,100 +917,203 @@ fn parse_material(material: SchemaMaterial) -> Box<dyn Material> {
 }
 
 fn parse_objects(
-    scene_objects: Vec<Box<SchemaObject>>,
-    t0: f32,
-    t1: f32,
-) -> Vec<Box<dyn Hittable>> {
+    scene_objects: &[Box<SchemaObject>],
+    ctx: &mut BuildContext,
+) -> Result<Vec<Box<dyn Hittable>>, String> {
     let mut objects: Vec<Box<dyn Hittable>> = Vec::new();
+    let shutter = ctx.shutter;
+    let (t0, t1) = (shutter.open, shutter.close);
 
     for object in scene_objects {
         if object.name == "BVH" {
-            objects.push(Bvh::construct(
-                &mut parse_objects(object.items.unwrap(), t0, t1),
-                t0,
-                t1,
-            ));
+            let items = parse_objects(object.items.as_ref().unwrap(), ctx)?;
+            objects.push(build_bvh(items, ctx));
             continue;
         } else if object.name == "Rotate" {
-            let angle = object.angle.unwrap();
+            let inner = parse_inner(object, ctx)?;
+            let motion =
+                wrapper_motion(object, &object.angle, &object.angle2, shutter);
+            let angle = at(&object.angle, t0);
 
-            let inner = parse_objects(vec![object.inner.unwrap()], t0, t1)
-                .pop()
-                .unwrap();
+            match object.axis.as_ref().unwrap().as_str() {
+                "X" => objects
+                    .push(rotate::<{ RotationAxis::X }>(inner, angle, motion)),
+                "Y" => objects
+                    .push(rotate::<{ RotationAxis::Y }>(inner, angle, motion)),
+                "Z" => objects
+                    .push(rotate::<{ RotationAxis::Z }>(inner, angle, motion)),
+                _ => unreachable!("Unknown rotation axis found"),
+            }
+            continue;
+        } else if object.name == "Translate" {
+            let inner = parse_inner(object, ctx)?;
 
-            match object.axis.unwrap().as_str() {
-                "X" => {
-                    objects.push(Box::new(Rotate::<{ RotationAxis::X }>::new(
-                        inner, angle,
+            match wrapper_motion(
+                object,
+                &object.offset,
+                &object.offset2,
+                shutter,
+            ) {
+                Some((offset0, offset1, t0, t1)) => {
+                    objects.push(Box::new(Translate::new_moving(
+                        inner, offset0, offset1, t0, t1,
                     )));
                 }
-                "Y" => {
-                    objects.push(Box::new(Rotate::<{ RotationAxis::Y }>::new(
-                        inner, angle,
-                    )));
+                None => {
+                    let offset = at(&object.offset, t0);
+                    objects.push(Box::new(Translate::new(inner, offset)));
                 }
-                "Z" => {
-                    objects.push(Box::new(Rotate::<{ RotationAxis::Z }>::new(
-                        inner, angle,
-                    )));
-                }
-                _ => unreachable!("Unknown rotation axis found"),
             }
             continue;
-        } else if object.name == "Translate" {
-            let offset = object.offset.unwrap();
+        } else if object.name == "ConstantMedium" {
+            let boundary = parse_inner(object, ctx)?;
+            let density = at(&object.density, t0);
+            let phase_function =
+                parse_material(object.material.as_ref().unwrap(), t0);
 
-            let inner = parse_objects(vec![object.inner.unwrap()], t0, t1)
-                .pop()
-                .unwrap();
+            objects.push(Box::new(ConstantMedium::new(
+                boundary,
+                density,
+                phase_function,
+            )));
+            continue;
+        } else if object.name == "Union"
+            || object.name == "Intersection"
+            || object.name == "Difference"
+        {
+            let operation = match object.name.as_str() {
+                "Union" => CsgOperation::Union,
+                "Intersection" => CsgOperation::Intersection,
+                _ => CsgOperation::Difference,
+            };
 
-            objects.push(Box::new(Translate {
-                offset: Vector::new(offset.x, offset.y, offset.z),
-                hittable: inner,
+            // Lists of more than two objects are combined from the left,
+            // so a difference subtracts every later object from the first
+            let mut items =
+                parse_objects(object.items.as_ref().unwrap(), ctx)?.into_iter();
+            let first = items
+                .next()
+                .expect("CSG items are checked when the scene is loaded");
+            objects.push(items.fold(first, |left, right| {
+                Box::new(Csg::new(operation, left, right))
             }));
             continue;
+        } else if object.name == "Transform" {
+            let inner = parse_inner(object, ctx)?;
+            let transforms = object.transforms.as_ref().unwrap();
+
+            objects.push(moving_transform(
+                inner,
+                |time| transform_steps(transforms, time),
+                transforms_are_animated(transforms),
+                shutter,
+            )?);
+            continue;
+        } else if object.name == "Group" {
+            let items = parse_objects(object.items.as_ref().unwrap(), ctx)?;
+            let bvh = build_bvh(items, ctx);
+
+            // Nested groups are wrapped in their own Transforms, so the
+            // transforms compose down the hierarchy
+            let transforms =
+                object.transforms.as_ref().map_or(&[][..], Vec::as_slice);
+            let group_transforms = object
+                .id
+                .as_ref()
+                .and_then(|id| ctx.group_transforms.get(id));
+
+            objects.push(match group_transforms {
+                Some(transforms) => moving_transform(
+                    bvh,
+                    |time| transforms.at(time),
+                    transforms.is_animated(),
+                    shutter,
+                )?,
+                None if transforms.is_empty() => bvh,
+                None => moving_transform(
+                    bvh,
+                    |time| transform_steps(transforms, time),
+                    transforms_are_animated(transforms),
+                    shutter,
+                )?,
+            });
+            continue;
+        } else if object.name == "Mesh" {
+            let data = ctx.meshes[&mesh_key(object)].clone();
+            let material =
+                object.material.as_ref().map(|m| parse_material(m, t0));
+            let mesh = Arc::new(Mesh::new(data, material));
+
+            let triangles =
+                mesh.triangles(object.groups.as_ref().map(Vec::as_slice));
+            objects.push(build_bvh(triangles, ctx));
+            continue;
+        } else if object.name == "Instance" {
+            let geometry =
+                &ctx.definitions[object.definition.as_ref().unwrap()];
+            let material =
+                object.material.as_ref().map(|m| parse_material(m, t0));
+            let instance = Box::new(Instance::new(geometry.clone(), material));
+
+            match &object.transforms {
+                Some(transforms) => objects.push(moving_transform(
+                    instance,
+                    |time| transform_steps(transforms, time),
+                    transforms_are_animated(transforms),
+                    shutter,
+                )?),
+                None => objects.push(instance),
+            }
+            continue;
         }
 
-        let object_material = object.material.unwrap();
-        let material: Box<dyn Material> = parse_material(object_material);
+        let object_material = object.material.as_ref().unwrap();
+        let material: Box<dyn Material> = parse_material(object_material, t0);
 
         match object.name.as_str() {
             "Sphere" => {
-                let center = object.center.unwrap();
-                let radius = object.radius.unwrap();
+                let center = object.center.as_ref().unwrap();
+                let radius = at(&object.radius, t0);
 
-                objects.push(Box::new(Sphere::new(
-                    Vector::new(center.x, center.y, center.z),
-                    radius,
-                    material,
-                )));
+                // An animated sphere is blurred across the shutter interval
+                if center.is_animated() && shutter.is_open() {
+                    objects.push(Box::new(MovingSphere::new(
+                        center.at(t0),
+                        center.at(t1),
+                        t0,
+                        t1,
+                        radius,
+                        material,
+                    )));
+                } else {
+                    objects.push(Box::new(Sphere::new(
+                        center.at(t0),
+                        radius,
+                        material,
+                    )));
+                }
             }
             "MovingSphere" => {
-                let center = object.center.unwrap();
-                let center2 = object.center2.unwrap();
-                let radius = object.radius.unwrap();
+                let center = at(&object.center, t0);
+                let center2 = at(&object.center2, t0);
+                let radius = at(&object.radius, t0);
                 let t0 = object.t0.unwrap();
                 let t1 = object.t1.unwrap();
 
                 objects.push(Box::new(MovingSphere::new(
-                    Vector::new(center.x, center.y, center.z),
-                    Vector::new(center2.x, center2.y, center2.z),
-                    t0,
-                    t1,
-                    radius,
-                    material,
+                    center, center2, t0, t1, radius, material,
                 )));
             }
             "Rectangle" => {
-                let a0 = object.a0.unwrap();
-                let a1 = object.a1.unwrap();
-                let b0 = object.b0.unwrap();
-                let b1 = object.b1.unwrap();
+                let a0 = at(&object.a0, t0);
+                let a1 = at(&object.a1, t0);
+                let b0 = at(&object.b0, t0);
+                let b1 = at(&object.b1, t0);
 
-                let k = object.k.unwrap();
+                let k = at(&object.k, t0);
                 let flip = object.flip.unwrap();
 
-                match object.plane.unwrap().as_str() {
+                match object.plane.as_ref().unwrap().as_str() {
                     "XY" => {
                         objects.push(Box::new(
                             Rectangle::<{ RectPlane::XY }> {
@@ -283,12 +1159,129 @@ fn parse_objects(
                 }
             }
             "Block" => {
-                let p0 = object.p0.unwrap();
-                let p1 = object.p1.unwrap();
+                let p0 = at(&object.p0, t0);
+                let p1 = at(&object.p1, t0);
+
+                objects.push(Box::new(Block::new(p0, p1, material)));
+            }
+            "Triangle" => {
+                let vertices = object.vertices.as_ref().unwrap();
+                let vertices = [
+                    vertices[0].at(t0),
+                    vertices[1].at(t0),
+                    vertices[2].at(t0),
+                ];
+
+                objects.push(Box::new(Triangle::new(vertices, material)));
+            }
+            "Volume" => {
+                let density = ctx.grids[object.file.as_ref().unwrap()].clone();
+                let density_scale =
+                    object.density.as_ref().map_or(1.0, |d| d.at(t0));
+                let temperature = object.temperature_file.as_ref().map(|f| {
+                    let scale = object
+                        .temperature_scale
+                        .as_ref()
+                        .map_or(1.0, |s| s.at(t0));
+                    (ctx.grids[f].clone(), scale)
+                });
+
+                objects.push(Box::new(GridVolume::new(
+                    density,
+                    density_scale,
+                    temperature,
+                    material,
+                )));
+            }
+            "Heightfield" => {
+                let map =
+                    ctx.height_maps[object.file.as_ref().unwrap()].clone();
 
-                objects.push(Box::new(Block::new(
-                    Vector::new(p0.x, p0.y, p0.z),
-                    Vector::new(p1.x, p1.y, p1.z),
+                objects.push(Box::new(Heightfield::new(
+                    map,
+                    at(&object.corner, t0),
+                    at(&object.size, t0),
+                    material,
+                )));
+            }
+            "Sdf" => {
+                let bounds = match (&object.p0, &object.p1) {
+                    (Some(p0), Some(p1)) => {
+                        Some(Aabb::new(p0.at(t0), p1.at(t0)))
+                    }
+                    _ => None,
+                };
+
+                objects.push(Box::new(
+                    Sdf::new(
+                        parse_sdf(object.root.as_ref().unwrap()).expect(
+                            "SDFs are checked when the scene is loaded",
+                        ),
+                        bounds,
+                        object.epsilon.unwrap_or(DEFAULT_SDF_EPSILON),
+                        object.max_steps.unwrap_or(DEFAULT_SDF_MAX_STEPS),
+                        material,
+                    )
+                    .expect("SDF shapes that repeat forever need bounds"),
+                ));
+            }
+            "Plane" => {
+                let point = at(&object.point, t0);
+                let normal = at(&object.normal, t0);
+
+                objects.push(Box::new(Plane::new(point, normal, material)));
+            }
+            "Quad" => {
+                let corner = at(&object.corner, t0);
+                let u = at(&object.u, t0);
+                let v = at(&object.v, t0);
+
+                objects.push(Box::new(Quad::new(corner, u, v, material)));
+            }
+            "Cylinder" | "Cone" | "Paraboloid" => {
+                let base = at(&object.base, t0);
+                let radius = at(&object.radius, t0);
+                let height = at(&object.height, t0);
+                let capped = object.capped.unwrap_or(false);
+
+                objects.push(match object.name.as_str() {
+                    "Cylinder" => Box::new(Cylinder::new(
+                        base, radius, height, capped, material,
+                    )),
+                    "Cone" => Box::new(Cone::new(
+                        base, radius, height, capped, material,
+                    )),
+                    _ => Box::new(Paraboloid::new(
+                        base, radius, height, capped, material,
+                    )),
+                });
+            }
+            "Torus" => {
+                let center = at(&object.center, t0);
+                let major_radius = at(&object.major_radius, t0);
+                let minor_radius = at(&object.minor_radius, t0);
+
+                objects.push(Box::new(Torus::new(
+                    center,
+                    major_radius,
+                    minor_radius,
+                    material,
+                )));
+            }
+            "Disk" | "Annulus" => {
+                let center = at(&object.center, t0);
+                let radius = at(&object.radius, t0);
+                let inner_radius = object
+                    .inner_radius
+                    .as_ref()
+                    .map_or(0.0, |inner_radius| inner_radius.at(t0));
+                let flip = object.flip.unwrap_or(false);
+
+                objects.push(Box::new(Disk::new(
+                    center,
+                    radius,
+                    inner_radius,
+                    flip,
                     material,
                 )));
             }
@@ -298,7 +1291,640 @@ fn parse_objects(
         }
     }
 
-    objects
+    Ok(objects)
+}
+
+/// Determines how a Translate or Rotate object moves, if at all. The motion
+/// is either given explicitly with a second value (`offset2` or `angle2`)
+/// and a time interval, or comes from an animated property in which case
+/// the object moves across the shutter interval.
+///
+/// Returns the start value, end value, and the time interval.
+fn wrapper_motion<T: Animatable>(
+    object: &SchemaObject,
+    property: &Option<Animated<T>>,
+    property2: &Option<Animated<T>>,
+    shutter: Shutter,
+) -> Option<(T::Value, T::Value, f32, f32)> {
+    let property = property.as_ref().unwrap();
+
+    if let Some(end) = property2.as_ref().map(|p| p.at(shutter.open)) {
+        Some((
+            property.at(shutter.open),
+            end,
+            object.t0.unwrap(),
+            object.t1.unwrap(),
+        ))
+    } else if property.is_animated() && shutter.is_open() {
+        Some((
+            property.at(shutter.open),
+            property.at(shutter.close),
+            shutter.open,
+            shutter.close,
+        ))
+    } else {
+        None
+    }
+}
+
+/// Creates a Rotate object, which moves if the motion from
+/// [`wrapper_motion`] is given
+///
+/// [`wrapper_motion`]: fn.wrapper_motion.html
+fn rotate<const A: RotationAxis>(
+    inner: Box<dyn Hittable>,
+    angle: f32,
+    motion: Option<(f32, f32, f32, f32)>,
+) -> Box<dyn Hittable> {
+    match motion {
+        Some((angle0, angle1, t0, t1)) => {
+            Box::new(Rotate::<{ A }>::new_moving(inner, angle0, angle1, t0, t1))
+        }
+        None => Box::new(Rotate::<{ A }>::new(inner, angle)),
+    }
+}
+
+/// A single transformation step evaluated at some point in time
+#[derive(Debug, Copy, Clone)]
+enum TransformStep {
+    Translate(Vector),
+    Rotate(Vector, f32),
+    Scale(Vector),
+    Matrix(Matrix4),
+}
+
+impl TransformStep {
+    fn matrix(self) -> Matrix4 {
+        match self {
+            TransformStep::Translate(offset) => Matrix4::translation(offset),
+            TransformStep::Rotate(axis, angle) => {
+                Matrix4::rotation(axis, angle)
+            }
+            TransformStep::Scale(factors) => Matrix4::scale(factors),
+            TransformStep::Matrix(matrix) => matrix,
+        }
+    }
+}
+
+/// Lists of steps are interpolated step by step, so keyframed transforms
+/// need to have the same steps in the same order
+impl Lerp for Vec<TransformStep> {
+    fn lerp(a: Self, b: Self, t: f32) -> Self {
+        use TransformStep::*;
+
+        a.into_iter()
+            .zip(b)
+            .map(|steps| match steps {
+                (Translate(a), Translate(b)) => Translate(Lerp::lerp(a, b, t)),
+                (Rotate(a, angle_a), Rotate(b, angle_b)) => {
+                    Rotate(Lerp::lerp(a, b, t), Lerp::lerp(angle_a, angle_b, t))
+                }
+                (Scale(a), Scale(b)) => Scale(Lerp::lerp(a, b, t)),
+                (Matrix(a), Matrix(b)) => Matrix(Lerp::lerp(a, b, t)),
+                _ => unreachable!("Mismatched transform keyframes found"),
+            })
+            .collect()
+    }
+}
+
+impl Animatable for Vec<SchemaTransform> {
+    type Value = Vec<TransformStep>;
+    fn value(&self) -> Vec<TransformStep> {
+        transform_steps(self, 0.0)
+    }
+}
+
+/// Evaluates each step of a list of transformations at the given time
+fn transform_steps(
+    transforms: &[SchemaTransform],
+    time: f32,
+) -> Vec<TransformStep> {
+    transforms
+        .iter()
+        .map(|step| {
+            if let Some(offset) = &step.translate {
+                TransformStep::Translate(offset.at(time))
+            } else if let Some(rotation) = &step.rotate {
+                TransformStep::Rotate(
+                    rotation.axis.value(),
+                    rotation.angle.at(time),
+                )
+            } else if let Some(factors) = &step.scale {
+                TransformStep::Scale(factors.at(time))
+            } else if let Some(values) = &step.matrix {
+                TransformStep::Matrix(
+                    Matrix4::from_rows(values).expect(
+                        "Matrices are checked when the scene is loaded",
+                    ),
+                )
+            } else {
+                unreachable!("Unknown transformation found");
+            }
+        })
+        .collect()
+}
+
+/// Combines a list of transformation steps into a single matrix. The steps
+/// are applied to the object in the order they're listed.
+fn compose(steps: &[TransformStep]) -> Matrix4 {
+    steps
+        .iter()
+        .fold(Matrix4::identity(), |acc, step| step.matrix() * acc)
+}
+
+/// Whether any step of a list of transformations changes over time
+fn transforms_are_animated(transforms: &[SchemaTransform]) -> bool {
+    transforms.iter().any(|step| {
+        step.translate.as_ref().map_or(false, Animated::is_animated)
+            || step
+                .rotate
+                .as_ref()
+                .map_or(false, |r| r.angle.is_animated())
+            || step.scale.as_ref().map_or(false, Animated::is_animated)
+    })
+}
+
+/// Parses the `inner` object of a wrapper object such as Rotate
+fn parse_inner(
+    object: &SchemaObject,
+    ctx: &mut BuildContext,
+) -> Result<Box<dyn Hittable>, String> {
+    let inner = object.inner.as_ref().unwrap();
+    Ok(parse_objects(std::slice::from_ref(inner), ctx)?
+        .pop()
+        .unwrap())
+}
+
+/// Wraps an object in a transformation, which fails if the matrix can't be
+/// inverted at the given time, e.g. when it scales by zero
+fn transform(
+    hittable: Box<dyn Hittable>,
+    matrix: Matrix4,
+    time: f32,
+) -> Result<Box<dyn Hittable>, String> {
+    match Transform::new(hittable, matrix) {
+        Some(transform) => Ok(Box::new(transform)),
+        None => {
+            Err(format!("Transformation at time {} can't be inverted", time))
+        }
+    }
+}
+
+/// Wraps an object in the transformation that `steps` gives at each point
+/// in time. While the shutter is open, an animated transformation is
+/// sampled across the shutter interval so the object blurs along its
+/// motion.
+fn moving_transform<F>(
+    hittable: Box<dyn Hittable>,
+    steps: F,
+    animated: bool,
+    shutter: Shutter,
+) -> Result<Box<dyn Hittable>, String>
+where
+    F: Fn(f32) -> Vec<TransformStep>,
+{
+    let (t0, t1) = (shutter.open, shutter.close);
+    if !animated || !shutter.is_open() {
+        return transform(hittable, compose(&steps(t0)), t0);
+    }
+
+    // The rotation between each pair of samples takes the shortest path,
+    // so spins of 180 degrees or more are split into segments that each
+    // turn by at most 90 degrees
+    let spin: f32 = steps(t0)
+        .iter()
+        .zip(&steps(t1))
+        .map(|pair| match pair {
+            (TransformStep::Rotate(_, a), TransformStep::Rotate(_, b)) => {
+                (b - a).abs()
+            }
+            _ => 0.0,
+        })
+        .sum();
+    let segments = (spin / 90.0).ceil().max(1.0) as usize;
+
+    let matrices: Vec<Matrix4> = (0..=segments)
+        .map(|i| {
+            let frac = i as f32 / segments as f32;
+            compose(&steps(Lerp::lerp(t0, t1, frac)))
+        })
+        .collect();
+
+    match Transform::new_moving(hittable, &matrices, t0, t1) {
+        Some(transform) => Ok(Box::new(transform)),
+        None => Err(format!(
+            "Transformation between times {} and {} can't be inverted",
+            t0, t1
+        )),
+    }
+}
+
+/// Builds a BVH over the given objects, recording how long it took.
+/// Objects without a bounding box, such as planes, can't go in the BVH, so
+/// they're kept in a list alongside it and always tested.
+fn build_bvh(
+    items: Vec<Box<dyn Hittable>>,
+    ctx: &mut BuildContext,
+) -> Box<dyn Hittable> {
+    let (mut bounded, mut unbounded): (Vec<_>, Vec<_>) =
+        items.into_iter().partition(|item| {
+            item.bounding_box(ctx.shutter.open, ctx.shutter.close)
+                .is_some()
+        });
+
+    if !bounded.is_empty() {
+        // Nested BVHs are built before this is called, so the timings never
+        // overlap
+        let start = time::Instant::now();
+        let bvh =
+            Bvh::construct(&mut bounded, ctx.shutter.open, ctx.shutter.close);
+        ctx.stats.bvh_build_time += start.elapsed();
+        ctx.stats.bvh_count += 1;
+
+        if unbounded.is_empty() {
+            return bvh;
+        }
+        unbounded.push(bvh);
+    }
+
+    Box::new(Scene::from_objects(unbounded, 0.0))
+}
+
+/// Calls `f` on the object and every object nested inside of it
+fn walk<'a>(
+    object: &'a SchemaObject,
+    f: &mut dyn FnMut(&'a SchemaObject) -> Result<(), String>,
+) -> Result<(), String> {
+    f(object)?;
+
+    for child in object.items.iter().flatten().chain(&object.inner) {
+        walk(child, f)?;
+    }
+
+    Ok(())
+}
+
+/// Loads the voxel grids used by volumes
+fn load_grids(scene: &SchemaScene, dir: &Path) -> Result<Grids, String> {
+    let mut grids = Grids::new();
+    let definitions = scene.definitions.iter().flatten().map(|d| &d.object);
+
+    for object in definitions.chain(&scene.objects) {
+        walk(object, &mut |o| {
+            if o.name != "Volume" {
+                return Ok(());
+            }
+
+            for file in o.file.iter().chain(&o.temperature_file) {
+                if !grids.contains_key(file) {
+                    let grid = loaders::load_vol(&dir.join(file))?;
+                    grids.insert(file.clone(), Arc::new(grid));
+                }
+            }
+
+            Ok(())
+        })?;
+    }
+
+    Ok(grids)
+}
+
+/// Loads the height maps used by heightfields
+fn load_height_maps(
+    scene: &SchemaScene,
+    dir: &Path,
+) -> Result<HeightMaps, String> {
+    let mut height_maps = HeightMaps::new();
+    let definitions = scene.definitions.iter().flatten().map(|d| &d.object);
+
+    for object in definitions.chain(&scene.objects) {
+        walk(object, &mut |o| {
+            if o.name != "Heightfield" {
+                return Ok(());
+            }
+
+            let file = o.file.as_ref().unwrap();
+            if !height_maps.contains_key(file) {
+                let map = loaders::load_heightmap(&dir.join(file))?;
+                height_maps.insert(file.clone(), Arc::new(map));
+            }
+
+            Ok(())
+        })?;
+    }
+
+    Ok(height_maps)
+}
+
+/// Makes sure that every Instance refers to a definition which has already
+/// been declared, since the schema can't check this
+fn check_instances(scene: &SchemaScene) -> Result<(), String> {
+    let mut declared = Vec::new();
+    let check = |declared: &[&str], object: &SchemaObject| {
+        walk(object, &mut |o| match &o.definition {
+            Some(name) if !declared.contains(&name.as_str()) => {
+                Err(format!("Unknown object definition {}", name))
+            }
+            _ => Ok(()),
+        })
+    };
+
+    for definition in scene.definitions.iter().flatten() {
+        check(&declared, &definition.object)?;
+        declared.push(definition.name.as_str());
+    }
+
+    for object in &scene.objects {
+        check(&declared, object)?;
+    }
+
+    Ok(())
+}
+
+/// Makes sure that Blackbody textures without a fixed temperature are only
+/// used for the emission of volumes with a temperature grid, since they
+/// take their temperature from the `u` coordinate that those volumes set
+fn check_blackbodies(scene: &SchemaScene) -> Result<(), String> {
+    let needs_temperature = |texture: Option<&SchemaTexture>| {
+        texture
+            .map_or(false, |t| t.name == "Blackbody" && t.temperature.is_none())
+    };
+    let definitions = scene.definitions.iter().flatten().map(|d| &d.object);
+
+    for object in definitions.chain(&scene.objects) {
+        walk(object, &mut |o| {
+            let material = match &o.material {
+                Some(material) => material,
+                None => return Ok(()),
+            };
+            let has_grid = o.name == "Volume" && o.temperature_file.is_some();
+
+            if needs_temperature(material.texture.as_ref())
+                || (needs_temperature(material.emission.as_ref()) && !has_grid)
+            {
+                return Err(String::from(
+                    "Blackbody textures need a temperature, unless they're \
+                     the emission of a Volume with a temperature_file",
+                ));
+            }
+
+            Ok(())
+        })?;
+    }
+
+    Ok(())
+}
+
+/// Makes sure that every SDF can be built, since the schema can't check
+/// that lists of shapes aren't empty, that scale factors are positive or
+/// that shapes which repeat forever are given bounds
+fn check_sdfs(scene: &SchemaScene) -> Result<(), String> {
+    let definitions = scene.definitions.iter().flatten().map(|d| &d.object);
+
+    for object in definitions.chain(&scene.objects) {
+        walk(object, &mut |o| {
+            let root = match &o.root {
+                Some(root) => parse_sdf(root)?,
+                None => return Ok(()),
+            };
+
+            let has_bounds = o.p0.is_some() && o.p1.is_some();
+            if !has_bounds && root.bounds().is_none() {
+                return Err(String::from(
+                    "SDF shapes that repeat forever need bounds",
+                ));
+            }
+
+            Ok(())
+        })?;
+    }
+
+    Ok(())
+}
+
+/// Makes sure that CSG operations and groups have something to combine,
+/// since the schema can't check that their lists of items aren't empty
+fn check_item_lists(scene: &SchemaScene) -> Result<(), String> {
+    let definitions = scene.definitions.iter().flatten().map(|d| &d.object);
+
+    for object in definitions.chain(&scene.objects) {
+        walk(object, &mut |o| {
+            let needs_items = ["Union", "Intersection", "Difference", "Group"]
+                .contains(&o.name.as_str());
+
+            if needs_items && o.items.as_ref().map_or(true, Vec::is_empty) {
+                return Err(format!("{} needs at least one item", o.name));
+            }
+
+            Ok(())
+        })?;
+    }
+
+    Ok(())
+}
+
+/// Makes sure that every keyframe track in the scene can be evaluated,
+/// since the schema can't check that tracks aren't empty or that their
+/// keyframes are in order
+fn check_tracks(scene: &SchemaScene) -> Result<(), String> {
+    fn check<T>(property: Option<&Animated<T>>) -> Result<(), String> {
+        match property {
+            Some(Animated::Track(track)) => track.check(),
+            _ => Ok(()),
+        }
+    }
+
+    let check_texture = |texture: Option<&SchemaTexture>| match texture {
+        Some(t) => {
+            check(t.values.as_ref())?;
+            check(t.temperature.as_ref())?;
+            check(t.scale.as_ref())
+        }
+        None => Ok(()),
+    };
+
+    let check_material = |material: &SchemaMaterial| {
+        check(material.albedo.as_ref())?;
+        check(material.ref_idx.as_ref())?;
+        check(material.fuzz.as_ref())?;
+        check(material.anisotropy.as_ref())?;
+        check_texture(material.texture.as_ref())?;
+        check_texture(material.emission.as_ref())
+    };
+
+    let definitions = scene.definitions.iter().flatten().map(|d| &d.object);
+    for object in definitions.chain(&scene.objects) {
+        walk(object, &mut |o| {
+            let numbers = [
+                &o.radius,
+                &o.inner_radius,
+                &o.major_radius,
+                &o.minor_radius,
+                &o.height,
+                &o.density,
+                &o.temperature_scale,
+                &o.a0,
+                &o.a1,
+                &o.b0,
+                &o.b1,
+                &o.k,
+                &o.angle,
+                &o.angle2,
+            ];
+            let vectors = [
+                &o.center, &o.center2, &o.base, &o.offset, &o.offset2, &o.p0,
+                &o.p1, &o.corner, &o.size, &o.point, &o.normal, &o.u, &o.v,
+            ];
+
+            for number in &numbers {
+                check(number.as_ref())?;
+            }
+            for vector in &vectors {
+                check(vector.as_ref())?;
+            }
+            for vertex in o.vertices.iter().flatten() {
+                check(Some(vertex))?;
+            }
+            for transform in o.transforms.iter().flatten() {
+                check(transform.translate.as_ref())?;
+                check(transform.rotate.as_ref().map(|r| &r.angle))?;
+                check(transform.scale.as_ref())?;
+            }
+            if let Some(material) = &o.material {
+                check_material(material)?;
+            }
+
+            Ok(())
+        })?;
+    }
+
+    if let Some(camera) = &scene.camera {
+        check(camera.look_from.as_ref())?;
+        check(camera.look_at.as_ref())?;
+        check(Some(&camera.vup))?;
+        check(Some(&camera.vfov))?;
+        check(camera.aperture.as_ref())?;
+        check(camera.focus_dist.as_ref())?;
+        check(camera.focus_target.as_ref())?;
+    }
+
+    if let Some(atmosphere) = &scene.atmosphere {
+        check(Some(&atmosphere.density))?;
+        check(atmosphere.albedo.as_ref())?;
+        check(atmosphere.anisotropy.as_ref())?;
+        check(atmosphere.falloff.as_ref())?;
+        check(atmosphere.base_height.as_ref())?;
+    }
+
+    check(scene.background.as_ref())
+}
+
+/// Makes sure that every list of transformations can be evaluated, since
+/// the schema can't check that each step is one of the known kinds or that
+/// matrices have 16 values
+fn check_transforms(scene: &SchemaScene) -> Result<(), String> {
+    let definitions = scene.definitions.iter().flatten().map(|d| &d.object);
+
+    for object in definitions.chain(&scene.objects) {
+        walk(object, &mut |o| match &o.transforms {
+            Some(transforms) => check_transform_list(transforms),
+            None => Ok(()),
+        })?;
+    }
+
+    Ok(())
+}
+
+fn check_transform_list(transforms: &[SchemaTransform]) -> Result<(), String> {
+    for step in transforms {
+        let kinds = [
+            step.translate.is_some(),
+            step.rotate.is_some(),
+            step.scale.is_some(),
+            step.matrix.is_some(),
+        ];
+        if kinds.iter().filter(|&&k| k).count() != 1 {
+            return Err(String::from(
+                "Each transformation needs exactly one of translate, rotate, \
+                 scale or matrix",
+            ));
+        }
+
+        if let Some(values) = &step.matrix {
+            Matrix4::from_rows(values)?;
+        }
+    }
+
+    Ok(())
+}
+
+/// The key of a Mesh object's data in the loaded meshes
+fn mesh_key(object: &SchemaObject) -> (String, Option<String>) {
+    (object.file.clone().unwrap(), object.format.clone())
+}
+
+/// Loads every mesh file used by the scene. Paths are relative to the
+/// directory containing the scene file.
+fn load_meshes(
+    scene: &SchemaScene,
+    dir: &Path,
+    meshes: &mut Meshes,
+) -> Result<(), String> {
+    let definitions = scene.definitions.iter().flatten().map(|d| &d.object);
+
+    for object in definitions.chain(&scene.objects) {
+        walk(object, &mut |o| {
+            let file = match (o.name.as_str(), &o.file) {
+                ("Mesh", Some(file)) => file,
+                _ => return Ok(()),
+            };
+
+            let key = mesh_key(o);
+            if !meshes.contains_key(&key) {
+                let format = o.format.as_ref().map(String::as_str);
+                let data = loaders::load_mesh(&dir.join(file), format)?;
+                meshes.insert(key.clone(), Arc::new(data));
+            }
+
+            // Empty meshes can't be put in a BVH
+            let data = &meshes[&key];
+            let has_faces = match &o.groups {
+                None => !data.faces.is_empty(),
+                Some(groups) => data.faces.iter().any(|f| {
+                    f.group.map_or(false, |g| groups.contains(&data.groups[g]))
+                }),
+            };
+
+            if has_faces {
+                Ok(())
+            } else {
+                Err(format!("{} has no faces to render", file))
+            }
+        })?;
+    }
+
+    Ok(())
+}
+
+/// Collects the IDs of every group, which must be unique
+fn group_ids(scene: &SchemaScene) -> Result<Vec<String>, String> {
+    let mut ids: Vec<String> = Vec::new();
+    let definitions = scene.definitions.iter().flatten().map(|d| &d.object);
+
+    for object in definitions.chain(&scene.objects) {
+        walk(object, &mut |o| match &o.id {
+            Some(id) if ids.contains(id) => {
+                Err(format!("Duplicate group ID {}", id))
+            }
+            Some(id) => {
+                ids.push(id.clone());
+                Ok(())
+            }
+            None => Ok(()),
+        })?;
+    }
+
+    Ok(ids)
 }
 
 impl Hittable for Scene {
@@ -348,6 +1974,9 @@ impl Hittable for Scene {
 /*              AUTO GENERATED FROM SCHEMA              */
 /********************************************************/
 
+type SchemaNumber = Animated<f32>;
+type SchemaAnimatedVector = Animated<SchemaVector>;
+
 #[derive(Debug, Serialize, Deserialize)]
 struct SchemaVector {
     x: f32,
@@ -358,57 +1987,195 @@ struct SchemaVector {
 #[derive(Debug, Serialize, Deserialize)]
 struct SchemaTexture {
     name: String,
-    values: Option<SchemaVector>,
+    values: Option<SchemaAnimatedVector>,
+    temperature: Option<SchemaNumber>,
+    scale: Option<SchemaNumber>,
 }
 
 #[derive(Debug, Serialize, Deserialize)]
 struct SchemaMaterial {
     name: String,
-    albedo: Option<SchemaVector>,
-    ref_idx: Option<f32>,
-    fuzz: Option<f32>,
+    albedo: Option<SchemaAnimatedVector>,
+    ref_idx: Option<SchemaNumber>,
+    fuzz: Option<SchemaNumber>,
     texture: Option<SchemaTexture>,
+    anisotropy: Option<SchemaNumber>,
+    emission: Option<SchemaTexture>,
 }
 
 #[derive(Debug, Serialize, Deserialize)]
 struct SchemaObject {
     name: String,
-    center: Option<SchemaVector>,
-    center2: Option<SchemaVector>,
-    radius: Option<f32>,
+    center: Option<SchemaAnimatedVector>,
+    center2: Option<SchemaAnimatedVector>,
+    radius: Option<SchemaNumber>,
+    inner_radius: Option<SchemaNumber>,
+    major_radius: Option<SchemaNumber>,
+    minor_radius: Option<SchemaNumber>,
+    base: Option<SchemaAnimatedVector>,
+    height: Option<SchemaNumber>,
+    capped: Option<bool>,
+    density: Option<SchemaNumber>,
+    temperature_file: Option<String>,
+    temperature_scale: Option<SchemaNumber>,
     t0: Option<f32>,
     t1: Option<f32>,
-    a0: Option<f32>,
-    a1: Option<f32>,
-    b0: Option<f32>,
-    b1: Option<f32>,
-    k: Option<f32>,
+    a0: Option<SchemaNumber>,
+    a1: Option<SchemaNumber>,
+    b0: Option<SchemaNumber>,
+    b1: Option<SchemaNumber>,
+    k: Option<SchemaNumber>,
     flip: Option<bool>,
     plane: Option<String>,
-    angle: Option<f32>,
-    offset: Option<SchemaVector>,
+    angle: Option<SchemaNumber>,
+    angle2: Option<SchemaNumber>,
+    offset: Option<SchemaAnimatedVector>,
+    offset2: Option<SchemaAnimatedVector>,
     axis: Option<String>,
-    p0: Option<SchemaVector>,
-    p1: Option<SchemaVector>,
+    p0: Option<SchemaAnimatedVector>,
+    p1: Option<SchemaAnimatedVector>,
+    vertices: Option<Vec<SchemaAnimatedVector>>,
+    corner: Option<SchemaAnimatedVector>,
+    size: Option<SchemaAnimatedVector>,
+    point: Option<SchemaAnimatedVector>,
+    normal: Option<SchemaAnimatedVector>,
+    u: Option<SchemaAnimatedVector>,
+    v: Option<SchemaAnimatedVector>,
     material: Option<SchemaMaterial>,
     items: Option<Vec<Box<SchemaObject>>>,
     inner: Option<Box<SchemaObject>>,
+    transforms: Option<Vec<SchemaTransform>>,
+    definition: Option<String>,
+    id: Option<String>,
+    file: Option<String>,
+    groups: Option<Vec<String>>,
+    format: Option<String>,
+    root: Option<SchemaSdf>,
+    epsilon: Option<f32>,
+    max_steps: Option<usize>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct SchemaSdf {
+    name: String,
+    radius: Option<f32>,
+    size: Option<SchemaVector>,
+    rounding: Option<f32>,
+    a: Option<SchemaVector>,
+    b: Option<SchemaVector>,
+    major_radius: Option<f32>,
+    minor_radius: Option<f32>,
+    height: Option<f32>,
+    smoothness: Option<f32>,
+    offset: Option<SchemaVector>,
+    factor: Option<f32>,
+    angle: Option<f32>,
+    period: Option<SchemaVector>,
+    limit: Option<SchemaVector>,
+    items: Option<Vec<SchemaSdf>>,
+    inner: Option<Box<SchemaSdf>>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct SchemaTrack {
+    target: String,
+    keyframes: Vec<Keyframe<Vec<SchemaTransform>>>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct SchemaDefinition {
+    name: String,
+    object: Box<SchemaObject>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct SchemaRotation {
+    axis: SchemaVector,
+    angle: SchemaNumber,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct SchemaTransform {
+    translate: Option<SchemaAnimatedVector>,
+    rotate: Option<SchemaRotation>,
+    scale: Option<SchemaAnimatedVector>,
+    matrix: Option<Vec<f32>>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct SchemaSpline {
+    kind: SplineKind,
+    points: Vec<SchemaVector>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct SchemaCameraPath {
+    path: SchemaSpline,
+    target: Option<SchemaSpline>,
+    look_along_tangent: Option<bool>,
+    start: Option<f32>,
+    duration: f32,
 }
 
 #[derive(Debug, Serialize, Deserialize)]
 struct SchemaCamera {
-    look_from: SchemaVector,
-    look_at: SchemaVector,
-    vup: SchemaVector,
-    vfov: f32,
-    aperture: Option<f32>,
-    focus_dist: Option<f32>,
+    look_from: Option<SchemaAnimatedVector>,
+    look_at: Option<SchemaAnimatedVector>,
+    vup: SchemaAnimatedVector,
+    vfov: SchemaNumber,
+    aperture: Option<SchemaNumber>,
+    focus_dist: Option<SchemaNumber>,
+    focus_target: Option<SchemaAnimatedVector>,
+    path: Option<SchemaCameraPath>,
     t0: Option<f32>,
     t1: Option<f32>,
 }
 
+#[derive(Debug, Serialize, Deserialize)]
+struct SchemaPlane {
+    point: SchemaVector,
+    normal: SchemaVector,
+    restitution: Option<f32>,
+    friction: Option<f32>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct SchemaBody {
+    center: SchemaVector,
+    velocity: Option<SchemaVector>,
+    radius: f32,
+    mass: Option<f32>,
+    restitution: Option<f32>,
+    friction: Option<f32>,
+    material: SchemaMaterial,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct SchemaSimulation {
+    gravity: Option<SchemaVector>,
+    start: Option<f32>,
+    duration: f32,
+    dt: Option<f32>,
+    planes: Option<Vec<SchemaPlane>>,
+    bodies: Vec<SchemaBody>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct SchemaAtmosphere {
+    density: SchemaNumber,
+    albedo: Option<SchemaAnimatedVector>,
+    anisotropy: Option<SchemaNumber>,
+    falloff: Option<SchemaNumber>,
+    base_height: Option<SchemaNumber>,
+}
+
 #[derive(Debug, Serialize, Deserialize)]
 struct SchemaScene {
+    definitions: Option<Vec<SchemaDefinition>>,
     objects: Vec<Box<SchemaObject>>,
     camera: Option<SchemaCamera>,
+    atmosphere: Option<SchemaAtmosphere>,
+    background: Option<SchemaAnimatedVector>,
+    simulation: Option<SchemaSimulation>,
+    tracks: Option<Vec<SchemaTrack>>,
 }
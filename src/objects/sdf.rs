@@ -0,0 +1,413 @@
+//! Shapes described by signed distance functions, which give the distance
+//! from any point to the nearest point on the surface and are negative
+//! inside. Simple shapes can be combined, smoothly blended, repeated and
+//! twisted without ever being turned into triangles, and are rendered by
+//! sphere tracing: stepping along the ray by the distance to the surface,
+//! which can never step through it.
+
+use super::{HitRecord, Hittable};
+use crate::{aabb::Aabb, materials::Material, onb::Onb, Ray, Vector};
+
+/// A node in the tree describing a signed distance function. Shapes are
+/// centered on the origin, and are moved by wrapping them in a
+/// `Translate`.
+#[derive(Debug)]
+pub enum SdfNode {
+    Sphere {
+        radius: f32,
+    },
+    /// A box with edges rounded off by `rounding`, which doesn't change
+    /// its size
+    Box {
+        half_size: Vector,
+        rounding: f32,
+    },
+    /// A line segment from `a` to `b` with a radius around it
+    Capsule {
+        a: Vector,
+        b: Vector,
+        radius: f32,
+    },
+    /// A torus lying in the XZ plane
+    Torus {
+        major_radius: f32,
+        minor_radius: f32,
+    },
+    /// A capped cylinder along the Y axis
+    Cylinder {
+        radius: f32,
+        half_height: f32,
+    },
+    Union(Vec<SdfNode>),
+    Intersection(Vec<SdfNode>),
+    /// The first shape with every other shape cut out of it
+    Difference(Vec<SdfNode>),
+    /// A union which blends shapes together where they're closer than
+    /// `smoothness`
+    SmoothUnion {
+        items: Vec<SdfNode>,
+        smoothness: f32,
+    },
+    Translate {
+        offset: Vector,
+        inner: Box<SdfNode>,
+    },
+    /// Scales the shape up by a positive factor
+    Scale {
+        factor: f32,
+        inner: Box<SdfNode>,
+    },
+    /// Rotates the shape about the Y axis by `rate` radians per unit of
+    /// height
+    Twist {
+        rate: f32,
+        inner: Box<SdfNode>,
+    },
+    /// Repeats the shape every `period` along each axis, or not at all
+    /// along axes where the period is zero. With a `limit` there are only
+    /// that many copies on each side of the original. The shape has to fit
+    /// inside a single period.
+    Repeat {
+        period: Vector,
+        limit: Option<Vector>,
+        inner: Box<SdfNode>,
+    },
+}
+
+impl SdfNode {
+    pub fn distance(&self, p: Vector) -> f32 {
+        match self {
+            SdfNode::Sphere { radius } => p.length() - radius,
+            SdfNode::Box {
+                half_size,
+                rounding,
+            } => {
+                let q = zip(p, *half_size, |p, h| p.abs() - h + rounding);
+                let outside = zip(q, Vector::zeros(), f32::max).length();
+                let inside = f32::min(q.x.max(q.y).max(q.z), 0.0);
+
+                outside + inside - rounding
+            }
+            SdfNode::Capsule { a, b, radius } => {
+                let pa = p - *a;
+                let ba = *b - *a;
+                let h = (Vector::dot(pa, ba) / Vector::dot(ba, ba))
+                    .max(0.0)
+                    .min(1.0);
+
+                (pa - ba * h).length() - radius
+            }
+            SdfNode::Torus {
+                major_radius,
+                minor_radius,
+            } => {
+                let x = p.x.hypot(p.z) - major_radius;
+                x.hypot(p.y) - minor_radius
+            }
+            SdfNode::Cylinder {
+                radius,
+                half_height,
+            } => {
+                let x = p.x.hypot(p.z) - radius;
+                let y = p.y.abs() - half_height;
+
+                f32::min(x.max(y), 0.0) + x.max(0.0).hypot(y.max(0.0))
+            }
+            SdfNode::Union(items) => items
+                .iter()
+                .map(|item| item.distance(p))
+                .fold(f32::INFINITY, f32::min),
+            SdfNode::Intersection(items) => items
+                .iter()
+                .map(|item| item.distance(p))
+                .fold(f32::NEG_INFINITY, f32::max),
+            SdfNode::Difference(items) => items[1..]
+                .iter()
+                .map(|item| -item.distance(p))
+                .fold(items[0].distance(p), f32::max),
+            SdfNode::SmoothUnion { items, smoothness } => {
+                let k = *smoothness;
+                let mut distances = items.iter().map(|item| item.distance(p));
+                let first = distances.next().unwrap();
+
+                // The polynomial smooth minimum from Inigo Quilez
+                distances.fold(first, |a, b| {
+                    if k <= 0.0 {
+                        return a.min(b);
+                    }
+
+                    let h = (0.5 + 0.5 * (b - a) / k).max(0.0).min(1.0);
+                    b + (a - b) * h - k * h * (1.0 - h)
+                })
+            }
+            SdfNode::Translate { offset, inner } => inner.distance(p - *offset),
+            SdfNode::Scale { factor, inner } => {
+                inner.distance(p / *factor) * factor
+            }
+            SdfNode::Twist { rate, inner } => {
+                let (sin, cos) = (-rate * p.y).sin_cos();
+                inner.distance(Vector::new(
+                    cos * p.x - sin * p.z,
+                    p.y,
+                    sin * p.x + cos * p.z,
+                ))
+            }
+            SdfNode::Repeat {
+                period,
+                limit,
+                inner,
+            } => {
+                let mut q = p;
+                for a in 0..3 {
+                    if period[a] == 0.0 {
+                        continue;
+                    }
+
+                    let mut cell = (p[a] / period[a]).round();
+                    if let Some(limit) = limit {
+                        cell = cell.max(-limit[a]).min(limit[a]);
+                    }
+                    q[a] = p[a] - period[a] * cell;
+                }
+
+                inner.distance(q)
+            }
+        }
+    }
+
+    /// A box containing the shape, which doesn't exist for shapes that
+    /// repeat forever
+    pub fn bounds(&self) -> Option<Aabb> {
+        let symmetric = |half_size: Vector| Aabb::new(-half_size, half_size);
+
+        match self {
+            SdfNode::Sphere { radius } => {
+                Some(symmetric(Vector::ones() * *radius))
+            }
+            SdfNode::Box { half_size, .. } => Some(symmetric(*half_size)),
+            SdfNode::Capsule { a, b, radius } => {
+                let padding = Vector::ones() * *radius;
+                Some(Aabb::new(
+                    zip(*a, *b, f32::min) - padding,
+                    zip(*a, *b, f32::max) + padding,
+                ))
+            }
+            SdfNode::Torus {
+                major_radius,
+                minor_radius,
+            } => {
+                let outer = major_radius + minor_radius;
+                Some(symmetric(Vector::new(outer, *minor_radius, outer)))
+            }
+            SdfNode::Cylinder {
+                radius,
+                half_height,
+            } => Some(symmetric(Vector::new(*radius, *half_height, *radius))),
+            SdfNode::Union(items) => surrounding(items),
+            SdfNode::Intersection(items) => {
+                let mut bounds = items.iter().filter_map(SdfNode::bounds);
+                let first = bounds.next()?;
+
+                Some(bounds.fold(first, |a, b| {
+                    Aabb::new(
+                        zip(a.min, b.min, f32::max),
+                        zip(a.max, b.max, f32::min),
+                    )
+                }))
+            }
+            SdfNode::Difference(items) => items[0].bounds(),
+            SdfNode::SmoothUnion { items, smoothness } => {
+                // Blending can fill in gaps a quarter of the smoothness
+                // across
+                let padding = Vector::ones() * (smoothness / 4.0);
+                surrounding(items)
+                    .map(|b| Aabb::new(b.min - padding, b.max + padding))
+            }
+            SdfNode::Translate { offset, inner } => inner
+                .bounds()
+                .map(|b| Aabb::new(b.min + *offset, b.max + *offset)),
+            SdfNode::Scale { factor, inner } => inner
+                .bounds()
+                .map(|b| Aabb::new(b.min * *factor, b.max * *factor)),
+            SdfNode::Twist { inner, .. } => {
+                let b = inner.bounds()?;
+                let radius = horizontal_radius(&b);
+
+                Some(Aabb::new(
+                    Vector::new(-radius, b.min.y, -radius),
+                    Vector::new(radius, b.max.y, radius),
+                ))
+            }
+            SdfNode::Repeat {
+                period,
+                limit,
+                inner,
+            } => {
+                let b = inner.bounds()?;
+                let mut extent = Vector::zeros();
+                for a in 0..3 {
+                    if period[a] != 0.0 {
+                        extent[a] = period[a].abs() * limit.as_ref()?[a];
+                    }
+                }
+
+                Some(Aabb::new(b.min - extent, b.max + extent))
+            }
+        }
+    }
+
+    /// How much faster the function can change than the distance to the
+    /// surface. Twisting stretches space, so the function's value is
+    /// divided by this to get a safe distance to step.
+    fn lipschitz(&self) -> f32 {
+        let children = |items: &[SdfNode]| {
+            items.iter().map(SdfNode::lipschitz).fold(1.0, f32::max)
+        };
+
+        match self {
+            SdfNode::Union(items)
+            | SdfNode::Intersection(items)
+            | SdfNode::Difference(items)
+            | SdfNode::SmoothUnion { items, .. } => children(items),
+            SdfNode::Translate { inner, .. }
+            | SdfNode::Scale { inner, .. }
+            | SdfNode::Repeat { inner, .. } => inner.lipschitz(),
+            SdfNode::Twist { rate, inner } => {
+                let radius =
+                    inner.bounds().map_or(0.0, |b| horizontal_radius(&b));
+                inner.lipschitz() * (rate * radius).hypot(1.0)
+            }
+            _ => 1.0,
+        }
+    }
+}
+
+/// Combines the matching components of two vectors
+fn zip<F>(a: Vector, b: Vector, f: F) -> Vector
+where
+    F: Fn(f32, f32) -> f32,
+{
+    Vector::new(f(a.x, b.x), f(a.y, b.y), f(a.z, b.z))
+}
+
+fn surrounding(items: &[SdfNode]) -> Option<Aabb> {
+    let mut bounds = items.iter().map(SdfNode::bounds);
+    let first = bounds.next()??;
+
+    bounds.try_fold(first, |a, b| Some(Aabb::surrounding_box(a, b?)))
+}
+
+/// The furthest any point in the box is from the Y axis
+fn horizontal_radius(b: &Aabb) -> f32 {
+    let x = f32::max(b.min.x.abs(), b.max.x.abs());
+    let z = f32::max(b.min.z.abs(), b.max.z.abs());
+
+    x.hypot(z)
+}
+
+#[derive(Debug)]
+pub struct Sdf {
+    root: SdfNode,
+    bounds: Aabb,
+    lipschitz: f32,
+    /// Points closer to the surface than this count as hits
+    epsilon: f32,
+    /// The most steps taken along a ray before giving up
+    max_steps: usize,
+    material: Box<dyn Material>,
+}
+
+impl Sdf {
+    /// Creates an SDF shape within `bounds`, which defaults to the bounds
+    /// of the tree. Returns None for shapes that repeat forever without
+    /// any bounds.
+    pub fn new(
+        root: SdfNode,
+        bounds: Option<Aabb>,
+        epsilon: f32,
+        max_steps: usize,
+        material: Box<dyn Material>,
+    ) -> Option<Self> {
+        let bounds = bounds.or_else(|| root.bounds())?;
+
+        Some(Self {
+            lipschitz: root.lipschitz(),
+            root,
+            bounds,
+            epsilon,
+            max_steps,
+            material,
+        })
+    }
+
+    /// The normal is the direction in which the distance grows fastest,
+    /// found by central differences
+    fn normal(&self, p: Vector) -> Vector {
+        let mut gradient = Vector::zeros();
+        for a in 0..3 {
+            let mut offset = Vector::zeros();
+            offset[a] = self.epsilon;
+
+            gradient[a] =
+                self.root.distance(p + offset) - self.root.distance(p - offset);
+        }
+
+        gradient.normalized_exact()
+    }
+}
+
+impl Hittable for Sdf {
+    fn hit(
+        &self,
+        r: Ray,
+        t_min: f32,
+        t_max: f32,
+    ) -> Option<(HitRecord, &Box<dyn Material>)> {
+        // Only the part of the ray inside the bounds is traced
+        let (mut t, end) = self.bounds.clip(r, t_min, t_max);
+
+        let length = r.dir().length();
+
+        // Rays leaving the surface start close enough to it to count as a
+        // hit, so they step out of that band before looking for one
+        let mut leaving = true;
+
+        for _ in 0..self.max_steps {
+            if !(t <= end) {
+                return None;
+            }
+
+            // Rays starting inside step by the distance to the surface
+            // just the same
+            let p = r.point_at_parameter(t);
+            let distance = self.root.distance(p).abs() / self.lipschitz;
+            if distance < self.epsilon && leaving {
+                t += self.epsilon / length;
+                continue;
+            }
+            leaving = false;
+
+            if distance < self.epsilon {
+                let normal = self.normal(p);
+                let uvw = Onb::build_from_w(normal);
+                let hit_record = HitRecord::new(
+                    r,
+                    t,
+                    (0.0, 0.0),
+                    normal,
+                    (uvw.u(), uvw.v()),
+                );
+
+                return Some((hit_record, &self.material));
+            }
+
+            t += distance / length;
+        }
+
+        None
+    }
+
+    fn bounding_box(&self, _t0: f32, _t1: f32) -> Option<Aabb> {
+        Some(self.bounds)
+    }
+}
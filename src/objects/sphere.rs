@@ -2,7 +2,11 @@
 
 use super::{HitRecord, Hittable};
 use crate::{
-    aabb::Aabb, materials::Material, onb::Onb, util::sphere_uv, Ray, Vector,
+    aabb::Aabb,
+    materials::Material,
+    onb::Onb,
+    util::{sphere_partials, sphere_uv},
+    Ray, Vector,
 };
 
 use rand::prelude::*;
@@ -51,18 +55,16 @@ impl Hittable for Sphere {
             }
 
             if q_eq < t_max && q_eq > t_min {
-                let point_at_parameter = r.point_at_parameter(q_eq);
-                let (u, v) =
-                    sphere_uv((point_at_parameter - self.center) / self.radius);
+                let offset = r.point_at_parameter(q_eq) - self.center;
+                let normal = offset / self.radius;
                 return Some((
-                    HitRecord {
-                        u,
-                        v,
-                        t: q_eq,
-                        p: point_at_parameter,
-                        normal: (point_at_parameter - self.center)
-                            / self.radius,
-                    },
+                    HitRecord::new(
+                        r,
+                        q_eq,
+                        sphere_uv(normal),
+                        normal,
+                        sphere_partials(offset),
+                    ),
                     &self.material,
                 ));
             }
@@ -98,6 +100,17 @@ impl Hittable for Sphere {
         let uvw = Onb::build_from_w(dir);
         uvw.local(random_to_sphere(self.radius, dist_squared))
     }
+
+    /// Returns the point on the near side of the sphere that the sampled
+    /// direction hits
+    fn random_point(&self, o: Vector) -> Vector {
+        let v = self.random(o);
+
+        match self.hit(Ray::new(o, v, 0.0), 0.001, f32::MAX) {
+            Some((hit_record, _)) => hit_record.p,
+            None => self.center,
+        }
+    }
 }
 
 impl Sphere {
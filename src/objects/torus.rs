@@ -0,0 +1,120 @@
+//! A torus lying flat in the XZ plane, like a ring placed on a table
+
+use super::{HitRecord, Hittable};
+use crate::{
+    aabb::Aabb,
+    materials::Material,
+    util::{azimuth, solve_quartic},
+    Ray, Vector,
+};
+
+use std::f32::consts::PI;
+
+#[derive(Debug)]
+pub struct Torus {
+    center: Vector,
+    /// The distance from the center to the middle of the tube
+    major_radius: f32,
+    /// The radius of the tube
+    minor_radius: f32,
+    material: Box<dyn Material>,
+}
+
+impl Hittable for Torus {
+    fn hit(
+        &self,
+        r: Ray,
+        t_min: f32,
+        t_max: f32,
+    ) -> Option<(HitRecord, &Box<dyn Material>)> {
+        let length = r.dir().length() as f64;
+        let d = r.dir() / r.dir().length();
+        let o = r.origin() - self.center;
+
+        // The quartic's roots lose precision the further the origin is from
+        // the torus, so the ray is solved from the nearest point where it
+        // could enter the torus' bounding sphere instead
+        let start = f32::max(
+            -Vector::dot(o, d) - (self.major_radius + self.minor_radius),
+            0.0,
+        );
+        let o = o + start * d;
+
+        let (ox, oy, oz) = (o.x as f64, o.y as f64, o.z as f64);
+        let (dx, dy, dz) = (d.x as f64, d.y as f64, d.z as f64);
+        let major = self.major_radius as f64 * self.major_radius as f64;
+        let minor = self.minor_radius as f64 * self.minor_radius as f64;
+
+        // Substituting the ray into (|p|² + R² - r²)² = 4R²(x² + z²), where
+        // the direction has unit length
+        let n = ox * dx + oy * dy + oz * dz;
+        let k = ox * ox + oy * oy + oz * oz + major - minor;
+        let roots = solve_quartic(
+            1.0,
+            4.0 * n,
+            4.0 * n * n + 2.0 * k - 4.0 * major * (dx * dx + dz * dz),
+            4.0 * n * k - 8.0 * major * (ox * dx + oz * dz),
+            k * k - 4.0 * major * (ox * ox + oz * oz),
+        );
+
+        let t = roots
+            .into_iter()
+            .map(|root| ((root + start as f64) / length) as f32)
+            .find(|&t| t > t_min && t < t_max)?;
+
+        let p = r.point_at_parameter(t) - self.center;
+        let phi = azimuth(p.x, p.z);
+        let (sin_phi, cos_phi) = phi.sin_cos();
+
+        // The point on the circle through the middle of the tube closest to
+        // the hit
+        let ring = self.major_radius * Vector::new(cos_phi, 0.0, sin_phi);
+        let normal = p - ring;
+        let normal = normal.normalized_exact();
+
+        let radial = Vector::dot(normal, Vector::new(cos_phi, 0.0, sin_phi));
+        let theta = azimuth(radial, normal.y);
+        let (sin_theta, cos_theta) = theta.sin_cos();
+
+        let u = phi / (2.0 * PI);
+        let v = theta / (2.0 * PI);
+
+        let dpdu = 2.0 * PI * Vector::new(-p.z, 0.0, p.x);
+        let dpdv = 2.0
+            * PI
+            * self.minor_radius
+            * Vector::new(
+                -sin_theta * cos_phi,
+                cos_theta,
+                -sin_theta * sin_phi,
+            );
+
+        Some((
+            HitRecord::new(r, t, (u, v), normal, (dpdu, dpdv)),
+            &self.material,
+        ))
+    }
+
+    fn bounding_box(&self, _t0: f32, _t1: f32) -> Option<Aabb> {
+        let outer = self.major_radius + self.minor_radius;
+        let extent = Vector::new(outer, self.minor_radius, outer);
+
+        Some(Aabb::new(self.center - extent, self.center + extent))
+    }
+}
+
+impl Torus {
+    pub fn new(
+        center: Vector,
+        major_radius: f32,
+        minor_radius: f32,
+        material: Box<dyn Material>,
+    ) -> Self {
+        Self {
+            center,
+            major_radius,
+            minor_radius,
+            material,
+        }
+    }
+}
@@ -0,0 +1,313 @@
+use super::{HitRecord, Hittable};
+use crate::{
+    aabb::Aabb, animation::Lerp, materials::Material, matrix::Matrix4,
+    quaternion::Quaternion, Ray, Vector,
+};
+
+/// The number of steps each segment of a moving transformation is split
+/// into when computing the bounding box of the whole motion
+const MOTION_BOX_STEPS: usize = 16;
+
+/// Applies an arbitrary affine transformation to an object. Rays are moved
+/// into the object's space with the inverse matrix, so a chain of
+/// rotations, scales and translations costs the same as a single one.
+#[derive(Debug)]
+pub struct Transform {
+    hittable: Box<dyn Hittable>,
+    matrix: Matrix4,
+    inverse: Matrix4,
+    /// The inverse transpose, used to transform normals
+    normal_matrix: Matrix4,
+    motion: Option<TransformMotion>,
+}
+
+/// The transformations of a Transform object that moves during the time
+/// interval [t0, t1], given at evenly spaced times. The object moves
+/// between each pair of poses without any change in speed.
+#[derive(Debug)]
+struct TransformMotion {
+    poses: Vec<Pose>,
+    t0: f32,
+    t1: f32,
+}
+
+/// A transformation split into a translation, a rotation and the scaling
+/// and shearing that's left, which is applied first. The translation is
+/// interpolated linearly and the rotation by slerp, so objects turn at a
+/// steady rate instead of shrinking partway through a rotation.
+#[derive(Debug, Copy, Clone)]
+struct Pose {
+    offset: Vector,
+    rotation: Quaternion,
+    /// An upper triangular matrix
+    rest: Matrix4,
+}
+
+impl Hittable for Transform {
+    fn hit(
+        &self,
+        r: Ray,
+        t_min: f32,
+        t_max: f32,
+    ) -> Option<(HitRecord, &Box<dyn Material>)> {
+        let (matrix, inverse) = match &self.motion {
+            Some(motion) => {
+                let matrix = motion.pose(r.time()).matrix();
+                let inverse = matrix
+                    .inverse()
+                    .expect("Poses are checked when the motion is created");
+                (matrix, inverse)
+            }
+            None => (self.matrix, self.inverse),
+        };
+        let normal_matrix = match &self.motion {
+            Some(_) => inverse.transpose(),
+            None => self.normal_matrix,
+        };
+
+        // The direction isn't normalized, so t is the same in both spaces
+        let object_r = Ray::new(
+            inverse.transform_point(r.origin()),
+            inverse.transform_vector(r.dir()),
+            r.time(),
+        );
+
+        self.hittable.hit(object_r, t_min, t_max).map(
+            |(mut hit_record, material)| {
+                let normal = |n: Vector| {
+                    normal_matrix.transform_vector(n).normalized_exact()
+                };
+
+                hit_record.p = matrix.transform_point(hit_record.p);
+                hit_record.geometric_normal =
+                    normal(hit_record.geometric_normal);
+                hit_record.shading_normal = normal(hit_record.shading_normal);
+                hit_record.dpdu = matrix.transform_vector(hit_record.dpdu);
+                hit_record.dpdv = matrix.transform_vector(hit_record.dpdv);
+                (hit_record, material)
+            },
+        )
+    }
+
+    fn bounding_box(&self, t0: f32, t1: f32) -> Option<Aabb> {
+        let bbox = self.hittable.bounding_box(t0, t1)?;
+
+        match &self.motion {
+            Some(motion) => Some(motion.bounding_box(bbox)),
+            None => Some(transformed_box(bbox, &self.matrix)),
+        }
+    }
+
+    /// Lights are sampled in the object's space. Only rotations and
+    /// translations keep the solid angles of directions the same, which
+    /// are all that turning a scene's lights for a turntable uses.
+    fn pdf_value(&self, o: Vector, v: Vector) -> f32 {
+        self.hittable.pdf_value(
+            self.inverse.transform_point(o),
+            self.inverse.transform_vector(v),
+        )
+    }
+
+    fn random(&self, o: Vector) -> Vector {
+        let v = self.hittable.random(self.inverse.transform_point(o));
+        self.matrix.transform_vector(v)
+    }
+
+    fn random_point(&self, o: Vector) -> Vector {
+        let p = self.hittable.random_point(self.inverse.transform_point(o));
+        self.matrix.transform_point(p)
+    }
+}
+
+impl Transform {
+    /// Creates a Transform object, returning None if the matrix can't be
+    /// inverted (e.g. a scale of 0 along some axis)
+    pub fn new(hittable: Box<dyn Hittable>, matrix: Matrix4) -> Option<Self> {
+        let inverse = matrix.inverse()?;
+
+        Some(Self {
+            hittable,
+            matrix,
+            inverse,
+            normal_matrix: inverse.transpose(),
+            motion: None,
+        })
+    }
+
+    /// Creates a Transform object which moves through the given matrices
+    /// at a steady rate over the time interval [t0, t1]. Neighbouring
+    /// matrices should rotate the object by less than 180 degrees from
+    /// each other, since each rotation takes the shortest path. Returns
+    /// None if any matrix can't be inverted, or if the object would be
+    /// mirrored partway through the motion.
+    pub fn new_moving(
+        hittable: Box<dyn Hittable>,
+        matrices: &[Matrix4],
+        t0: f32,
+        t1: f32,
+    ) -> Option<Self> {
+        let poses = matrices
+            .iter()
+            .map(Pose::new)
+            .collect::<Option<Vec<Pose>>>()?;
+
+        // The scaling along the last axis only changes sign when the
+        // object passes through being flat
+        let mirrored = |pose: &Pose| pose.rest.m[2][2] < 0.0;
+        if poses.iter().any(|p| mirrored(p) != mirrored(&poses[0])) {
+            return None;
+        }
+
+        let mut ret = Self::new(hittable, matrices[0])?;
+        ret.motion = Some(TransformMotion { poses, t0, t1 });
+        Some(ret)
+    }
+}
+
+impl TransformMotion {
+    fn pose(&self, time: f32) -> Pose {
+        if self.poses.len() == 1 || self.t1 <= self.t0 {
+            return self.poses[0];
+        }
+
+        let segments = self.poses.len() - 1;
+        let s = (time - self.t0) / (self.t1 - self.t0) * segments as f32;
+        let i = (s.max(0.0) as usize).min(segments - 1);
+
+        Pose::lerp(self.poses[i], self.poses[i + 1], (s - i as f32).min(1.0))
+    }
+
+    /// Computes a bounding box which contains the object throughout its
+    /// motion. Each segment is sampled at several steps and the union of
+    /// those boxes is padded to account for the arcs that the corners
+    /// trace out between the steps.
+    fn bounding_box(&self, bbox: Aabb) -> Aabb {
+        let mut ret = transformed_box(bbox, &self.poses[0].matrix());
+        let mut radius: f32 = 0.0;
+        let mut max_step: f32 = 0.0;
+
+        for pair in self.poses.windows(2) {
+            let mut previous = pair[0].rotation;
+            for i in 1..=MOTION_BOX_STEPS {
+                let s = i as f32 / MOTION_BOX_STEPS as f32;
+                let pose = Pose::lerp(pair[0], pair[1], s);
+
+                let matrix = pose.matrix();
+                ret =
+                    Aabb::surrounding_box(ret, transformed_box(bbox, &matrix));
+
+                max_step = max_step
+                    .max(Quaternion::angle_between(previous, pose.rotation));
+                previous = pose.rotation;
+            }
+        }
+
+        // The rotation turns the scaled box about the translated origin,
+        // so its furthest corner from there bounds how far the arcs can
+        // bulge past the chords between steps
+        for pose in &self.poses {
+            let scaled = transformed_box(bbox, &pose.rest);
+            for &x in &[scaled.min.x, scaled.max.x] {
+                for &y in &[scaled.min.y, scaled.max.y] {
+                    for &z in &[scaled.min.z, scaled.max.z] {
+                        radius = radius.max(Vector::new(x, y, z).length());
+                    }
+                }
+            }
+        }
+
+        let pad = radius * (1.0 - f32::cos(0.5 * max_step));
+        let pad = Vector::new(pad, pad, pad);
+
+        Aabb::new(ret.min - pad, ret.max + pad)
+    }
+}
+
+impl Pose {
+    /// Splits the matrix into a rotation and an upper triangular matrix
+    /// with Gram-Schmidt on its columns, returning None if it can't be
+    /// inverted
+    fn new(matrix: &Matrix4) -> Option<Self> {
+        let m = &matrix.m;
+        let column = |c: usize| Vector::new(m[0][c], m[1][c], m[2][c]);
+        let (c0, c1, c2) = (column(0), column(1), column(2));
+
+        let mut rest = Matrix4::identity();
+        let r = &mut rest.m;
+
+        r[0][0] = c0.length();
+        let q0 = c0 / r[0][0];
+
+        r[0][1] = Vector::dot(q0, c1);
+        let c1 = c1 - r[0][1] * q0;
+        r[1][1] = c1.length();
+        let q1 = c1 / r[1][1];
+
+        r[0][2] = Vector::dot(q0, c2);
+        r[1][2] = Vector::dot(q1, c2);
+        let c2 = c2 - r[0][2] * q0 - r[1][2] * q1;
+        r[2][2] = c2.length();
+        let mut q2 = c2 / r[2][2];
+
+        if r[0][0] < 1e-6 || r[1][1] < 1e-6 || r[2][2] < 1e-6 {
+            return None;
+        }
+
+        // A mirroring matrix is split into a rotation and a mirror
+        // along the last axis
+        if Vector::dot(Vector::cross(q0, q1), q2) < 0.0 {
+            q2 = -q2;
+            r[2][2] = -r[2][2];
+        }
+
+        let mut rotation = Matrix4::identity();
+        for (c, q) in [q0, q1, q2].iter().enumerate() {
+            for row in 0..3 {
+                rotation.m[row][c] = q[row];
+            }
+        }
+
+        Some(Self {
+            offset: column(3),
+            rotation: Quaternion::from_matrix(&rotation),
+            rest,
+        })
+    }
+
+    fn matrix(&self) -> Matrix4 {
+        Matrix4::translation(self.offset) * self.rotation.matrix() * self.rest
+    }
+
+    fn lerp(a: Self, b: Self, t: f32) -> Self {
+        Self {
+            offset: Lerp::lerp(a.offset, b.offset, t),
+            rotation: Quaternion::slerp(a.rotation, b.rotation, t),
+            rest: Lerp::lerp(a.rest, b.rest, t),
+        }
+    }
+}
+
+/// Returns the bounding box of the given box after transforming it
+fn transformed_box(bbox: Aabb, matrix: &Matrix4) -> Aabb {
+    // Each axis of the new box is a sum of terms from the matrix row,
+    // and each term is smallest (or largest) at one end of the old box
+    let m = &matrix.m;
+
+    let mut min = Vector::zeros();
+    let mut max = Vector::zeros();
+
+    for i in 0..3 {
+        min[i] = m[i][3];
+        max[i] = m[i][3];
+
+        for j in 0..3 {
+            let a = m[i][j] * bbox.min[j];
+            let b = m[i][j] * bbox.max[j];
+
+            min[i] += f32::min(a, b);
+            max[i] += f32::max(a, b);
+        }
+    }
+
+    Aabb::new(min, max)
+}
@@ -3,8 +3,11 @@ use crate::{aabb::Aabb, materials::Material, Ray, Vector};
 
 #[derive(Debug)]
 pub struct Translate {
-    pub hittable: Box<dyn Hittable>,
-    pub offset: Vector,
+    hittable: Box<dyn Hittable>,
+    offset0: Vector,
+    offset1: Vector,
+    t0: f32,
+    t1: f32,
 }
 
 impl Hittable for Translate {
@@ -14,21 +17,64 @@ impl Hittable for Translate {
         t_min: f32,
         t_max: f32,
     ) -> Option<(HitRecord, &Box<dyn Material>)> {
-        let moved_r = Ray::new(r.origin() - self.offset, r.dir(), r.time());
+        let offset = self.offset(r.time());
+        let moved_r = Ray::new(r.origin() - offset, r.dir(), r.time());
 
         self.hittable.hit(moved_r, t_min, t_max).map(
             |(mut hit_record, material)| {
-                hit_record.p += self.offset;
+                hit_record.p += offset;
                 (hit_record, material)
             },
         )
     }
 
     fn bounding_box(&self, t0: f32, t1: f32) -> Option<Aabb> {
-        self.hittable.bounding_box(t0, t1).map(|mut aabb| {
-            aabb.min += self.offset;
-            aabb.max += self.offset;
-            aabb
-        })
+        // The offset moves linearly, so the boxes at either end of the
+        // interval contain the whole motion
+        let moved = |time: f32| {
+            self.hittable.bounding_box(t0, t1).map(|mut aabb| {
+                aabb.min += self.offset(time);
+                aabb.max += self.offset(time);
+                aabb
+            })
+        };
+
+        match (moved(t0), moved(t1)) {
+            (Some(box0), Some(box1)) => Some(Aabb::surrounding_box(box0, box1)),
+            _ => None,
+        }
+    }
+}
+
+impl Translate {
+    pub fn new(hittable: Box<dyn Hittable>, offset: Vector) -> Self {
+        Self::new_moving(hittable, offset, offset, 0.0, 0.0)
+    }
+
+    /// Creates a Translate object whose offset moves linearly from
+    /// `offset0` to `offset1` over the time interval [t0, t1]
+    pub fn new_moving(
+        hittable: Box<dyn Hittable>,
+        offset0: Vector,
+        offset1: Vector,
+        t0: f32,
+        t1: f32,
+    ) -> Self {
+        Self {
+            hittable,
+            offset0,
+            offset1,
+            t0,
+            t1,
+        }
+    }
+
+    fn offset(&self, time: f32) -> Vector {
+        if self.t1 <= self.t0 {
+            return self.offset0;
+        }
+
+        let s = ((time - self.t0) / (self.t1 - self.t0)).max(0.0).min(1.0);
+        self.offset0 + s * (self.offset1 - self.offset0)
     }
 }
@@ -0,0 +1,141 @@
+use super::{HitRecord, Hittable};
+use crate::{aabb::Aabb, materials::Material, Ray, Vector};
+
+use rand::prelude::*;
+use std::f32;
+
+/// Padding for bounding boxes of triangles lying in an axis aligned plane
+const BOX_PADDING: f32 = 0.0001;
+
+#[derive(Debug)]
+pub struct Triangle {
+    vertices: [Vector; 3],
+    /// The geometric normal, which follows the right hand rule for the
+    /// vertices in the order they're given
+    normal: Vector,
+    area: f32,
+    material: Box<dyn Material>,
+}
+
+impl Hittable for Triangle {
+    fn hit(
+        &self,
+        r: Ray,
+        t_min: f32,
+        t_max: f32,
+    ) -> Option<(HitRecord, &Box<dyn Material>)> {
+        let [p0, p1, p2] = self.vertices;
+        let (t, b1, b2) = intersect_triangle(r, p0, p1, p2, t_min, t_max)?;
+
+        Some((
+            HitRecord::new(r, t, (b1, b2), self.normal, (p1 - p0, p2 - p0)),
+            &self.material,
+        ))
+    }
+
+    fn bounding_box(&self, _t0: f32, _t1: f32) -> Option<Aabb> {
+        Some(triangle_box(self.vertices))
+    }
+
+    fn pdf_value(&self, o: Vector, v: Vector) -> f32 {
+        match self.hit(Ray::new(o, v, 0.0), 0.001, f32::MAX) {
+            Some((hit_record, _)) => {
+                let dist_squared =
+                    hit_record.t * hit_record.t * v.length_squared();
+                let cosine =
+                    f32::abs(Vector::dot(v, hit_record.geometric_normal))
+                        / v.length();
+
+                dist_squared / (cosine * self.area)
+            }
+            None => 0.0,
+        }
+    }
+
+    fn random(&self, o: Vector) -> Vector {
+        let [p0, p1, p2] = self.vertices;
+
+        // Uniformly distributed barycentric coordinates
+        let s = f32::sqrt(random::<f32>());
+        let t = random::<f32>();
+        let random_point = (1.0 - s) * p0 + s * (1.0 - t) * p1 + s * t * p2;
+
+        random_point - o
+    }
+}
+
+impl Triangle {
+    pub fn new(vertices: [Vector; 3], material: Box<dyn Material>) -> Self {
+        let [p0, p1, p2] = vertices;
+        let cross = Vector::cross(p1 - p0, p2 - p0);
+        let length = cross.length();
+
+        Self {
+            vertices,
+            normal: cross / length,
+            area: 0.5 * length,
+            material,
+        }
+    }
+}
+
+/// Intersects a ray with a triangle using the Möller–Trumbore algorithm.
+/// Both sides of the triangle can be hit.
+///
+/// Returns the ray parameter and the barycentric coordinates of the hit
+/// point with respect to `p1` and `p2`.
+pub fn intersect_triangle(
+    r: Ray,
+    p0: Vector,
+    p1: Vector,
+    p2: Vector,
+    t_min: f32,
+    t_max: f32,
+) -> Option<(f32, f32, f32)> {
+    let e1 = p1 - p0;
+    let e2 = p2 - p0;
+
+    let p = Vector::cross(r.dir(), e2);
+    let det = Vector::dot(e1, p);
+
+    // The ray is parallel to the triangle
+    if det.abs() < 1e-12 {
+        return None;
+    }
+
+    let inv_det = 1.0 / det;
+    let s = r.origin() - p0;
+    let b1 = Vector::dot(s, p) * inv_det;
+    if b1 < 0.0 || b1 > 1.0 {
+        return None;
+    }
+
+    let q = Vector::cross(s, e1);
+    let b2 = Vector::dot(r.dir(), q) * inv_det;
+    if b2 < 0.0 || b1 + b2 > 1.0 {
+        return None;
+    }
+
+    let t = Vector::dot(e2, q) * inv_det;
+    if t < t_min || t > t_max {
+        return None;
+    }
+
+    Some((t, b1, b2))
+}
+
+/// Returns the bounding box of a triangle, padded so that it has some
+/// thickness along every axis
+pub fn triangle_box(vertices: [Vector; 3]) -> Aabb {
+    let mut min = vertices[0];
+    let mut max = vertices[0];
+
+    for p in &vertices[1..] {
+        for a in 0..3 {
+            min[a] = f32::min(min[a], p[a]);
+            max[a] = f32::max(max[a], p[a]);
+        }
+    }
+
+    Aabb::new(min + -BOX_PADDING, max + BOX_PADDING)
+}
@@ -0,0 +1,62 @@
+use super::Pdf;
+use crate::{onb::Onb, Vector};
+
+use rand::prelude::*;
+use std::f32::consts::PI;
+
+/// Picks directions according to the Henyey-Greenstein phase function,
+/// which scatters mostly forwards when `g` is positive and mostly
+/// backwards when it's negative
+#[derive(Debug)]
+pub struct HenyeyGreensteinPdf {
+    /// The direction the light was travelling in
+    uvw: Onb,
+    g: f32,
+}
+
+impl Pdf for HenyeyGreensteinPdf {
+    fn value(&self, dir: Vector) -> f32 {
+        henyey_greenstein(
+            Vector::dot(dir.normalized_exact(), self.uvw.w()),
+            self.g,
+        )
+    }
+
+    fn generate(&self) -> Vector {
+        let g = self.g;
+        let r1 = random::<f32>();
+        let r2 = random::<f32>();
+
+        let cos_theta = if g.abs() < 1e-3 {
+            1.0 - 2.0 * r1
+        } else {
+            let s = (1.0 - g * g) / (1.0 - g + 2.0 * g * r1);
+            (1.0 + g * g - s * s) / (2.0 * g)
+        };
+        let cos_theta = cos_theta.max(-1.0).min(1.0);
+        let sin_theta = f32::sqrt(1.0 - cos_theta * cos_theta);
+        let phi = 2.0 * PI * r2;
+
+        self.uvw.local(Vector::new(
+            sin_theta * phi.cos(),
+            sin_theta * phi.sin(),
+            cos_theta,
+        ))
+    }
+}
+
+impl HenyeyGreensteinPdf {
+    pub fn new(dir: Vector, g: f32) -> Self {
+        Self {
+            uvw: Onb::build_from_w(dir),
+            g,
+        }
+    }
+}
+
+/// The Henyey-Greenstein phase function for the cosine of the angle
+/// between the incoming and scattered directions
+pub fn henyey_greenstein(cos_theta: f32, g: f32) -> f32 {
+    let denom = 1.0 + g * g - 2.0 * g * cos_theta;
+    (1.0 - g * g) / (4.0 * PI * denom * denom.sqrt())
+}
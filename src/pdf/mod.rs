@@ -5,12 +5,18 @@ use std::fmt::Debug;
 mod cosine;
 pub use cosine::*;
 
+mod henyey_greenstein;
+pub use henyey_greenstein::*;
+
 mod hittable;
 pub use hittable::*;
 
 mod mixture;
 pub use mixture::*;
 
+mod uniform;
+pub use uniform::*;
+
 pub trait Pdf: Debug + Send + Sync {
     fn value(&self, dir: Vector) -> f32;
     fn generate(&self) -> Vector;
@@ -0,0 +1,17 @@
+use super::Pdf;
+use crate::{util::random_on_unit_sphere, Vector};
+use std::f32::consts::PI;
+
+/// Picks directions uniformly over the whole sphere
+#[derive(Debug)]
+pub struct UniformSphere;
+
+impl Pdf for UniformSphere {
+    fn value(&self, _dir: Vector) -> f32 {
+        1.0 / (4.0 * PI)
+    }
+
+    fn generate(&self) -> Vector {
+        random_on_unit_sphere()
+    }
+}
@@ -0,0 +1,222 @@
+//! A small rigid body simulation for animated scenes. Spheres fall under
+//! gravity and bounce off of infinite planes and each other, losing energy
+//! through restitution and friction. The simulation is run once up front
+//! and the resulting trajectories are sampled when each frame is built,
+//! which keeps the physics independent of the frame rate.
+//!
+//! Only linear motion is simulated, spheres don't spin.
+use crate::Vector;
+
+/// Bouncing velocities slower than this (relative to the velocity gained
+/// from gravity in a single step) are treated as resting contact, which
+/// stops spheres from jittering on the ground forever
+const RESTING_THRESHOLD: f32 = 2.0;
+
+/// The largest portion of the tangential velocity that friction can remove
+/// in a single contact. A solid sphere that starts rolling without slipping
+/// keeps 5/7 of its velocity, and since spin isn't simulated this stops a
+/// hard bounce from bringing a sphere to a dead stop.
+const MAX_FRICTION_LOSS: f32 = 2.0 / 7.0;
+
+#[derive(Debug, Copy, Clone)]
+pub struct Body {
+    pub center: Vector,
+    pub velocity: Vector,
+    pub radius: f32,
+    pub mass: f32,
+    pub restitution: f32,
+    pub friction: f32,
+}
+
+/// An infinite, immovable plane for bodies to collide with
+#[derive(Debug, Copy, Clone)]
+pub struct Plane {
+    pub point: Vector,
+    pub normal: Vector,
+    pub restitution: f32,
+    pub friction: f32,
+}
+
+#[derive(Debug)]
+pub struct World {
+    pub gravity: Vector,
+    pub bodies: Vec<Body>,
+    pub planes: Vec<Plane>,
+}
+
+/// The recorded positions of each body, one sample per time step
+#[derive(Debug)]
+pub struct Trajectories {
+    start: f32,
+    dt: f32,
+    positions: Vec<Vec<Vector>>,
+}
+
+impl Trajectories {
+    /// Returns the center of the given body at the given time. Times
+    /// outside of the simulated range hold the first or last position.
+    pub fn position(&self, body: usize, time: f32) -> Vector {
+        let samples = &self.positions[body];
+        let s = ((time - self.start) / self.dt).max(0.0);
+        let i = s as usize;
+
+        if i + 1 >= samples.len() {
+            return samples[samples.len() - 1];
+        }
+
+        let frac = s - i as f32;
+        samples[i] + (samples[i + 1] - samples[i]) * frac
+    }
+}
+
+impl World {
+    /// Runs the simulation from `start` for `duration` seconds using
+    /// fixed time steps of `dt` seconds
+    pub fn simulate(
+        mut self,
+        start: f32,
+        duration: f32,
+        dt: f32,
+    ) -> Trajectories {
+        let steps = (duration / dt).ceil() as usize;
+        let mut positions: Vec<Vec<Vector>> = self
+            .bodies
+            .iter()
+            .map(|b| {
+                let mut samples = Vec::with_capacity(steps + 1);
+                samples.push(b.center);
+                samples
+            })
+            .collect();
+
+        let resting_speed = RESTING_THRESHOLD * self.gravity.length() * dt;
+
+        for _ in 0..steps {
+            self.step(dt, resting_speed);
+
+            for (samples, body) in positions.iter_mut().zip(&self.bodies) {
+                samples.push(body.center);
+            }
+        }
+
+        Trajectories {
+            start,
+            dt,
+            positions,
+        }
+    }
+
+    fn step(&mut self, dt: f32, resting_speed: f32) {
+        // Semi-implicit Euler integration
+        for body in &mut self.bodies {
+            body.velocity += self.gravity * dt;
+            body.center += body.velocity * dt;
+        }
+
+        for body in &mut self.bodies {
+            for plane in &self.planes {
+                collide_plane(body, plane, resting_speed);
+            }
+        }
+
+        for i in 0..self.bodies.len() {
+            let (left, right) = self.bodies.split_at_mut(i + 1);
+            let a = &mut left[i];
+
+            for b in right {
+                collide_bodies(a, b, resting_speed);
+            }
+        }
+    }
+}
+
+/// Removes the normal component of the relative velocity `v` (scaled by
+/// restitution) and applies Coulomb friction to the tangential part
+/// (limited by [`MAX_FRICTION_LOSS`]).
+///
+/// Returns the change in velocity that should be applied.
+///
+/// [`MAX_FRICTION_LOSS`]: constant.MAX_FRICTION_LOSS.html
+fn contact_impulse(
+    v: Vector,
+    normal: Vector,
+    restitution: f32,
+    friction: f32,
+    resting_speed: f32,
+) -> Vector {
+    let vn = Vector::dot(v, normal);
+    let restitution = if -vn < resting_speed {
+        0.0
+    } else {
+        restitution
+    };
+
+    let jn = -(1.0 + restitution) * vn;
+    let vt = v - vn * normal;
+    let vt_len = vt.length();
+
+    let mut dv = jn * normal;
+    if vt_len > 0.0 {
+        let loss = f32::min(friction * jn, MAX_FRICTION_LOSS * vt_len);
+        dv += -loss * (vt / vt_len);
+    }
+
+    dv
+}
+
+fn collide_plane(body: &mut Body, plane: &Plane, resting_speed: f32) {
+    let dist = Vector::dot(body.center - plane.point, plane.normal);
+    let penetration = body.radius - dist;
+
+    if penetration <= 0.0 {
+        return;
+    }
+
+    body.center += penetration * plane.normal;
+
+    if Vector::dot(body.velocity, plane.normal) < 0.0 {
+        body.velocity += contact_impulse(
+            body.velocity,
+            plane.normal,
+            0.5 * (body.restitution + plane.restitution),
+            0.5 * (body.friction + plane.friction),
+            resting_speed,
+        );
+    }
+}
+
+fn collide_bodies(a: &mut Body, b: &mut Body, resting_speed: f32) {
+    let offset = b.center - a.center;
+    let dist = offset.length();
+    let penetration = a.radius + b.radius - dist;
+
+    if penetration <= 0.0 || dist == 0.0 {
+        return;
+    }
+
+    let normal = offset / dist;
+    let inv_a = 1.0 / a.mass;
+    let inv_b = 1.0 / b.mass;
+    let inv_sum = inv_a + inv_b;
+
+    // Push the bodies apart in proportion to their inverse masses
+    a.center += -(penetration * inv_a / inv_sum) * normal;
+    b.center += (penetration * inv_b / inv_sum) * normal;
+
+    let relative = b.velocity - a.velocity;
+    if Vector::dot(relative, normal) >= 0.0 {
+        return;
+    }
+
+    // The change in relative velocity is shared between the two bodies
+    let dv = contact_impulse(
+        relative,
+        normal,
+        0.5 * (a.restitution + b.restitution),
+        0.5 * (a.friction + b.friction),
+        resting_speed,
+    );
+
+    a.velocity += -(inv_a / inv_sum) * dv;
+    b.velocity += (inv_b / inv_sum) * dv;
+}
@@ -0,0 +1,153 @@
+//! Progress reporting for the renderer. By default a human readable
+//! progress bar is drawn on stdout, but a machine readable mode is also
+//! available which emits one JSON object per line (to stderr or a file)
+//! so that job runners can follow a render without scraping the bar.
+use crate::util::progress_bar;
+
+use serde::Serialize;
+
+use std::fs::File;
+use std::io::{stderr, LineWriter, Write};
+use std::time;
+
+const PROG_BAR_WIDTH: usize = 80;
+
+/// Where (and how) progress should be reported
+#[derive(Debug, Clone)]
+pub enum ProgressMode {
+    /// The interactive progress bar on stdout
+    Human,
+    /// JSON lines written to the given file, or stderr if there is none
+    Json(Option<String>),
+}
+
+/// A single progress event. In JSON mode each event is serialized
+/// on its own line with an `event` tag, e.g.
+/// `{"event":"row_completed","row":10,"rows":300,...}`
+#[derive(Debug, Serialize)]
+#[serde(tag = "event", rename_all = "snake_case")]
+pub enum Event<'a> {
+    SceneLoaded {
+        scene: &'a str,
+        width: usize,
+        height: usize,
+        samples: usize,
+        load_secs: f32,
+    },
+    FrameStarted {
+        frame: usize,
+        first: usize,
+        last: usize,
+    },
+    BvhBuilt {
+        count: usize,
+        build_secs: f32,
+    },
+    RowCompleted {
+        row: usize,
+        rows: usize,
+        spp: usize,
+        elapsed_secs: f32,
+        eta_secs: f32,
+        rays_per_sec: f32,
+    },
+    RenderFinished {
+        elapsed_secs: f32,
+    },
+    ImageWritten {
+        path: &'a str,
+        elapsed_secs: f32,
+    },
+    Error {
+        message: &'a str,
+    },
+}
+
+pub struct Reporter {
+    out: Option<Box<dyn Write + Send>>,
+    start: time::Instant,
+}
+
+impl Reporter {
+    pub fn new(mode: &ProgressMode) -> Result<Self, String> {
+        let out: Option<Box<dyn Write + Send>> = match mode {
+            ProgressMode::Human => None,
+            ProgressMode::Json(None) => Some(Box::new(stderr())),
+            ProgressMode::Json(Some(path)) => {
+                let file = File::create(path).map_err(|e| {
+                    format!("Couldn't create progress file {}: {}", path, e)
+                })?;
+                Some(Box::new(LineWriter::new(file)))
+            }
+        };
+
+        Ok(Self {
+            out,
+            start: time::Instant::now(),
+        })
+    }
+
+    /// Time elapsed since the reporter was created, in seconds
+    pub fn elapsed_secs(&self) -> f32 {
+        self.start.elapsed().as_secs_f32()
+    }
+
+    /// Reports the given event, either as a JSON line or as the
+    /// equivalent human readable output
+    pub fn emit(&mut self, event: Event) {
+        match &mut self.out {
+            Some(out) => {
+                // Progress reporting should never bring down a render,
+                // so failures to write are deliberately ignored here
+                if let Ok(line) = serde_json::to_string(&event) {
+                    let _ = writeln!(out, "{}", line);
+                    let _ = out.flush();
+                }
+            }
+            None => Self::emit_human(event),
+        }
+    }
+
+    fn emit_human(event: Event) {
+        match event {
+            Event::SceneLoaded {
+                scene,
+                width,
+                height,
+                samples,
+                ..
+            } => {
+                println!(
+                    "Scene loaded from {}, rendering {} x {} @ {} samples ({} rays)",
+                    scene,
+                    width,
+                    height,
+                    samples,
+                    height * width * samples,
+                );
+            }
+            Event::FrameStarted { frame, first, last } => {
+                println!(
+                    "Rendering frame {} ({} of {})",
+                    frame,
+                    frame - first + 1,
+                    last - first + 1
+                );
+            }
+            Event::RowCompleted { row, rows, .. } => {
+                progress_bar(row, rows, PROG_BAR_WIDTH, "Rendering");
+            }
+            Event::RenderFinished { elapsed_secs } => {
+                println!(
+                    "\nCompleted rendering in {:#?}",
+                    time::Duration::from_secs_f32(elapsed_secs)
+                );
+            }
+            // Errors are printed by main() when it returns, and the
+            // remaining events don't have a human readable equivalent
+            Event::BvhBuilt { .. }
+            | Event::ImageWritten { .. }
+            | Event::Error { .. } => {}
+        }
+    }
+}
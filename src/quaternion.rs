@@ -0,0 +1,191 @@
+//! A minimal quaternion implementation for representing and smoothly
+//! interpolating rotations.
+use crate::{matrix::Matrix4, Vector};
+
+use std::f32;
+
+/// A rotation quaternion with scalar part `w` and vector part `v`
+#[derive(Debug, Copy, Clone)]
+pub struct Quaternion {
+    pub w: f32,
+    pub v: Vector,
+}
+
+impl Quaternion {
+    /// Creates a quaternion representing a rotation of `angle` degrees
+    /// about the given axis
+    pub fn from_axis_angle(axis: Vector, angle: f32) -> Self {
+        let half = 0.5 * angle * f32::consts::PI / 180.0;
+        Self {
+            w: f32::cos(half),
+            v: f32::sin(half) * axis.normalized_exact(),
+        }
+    }
+
+    /// Creates a quaternion with the same rotation as the upper left 3x3
+    /// part of the given matrix, which must be a rotation
+    pub fn from_matrix(matrix: &Matrix4) -> Self {
+        let m = &matrix.m;
+        let trace = m[0][0] + m[1][1] + m[2][2];
+
+        // The square root is taken of the largest of the four candidates
+        // for |w|, |x|, |y| and |z| to keep the division below accurate
+        let (w, x, y, z) = if trace > 0.0 {
+            let s = 2.0 * f32::sqrt(1.0 + trace);
+            (
+                0.25 * s,
+                (m[2][1] - m[1][2]) / s,
+                (m[0][2] - m[2][0]) / s,
+                (m[1][0] - m[0][1]) / s,
+            )
+        } else if m[0][0] > m[1][1] && m[0][0] > m[2][2] {
+            let s = 2.0 * f32::sqrt(1.0 + m[0][0] - m[1][1] - m[2][2]);
+            (
+                (m[2][1] - m[1][2]) / s,
+                0.25 * s,
+                (m[0][1] + m[1][0]) / s,
+                (m[0][2] + m[2][0]) / s,
+            )
+        } else if m[1][1] > m[2][2] {
+            let s = 2.0 * f32::sqrt(1.0 + m[1][1] - m[0][0] - m[2][2]);
+            (
+                (m[0][2] - m[2][0]) / s,
+                (m[0][1] + m[1][0]) / s,
+                0.25 * s,
+                (m[1][2] + m[2][1]) / s,
+            )
+        } else {
+            let s = 2.0 * f32::sqrt(1.0 + m[2][2] - m[0][0] - m[1][1]);
+            (
+                (m[1][0] - m[0][1]) / s,
+                (m[0][2] + m[2][0]) / s,
+                (m[1][2] + m[2][1]) / s,
+                0.25 * s,
+            )
+        };
+
+        Self {
+            w,
+            v: Vector::new(x, y, z),
+        }
+        .normalize()
+    }
+
+    /// Returns the rotation as a matrix
+    pub fn matrix(self) -> Matrix4 {
+        // The columns of a rotation matrix are the rotated basis vectors
+        let mut ret = Matrix4::identity();
+        for c in 0..3 {
+            let mut basis = Vector::zeros();
+            basis[c] = 1.0;
+
+            let column = self.rotate(basis);
+            for r in 0..3 {
+                ret.m[r][c] = column[r];
+            }
+        }
+
+        ret
+    }
+
+    /// Rotates the given vector by this (unit) quaternion
+    pub fn rotate(self, p: Vector) -> Vector {
+        let t = 2.0 * Vector::cross(self.v, p);
+        p + self.w * t + Vector::cross(self.v, t)
+    }
+
+    pub fn dot(a: Self, b: Self) -> f32 {
+        a.w * b.w + Vector::dot(a.v, b.v)
+    }
+
+    /// Returns a quaternion with the same rotation and length 1
+    pub fn normalize(self) -> Self {
+        let len = f32::sqrt(Self::dot(self, self));
+        Self {
+            w: self.w / len,
+            v: self.v / len,
+        }
+    }
+
+    /// Spherical linear interpolation between two rotations. This always
+    /// takes the shortest path between them.
+    pub fn slerp(a: Self, b: Self, t: f32) -> Self {
+        let mut b = b;
+        let mut cos_theta = Self::dot(a, b);
+
+        // q and -q are the same rotation, pick the one that's closer
+        if cos_theta < 0.0 {
+            b = Self { w: -b.w, v: -b.v };
+            cos_theta = -cos_theta;
+        }
+
+        // Nearly identical rotations would divide by ~0 below, so fall
+        // back to normalized linear interpolation
+        let (s0, s1) = if cos_theta > 0.9995 {
+            (1.0 - t, t)
+        } else {
+            let theta = f32::acos(cos_theta);
+            let sin_theta = f32::sin(theta);
+            (
+                f32::sin((1.0 - t) * theta) / sin_theta,
+                f32::sin(t * theta) / sin_theta,
+            )
+        };
+
+        Self {
+            w: s0 * a.w + s1 * b.w,
+            v: s0 * a.v + s1 * b.v,
+        }
+        .normalize()
+    }
+
+    /// The angle in radians of the smallest rotation between two rotations
+    pub fn angle_between(a: Self, b: Self) -> f32 {
+        2.0 * f32::acos(Self::dot(a, b).abs().min(1.0))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Rotations about each axis by more than 90 degrees take each branch
+    /// of `from_matrix`, and each should survive the round trip
+    #[test]
+    fn matrix_round_trip() {
+        let axes = [
+            Vector::new(1.0, 0.0, 0.0),
+            Vector::new(0.0, 1.0, 0.0),
+            Vector::new(0.0, 0.0, 1.0),
+            Vector::new(1.0, 2.0, 3.0),
+        ];
+
+        for &axis in &axes {
+            for &angle in &[30.0, 170.0] {
+                let q = Quaternion::from_axis_angle(axis, angle);
+                let m = Matrix4::rotation(axis, angle);
+                let p = Vector::new(0.3, -1.2, 2.5);
+
+                let expected = m.transform_vector(p);
+                for &rotated in &[
+                    q.rotate(p),
+                    q.matrix().transform_vector(p),
+                    Quaternion::from_matrix(&m).rotate(p),
+                ] {
+                    assert!((rotated - expected).length() < 1e-4);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn slerp_halfway() {
+        let axis = Vector::new(0.0, 1.0, 0.0);
+        let a = Quaternion::from_axis_angle(axis, 20.0);
+        let b = Quaternion::from_axis_angle(axis, 120.0);
+        let halfway = Quaternion::slerp(a, b, 0.5);
+
+        let expected = Quaternion::from_axis_angle(axis, 70.0);
+        assert!(Quaternion::angle_between(halfway, expected) < 1e-3);
+    }
+}
@@ -0,0 +1,177 @@
+//! Splines through 3D space, used for camera fly-throughs. Splines are
+//! reparameterized by arc length so that moving along them at a constant
+//! rate gives a constant speed, regardless of how the control points are
+//! spaced.
+use crate::Vector;
+
+use serde::{Deserialize, Serialize};
+
+/// The number of samples per segment used to build the arc length table
+const ARC_LENGTH_SAMPLES: usize = 64;
+
+#[derive(Debug, Copy, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SplineKind {
+    /// A uniform Catmull-Rom spline which passes through every point
+    CatmullRom,
+    /// Piecewise cubic Bezier curves. The points are laid out as
+    /// `[p0, c0, c1, p1, c2, c3, p2, ...]`, so there must be `3n + 1`.
+    Bezier,
+}
+
+#[derive(Debug)]
+pub struct Spline {
+    kind: SplineKind,
+    points: Vec<Vector>,
+    /// Cumulative arc length at evenly spaced values of the curve parameter
+    lengths: Vec<f32>,
+}
+
+impl Spline {
+    pub fn new(kind: SplineKind, points: Vec<Vector>) -> Result<Self, String> {
+        let valid = match kind {
+            SplineKind::CatmullRom => points.len() >= 2,
+            SplineKind::Bezier => points.len() >= 4 && points.len() % 3 == 1,
+        };
+
+        if !valid {
+            return Err(format!(
+                "Invalid number of control points ({}) for {:?} spline",
+                points.len(),
+                kind
+            ));
+        }
+
+        // The tangent is found from points on either side of the curve
+        // parameter, so the curve must move wherever it's sampled. Bezier
+        // control points may sit on the end points, but a whole segment
+        // can't be a single point.
+        let same = |a: Vector, b: Vector| (a - b).length_squared() == 0.0;
+        let stationary = match kind {
+            SplineKind::CatmullRom => {
+                points.windows(2).any(|w| same(w[0], w[1]))
+            }
+            SplineKind::Bezier => points
+                .windows(4)
+                .step_by(3)
+                .any(|w| w.iter().all(|&p| same(p, w[0]))),
+        };
+
+        if stationary {
+            return Err(format!(
+                "{:?} spline has a segment with no length",
+                kind
+            ));
+        }
+
+        let mut spline = Self {
+            kind,
+            points,
+            lengths: Vec::new(),
+        };
+
+        let samples = spline.segments() * ARC_LENGTH_SAMPLES;
+        let mut lengths = Vec::with_capacity(samples + 1);
+        let mut total = 0.0;
+        let mut prev = spline.eval(0.0);
+        lengths.push(0.0);
+
+        for i in 1..=samples {
+            let p = spline.eval(i as f32 / samples as f32);
+            total += (p - prev).length();
+            lengths.push(total);
+            prev = p;
+        }
+
+        spline.lengths = lengths;
+        Ok(spline)
+    }
+
+    pub fn length(&self) -> f32 {
+        self.lengths[self.lengths.len() - 1]
+    }
+
+    /// Returns the point at the given fraction (between 0 and 1) of the
+    /// spline's total arc length
+    pub fn point(&self, fraction: f32) -> Vector {
+        self.eval(self.param_at(fraction))
+    }
+
+    /// Returns the direction of travel at the given fraction of the
+    /// spline's total arc length
+    pub fn tangent(&self, fraction: f32) -> Vector {
+        let eps = 1.0 / self.lengths.len() as f32;
+        let u = self.param_at(fraction);
+        let (u0, u1) = (f32::max(u - eps, 0.0), f32::min(u + eps, 1.0));
+
+        (self.eval(u1) - self.eval(u0)).normalize()
+    }
+
+    fn segments(&self) -> usize {
+        match self.kind {
+            SplineKind::CatmullRom => self.points.len() - 1,
+            SplineKind::Bezier => (self.points.len() - 1) / 3,
+        }
+    }
+
+    /// Maps a fraction of the arc length onto the curve parameter using
+    /// the arc length table
+    fn param_at(&self, fraction: f32) -> f32 {
+        let target = fraction.max(0.0).min(1.0) * self.length();
+        let n = self.lengths.len() - 1;
+
+        // Find the first sample at or past the target length
+        let i = match self
+            .lengths
+            .binary_search_by(|l| l.partial_cmp(&target).unwrap())
+        {
+            Ok(i) => return i as f32 / n as f32,
+            Err(i) => i.max(1).min(n),
+        };
+
+        let (l0, l1) = (self.lengths[i - 1], self.lengths[i]);
+        let frac = if l1 > l0 {
+            (target - l0) / (l1 - l0)
+        } else {
+            0.0
+        };
+
+        (i as f32 - 1.0 + frac) / n as f32
+    }
+
+    /// Evaluates the curve at the parameter `u` in [0, 1], where each
+    /// segment covers an equal range of the parameter
+    fn eval(&self, u: f32) -> Vector {
+        let segments = self.segments();
+        let s = u.max(0.0).min(1.0) * segments as f32;
+        let seg = (s as usize).min(segments - 1);
+        let t = s - seg as f32;
+
+        match self.kind {
+            SplineKind::CatmullRom => {
+                let last = self.points.len() - 1;
+                let p0 = self.points[seg.saturating_sub(1)];
+                let p1 = self.points[seg];
+                let p2 = self.points[seg + 1];
+                let p3 = self.points[(seg + 2).min(last)];
+
+                let t2 = t * t;
+                let t3 = t2 * t;
+
+                0.5 * (2.0 * p1
+                    + (p2 - p0) * t
+                    + (2.0 * p0 - 5.0 * p1 + 4.0 * p2 - p3) * t2
+                    + (3.0 * p1 - p0 - 3.0 * p2 + p3) * t3)
+            }
+            SplineKind::Bezier => {
+                let p = &self.points[seg * 3..seg * 3 + 4];
+                let inv = 1.0 - t;
+
+                inv * inv * inv * p[0]
+                    + 3.0 * inv * inv * t * p[1]
+                    + 3.0 * inv * t * t * p[2]
+                    + t * t * t * p[3]
+            }
+        }
+    }
+}
@@ -0,0 +1,129 @@
+use crate::Vector;
+
+use std::sync::Arc;
+
+use super::Texture;
+
+/// The hottest temperature in the lookup table, in kelvin. Hotter
+/// temperatures use the color at this temperature.
+const MAX_TEMPERATURE: f32 = 12000.0;
+const TABLE_STEP: f32 = 50.0;
+
+/// The temperature which has a luminance of 1 before scaling
+const REFERENCE_TEMPERATURE: f64 = 6500.0;
+
+/// The color of light given off by a black body at a temperature, either a
+/// fixed one or the temperature of the point being shaded, which volumes
+/// with a temperature grid provide.
+///
+/// Brightness follows Planck's law, so it rises steeply with temperature.
+/// A body at 6500 K has a luminance equal to the texture's scale.
+#[derive(Debug, Clone)]
+pub struct Blackbody {
+    temperature: Option<f32>,
+    scale: f32,
+    /// Linear RGB colors at every multiple of `TABLE_STEP` up to
+    /// `MAX_TEMPERATURE`
+    table: Arc<Vec<Vector>>,
+}
+
+impl Texture for Blackbody {
+    /// Without a temperature of its own or of the point, the texture is as
+    /// dark as a black body at absolute zero
+    fn value(&self, u: f32, v: f32, p: Vector) -> Vector {
+        self.value_at_temperature(u, v, p, 0.0)
+    }
+
+    fn value_at_temperature(
+        &self,
+        _u: f32,
+        _v: f32,
+        _p: Vector,
+        temperature: f32,
+    ) -> Vector {
+        let temperature = self.temperature.unwrap_or(temperature);
+        let x = temperature.max(0.0).min(MAX_TEMPERATURE) / TABLE_STEP;
+        let i = usize::min(x as usize, self.table.len() - 2);
+        let f = x - i as f32;
+
+        self.scale * ((1.0 - f) * self.table[i] + f * self.table[i + 1])
+    }
+}
+
+impl Blackbody {
+    pub fn new(temperature: Option<f32>, scale: f32) -> Self {
+        let reference = planck_xyz(REFERENCE_TEMPERATURE)[1];
+        let steps = (MAX_TEMPERATURE / TABLE_STEP) as usize;
+
+        let table = (0..=steps)
+            .map(|i| {
+                let [x, y, z] = planck_xyz(i as f64 * TABLE_STEP as f64);
+                let (x, y, z) = (x / reference, y / reference, z / reference);
+
+                // CIE XYZ to linear sRGB. Cool black bodies are redder than
+                // any sRGB color, so negative components are clamped.
+                let rgb = [
+                    3.2406 * x - 1.5372 * y - 0.4986 * z,
+                    -0.9689 * x + 1.8758 * y + 0.0415 * z,
+                    0.0557 * x - 0.2040 * y + 1.0570 * z,
+                ];
+                Vector::new(
+                    rgb[0].max(0.0) as f32,
+                    rgb[1].max(0.0) as f32,
+                    rgb[2].max(0.0) as f32,
+                )
+            })
+            .collect();
+
+        Self {
+            temperature,
+            scale,
+            table: Arc::new(table),
+        }
+    }
+}
+
+/// Integrates the spectrum of a black body against the CIE color matching
+/// functions, in arbitrary units
+fn planck_xyz(temperature: f64) -> [f64; 3] {
+    const H: f64 = 6.626_070_15e-34;
+    const C: f64 = 2.997_924_58e8;
+    const K: f64 = 1.380_649e-23;
+
+    let mut xyz = [0.0; 3];
+    if temperature <= 0.0 {
+        return xyz;
+    }
+
+    for nm in (380..=780).step_by(5) {
+        let lambda = nm as f64 * 1e-9;
+        let radiance = 2.0 * H * C * C
+            / lambda.powi(5)
+            / ((H * C / (lambda * K * temperature)).exp() - 1.0);
+
+        let matching = color_matching(nm as f64);
+        for c in 0..3 {
+            xyz[c] += radiance * matching[c];
+        }
+    }
+
+    xyz
+}
+
+/// The CIE 1931 color matching functions, using the multi-lobe fit from
+/// Wyman, Sloan and Shirley's "Simple Analytic Approximations to the CIE
+/// XYZ Color Matching Functions"
+fn color_matching(nm: f64) -> [f64; 3] {
+    let lobe = |mean: f64, below: f64, above: f64| {
+        let spread = if nm < mean { below } else { above };
+        let t = (nm - mean) / spread;
+        (-0.5 * t * t).exp()
+    };
+
+    [
+        1.056 * lobe(599.8, 37.9, 31.0) + 0.362 * lobe(442.0, 16.0, 26.7)
+            - 0.065 * lobe(501.1, 20.4, 26.2),
+        0.821 * lobe(568.8, 46.9, 40.5) + 0.286 * lobe(530.9, 16.3, 31.1),
+        1.217 * lobe(437.0, 11.8, 36.0) + 0.681 * lobe(459.0, 26.0, 13.8),
+    ]
+}
@@ -0,0 +1,56 @@
+use crate::Vector;
+
+use std::sync::Arc;
+
+use super::Texture;
+
+/// A texture looked up from an image, which repeats outside of the unit
+/// square. `v` increases towards the top of the image.
+#[derive(Debug, Clone)]
+pub struct ImageTexture {
+    width: usize,
+    height: usize,
+    /// The linear colors of the pixels, stored row by row from the top.
+    /// These are shared by every texture using the same image.
+    pixels: Arc<Vec<Vector>>,
+    /// Multiplies every pixel's color
+    tint: Vector,
+}
+
+impl Texture for ImageTexture {
+    fn value(&self, u: f32, v: f32, _p: Vector) -> Vector {
+        // Bilinear filtering between the four nearest pixel centers
+        let x = (u - u.floor()) * self.width as f32 - 0.5;
+        let y = (1.0 - (v - v.floor())) * self.height as f32 - 0.5;
+        let (x0, y0) = (x.floor(), y.floor());
+        let (fx, fy) = (x - x0, y - y0);
+
+        let pixel = |x: f32, y: f32| {
+            let x = (x as isize).rem_euclid(self.width as isize) as usize;
+            let y = (y as isize).rem_euclid(self.height as isize) as usize;
+            self.pixels[y * self.width + x]
+        };
+
+        let top = (1.0 - fx) * pixel(x0, y0) + fx * pixel(x0 + 1.0, y0);
+        let bottom =
+            (1.0 - fx) * pixel(x0, y0 + 1.0) + fx * pixel(x0 + 1.0, y0 + 1.0);
+
+        self.tint * ((1.0 - fy) * top + fy * bottom)
+    }
+}
+
+impl ImageTexture {
+    pub fn new(
+        width: usize,
+        height: usize,
+        pixels: Arc<Vec<Vector>>,
+        tint: Vector,
+    ) -> Self {
+        Self {
+            width,
+            height,
+            pixels,
+            tint,
+        }
+    }
+}
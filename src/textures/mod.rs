@@ -1,8 +1,12 @@
 use crate::Vector;
 use std::fmt::Debug;
 
+mod blackbody;
 mod constant;
+mod image;
+pub use blackbody::*;
 pub use constant::*;
+pub use image::*;
 
 pub trait TextureClone {
     fn clone_box(&self) -> Box<dyn Texture>;
@@ -25,4 +29,16 @@ impl Clone for Box<dyn Texture> {
 
 pub trait Texture: Debug + Send + Sync + TextureClone {
     fn value(&self, u: f32, v: f32, p: Vector) -> Vector;
+
+    /// The texture's value at a point whose temperature in kelvin is known,
+    /// such as inside a glowing volume. Most textures ignore it.
+    fn value_at_temperature(
+        &self,
+        u: f32,
+        v: f32,
+        p: Vector,
+        _temperature: f32,
+    ) -> Vector {
+        self.value(u, v, p)
+    }
 }
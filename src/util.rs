@@ -77,6 +77,210 @@ pub fn sphere_uv(p: Vector) -> (f32, f32) {
     ((1.0 - (phi + PI) / (2.0 * PI)), ((theta + PI / 2.0) / PI))
 }
 
+/// Computes the derivatives of a point on a sphere with respect to the u
+/// and v values from [`sphere_uv`], given the point's offset from the
+/// center of the sphere
+///
+/// [`sphere_uv`]: fn.sphere_uv.html
+pub fn sphere_partials(offset: Vector) -> (Vector, Vector) {
+    // The distance from the poles' axis, which only vanishes at the poles
+    let rho = f32::max(f32::hypot(offset.x, offset.z), 1e-8);
+
+    let dpdu = 2.0 * PI * Vector::new(offset.z, 0.0, -offset.x);
+    let dpdv = PI
+        * Vector::new(
+            -offset.y * offset.x / rho,
+            rho,
+            -offset.y * offset.z / rho,
+        );
+
+    (dpdu, dpdv)
+}
+
+/// The angle of the point (x, z) around the Y axis, in [0, 2π)
+pub fn azimuth(x: f32, z: f32) -> f32 {
+    let phi = f32::atan2(z, x);
+    if phi < 0.0 {
+        phi + 2.0 * PI
+    } else {
+        phi
+    }
+}
+
+/// Finds the real roots of a quadratic in ascending order, or the single
+/// root of a linear equation (twice) if `a` is zero
+pub fn solve_quadratic(a: f32, b: f32, c: f32) -> Option<(f32, f32)> {
+    if a == 0.0 {
+        if b == 0.0 {
+            return None;
+        }
+        return Some((-c / b, -c / b));
+    }
+
+    let discriminant = b * b - 4.0 * a * c;
+    if discriminant < 0.0 {
+        return None;
+    }
+
+    // This avoids the cancellation in the textbook formula when b is
+    // close to the square root of the discriminant
+    let q = -0.5 * (b + f32::copysign(discriminant.sqrt(), b));
+    let (t0, t1) = if q == 0.0 { (0.0, 0.0) } else { (q / a, c / q) };
+
+    Some((f32::min(t0, t1), f32::max(t0, t1)))
+}
+
+/// Finds the real roots of a cubic in ascending order. Repeated roots are
+/// only returned once.
+pub fn solve_cubic(a: f64, b: f64, c: f64, d: f64) -> Vec<f64> {
+    if a == 0.0 {
+        return quadratic_roots(b, c, d);
+    }
+
+    // Substituting x = y - b / 3 gives the depressed cubic y³ + py + q
+    let (b, c, d) = (b / a, c / a, d / a);
+    let shift = b / 3.0;
+    let p = c - b * shift;
+    let q = d - c * shift + 2.0 * shift * shift * shift;
+
+    let mut roots = if p == 0.0 {
+        vec![-q.cbrt()]
+    } else if 4.0 * p * p * p + 27.0 * q * q > 0.0 {
+        // A single real root, from Cardano's formula
+        let discriminant = (q * q / 4.0 + p * p * p / 27.0).sqrt();
+        let u = (-q / 2.0 + f64::copysign(discriminant, -q)).cbrt();
+        vec![u - p / (3.0 * u)]
+    } else {
+        // Three real roots, from the trigonometric form
+        let m = 2.0 * (-p / 3.0).sqrt();
+        let cos = (3.0 * q / (p * m)).max(-1.0).min(1.0);
+        let theta = cos.acos() / 3.0;
+        (0..3)
+            .map(|k| {
+                m * (theta - 2.0 * std::f64::consts::PI * k as f64 / 3.0).cos()
+            })
+            .collect()
+    };
+
+    for root in &mut roots {
+        *root = polish_root(&[1.0, b, c, d], *root - shift);
+    }
+
+    sort_roots(roots)
+}
+
+/// Finds the real roots of a quartic in ascending order using Ferrari's
+/// method. Repeated roots are only returned once.
+///
+/// The roots are refined with Newton's method afterwards, since the
+/// closed form solution loses a lot of precision when roots are close
+/// together.
+pub fn solve_quartic(a: f64, b: f64, c: f64, d: f64, e: f64) -> Vec<f64> {
+    if a == 0.0 {
+        return solve_cubic(b, c, d, e);
+    }
+
+    // Substituting x = y - b / 4 gives the depressed quartic
+    // y⁴ + py² + qy + r
+    let (b, c, d, e) = (b / a, c / a, d / a, e / a);
+    let shift = b / 4.0;
+    let p = c - 6.0 * shift * shift;
+    let q = d - 2.0 * c * shift + 8.0 * shift * shift * shift;
+    let r = e - d * shift + c * shift * shift - 3.0 * shift.powi(4);
+
+    let mut roots = Vec::with_capacity(4);
+
+    if q.abs() <= 1e-12 * (1.0 + p.abs() + r.abs()) {
+        // A biquadratic, which is a quadratic in y²
+        for z in quadratic_roots(1.0, p, r) {
+            if z >= 0.0 {
+                roots.push(z.sqrt());
+                roots.push(-z.sqrt());
+            }
+        }
+    } else {
+        // Adding m to the square of y² + p / 2 makes the rest a perfect
+        // square when m solves this resolvent cubic. Its largest root is
+        // always positive because the cubic is negative at zero.
+        let m = solve_cubic(8.0, 8.0 * p, 2.0 * p * p - 8.0 * r, -q * q)
+            .into_iter()
+            .fold(f64::MIN, f64::max);
+
+        if m > 0.0 {
+            let s = (2.0 * m).sqrt();
+            let offset = q / (2.0 * s);
+            roots.extend(quadratic_roots(1.0, -s, p / 2.0 + m + offset));
+            roots.extend(quadratic_roots(1.0, s, p / 2.0 + m - offset));
+        }
+    }
+
+    for root in &mut roots {
+        *root = polish_root(&[1.0, b, c, d, e], *root - shift);
+    }
+
+    sort_roots(roots)
+}
+
+/// The real roots of a quadratic in double precision, without duplicates
+fn quadratic_roots(a: f64, b: f64, c: f64) -> Vec<f64> {
+    if a == 0.0 {
+        return if b == 0.0 { Vec::new() } else { vec![-c / b] };
+    }
+
+    let discriminant = b * b - 4.0 * a * c;
+    if discriminant < 0.0 {
+        Vec::new()
+    } else if discriminant == 0.0 {
+        vec![-b / (2.0 * a)]
+    } else {
+        let q = -0.5 * (b + f64::copysign(discriminant.sqrt(), b));
+        if q == 0.0 {
+            vec![0.0]
+        } else {
+            vec![q / a, c / q]
+        }
+    }
+}
+
+/// Refines a root of the polynomial with the given coefficients (highest
+/// degree first) using Newton's method, stopping once it stops improving
+fn polish_root(coeffs: &[f64], mut x: f64) -> f64 {
+    let eval = |x: f64| {
+        coeffs.iter().fold((0.0, 0.0), |(value, slope), &coeff| {
+            (value * x + coeff, slope * x + value)
+        })
+    };
+
+    let (mut value, mut slope) = eval(x);
+    for _ in 0..8 {
+        if value == 0.0 || slope == 0.0 {
+            break;
+        }
+
+        let next = x - value / slope;
+        let (next_value, next_slope) = eval(next);
+        if !(next_value.abs() < value.abs()) {
+            break;
+        }
+
+        x = next;
+        value = next_value;
+        slope = next_slope;
+    }
+
+    x
+}
+
+/// Sorts roots in ascending order, dropping any that aren't finite and
+/// merging duplicates
+fn sort_roots(mut roots: Vec<f64>) -> Vec<f64> {
+    roots.retain(|root| root.is_finite());
+    roots.sort_by(|a, b| a.partial_cmp(b).unwrap());
+    roots.dedup();
+
+    roots
+}
+
 pub fn vector_reflect(v: Vector, n: Vector) -> Vector {
     v - 2.0 * Vector::dot(v, n) * n
 }
@@ -115,3 +319,62 @@ pub fn progress_bar(
 
     curr == total
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn assert_roots(roots: Vec<f64>, expected: &[f64]) {
+        assert_eq!(roots.len(), expected.len(), "{:?}", roots);
+        for (root, expected) in roots.iter().zip(expected) {
+            assert!((root - expected).abs() < 1e-6, "{:?}", roots);
+        }
+    }
+
+    #[test]
+    fn quartic_without_real_roots() {
+        // (x² + 1)(x² + 4)
+        assert_roots(solve_quartic(1.0, 0.0, 5.0, 0.0, 4.0), &[]);
+    }
+
+    #[test]
+    fn quartic_with_two_real_roots() {
+        // (x - 1)(x + 2)(x² + 1)
+        assert_roots(solve_quartic(1.0, 1.0, -1.0, 1.0, -2.0), &[-2.0, 1.0]);
+    }
+
+    #[test]
+    fn quartic_with_four_real_roots() {
+        // (x - 1)(x - 2)(x - 3)(x - 4), scaled
+        assert_roots(
+            solve_quartic(2.0, -20.0, 70.0, -100.0, 48.0),
+            &[1.0, 2.0, 3.0, 4.0],
+        );
+    }
+
+    #[test]
+    fn quartic_with_double_root() {
+        // (x - 1)²(x - 3)(x + 2)
+        assert_roots(
+            solve_quartic(1.0, -3.0, -3.0, 11.0, -6.0),
+            &[-2.0, 1.0, 3.0],
+        );
+    }
+
+    #[test]
+    fn biquadratic() {
+        // (x² - 1)(x² - 9)
+        assert_roots(
+            solve_quartic(1.0, 0.0, -10.0, 0.0, 9.0),
+            &[-3.0, -1.0, 1.0, 3.0],
+        );
+    }
+
+    #[test]
+    fn cubic_roots() {
+        // (x - 1)(x - 2)(x - 3)
+        assert_roots(solve_cubic(1.0, -6.0, 11.0, -6.0), &[1.0, 2.0, 3.0]);
+        // (x - 2)(x² + 1)
+        assert_roots(solve_cubic(1.0, -2.0, 1.0, -2.0), &[2.0]);
+    }
+}
@@ -69,6 +69,17 @@ impl Vector {
         }
     }
 
+    /// Return a vector with the same direction but with length 1, computed
+    /// with an exact square root. [`normalize`] uses a fast approximation
+    /// whose error of up to a fraction of a percent is fine for directions
+    /// that are only sampled, but shows up in normals used for shading
+    /// and in rotation axes, where it skews the result.
+    ///
+    /// [`normalize`]: #method.normalize
+    pub fn normalized_exact(self) -> Self {
+        self / self.length()
+    }
+
     /// Returns the reciprocal of the square root of the vector's magnitude
     pub fn inv_mag(self) -> f32 {
         fast_inv_sqrt((self.x * self.x) + (self.y * self.y) + (self.z * self.z))